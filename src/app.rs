@@ -1,12 +1,32 @@
-use std::path::PathBuf;
-
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-
-use crate::event::{
-    self, Command, Movement, is_back, is_download, is_help, is_panel_switch, is_quit, is_select,
-};
-use crate::file::{FileNode, parent_entry, path_entry};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::Position;
+
+use crate::bookmark::{self, Bookmark};
+use crate::check::{CheckRun, CheckStatus, format_duration};
+use crate::compare::{Compare, CompareRow, Listing};
+use crate::config::{Config, DEFAULT_TIME_FORMAT};
+use crate::deepsearch::{DeepSearch, DeepSearchStatus};
+use crate::diff::{DiffBody, FileDiff};
+use crate::dirsize::{DirSize, DirSizeIndex};
+use crate::event::{Command, Movement};
+use crate::file::{FileNode, FileSort, SortKey, format_bytes, parent_entry, path_entry};
+use crate::forget::{ForgetDialog, ForgetGroup, ForgetPlan, ForgetPolicy};
+use crate::goto::{GoToPrompt, GoToWaiting};
+use crate::history::{History, Location};
+use crate::jobs::{DEFAULT_MAX_JOBS, Job, JobKind, JobStatus};
+use crate::keymap::{Action, Keymap};
+use crate::mouse::{ClickTracker, MouseAreas, WHEEL_LINES};
+use crate::preview::{PREVIEW_DELAY, Preview, PreviewCache};
+use crate::restic::is_not_found;
+use crate::search::{Matcher, SearchMatch, SearchMode};
 use crate::snapshot::Snapshot;
+use crate::stats::{ResticStats, StatsEntry, StatsMode, StatsStatus, StatsTarget};
+use crate::theme::Theme;
+use crate::tree::{self, TreeRow};
 
 /// Which panel is currently focused
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -15,6 +35,8 @@ pub enum Panel
     Snapshots,
     Files,
     CommandLog,
+    Jobs,       // Only shown once a job has been queued
+    Check,      // Only shown once a repository check has been started
 }
 
 /// A single command log entry
@@ -25,6 +47,7 @@ pub struct CommandLogEntry
     pub command: String,
     pub success: bool,
     pub error_output: Option<String>,
+    pub duration: Option<std::time::Duration>,
 }
 
 impl CommandLogEntry
@@ -39,6 +62,7 @@ impl CommandLogEntry
             command,
             success,
             error_output,
+            duration: None,
         }
     }
 }
@@ -50,10 +74,17 @@ pub enum AppState
     Loading,
     Ready,
     FileSearch,                  // Searching/filtering files
+    DeepSearch,                  // Searching everything below the current directory
+    GoToPath,                    // Typing a path to jump to
+    Compare,                     // Comparing a directory across two snapshots
+    Diff,                        // Text diff of a file from the comparison
     DownloadDialog,              // Showing download directory picker
-    Downloading(String),         // path being downloaded
     Error(String),
     Help,
+    Stats,                       // Showing snapshot/repository stats
+    ForgetDialog,                // Editing a retention policy to simulate
+    CheckPrompt,                 // Asking for --read-data-subset before a check
+    Bookmarks,                   // Picking a bookmark to open
 }
 
 /// Which control is focused in download dialog
@@ -65,11 +96,70 @@ pub enum DialogFocus
     CancelButton,
 }
 
+/// What the download dialog does with the selected entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DownloadMode
+{
+    Restore,        // restic restore into the target directory
+    Dump,           // restic dump into a file in the target directory
+    CompareLocal,   // Pick the local directory to compare the source directory with
+}
+
+/// How the Files panel is laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilesLayout
+{
+    List,       // Current directory only
+    Columns,    // Parent, current directory and a preview (ranger-style)
+    Tree,       // Current directory with subdirectories expanded in place
+}
+
+impl FilesLayout
+{
+    /// Next layout in the cycle
+    pub fn next(&self) -> Self
+    {
+        match self
+        {
+            FilesLayout::List => FilesLayout::Columns,
+            FilesLayout::Columns => FilesLayout::Tree,
+            FilesLayout::Tree => FilesLayout::List,
+        }
+    }
+
+    /// Parse a label (as used in the config file)
+    pub fn parse(label: &str) -> Option<Self>
+    {
+        match label
+        {
+            "list" => Some(FilesLayout::List),
+            "columns" => Some(FilesLayout::Columns),
+            "tree" => Some(FilesLayout::Tree),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str
+    {
+        match self
+        {
+            FilesLayout::List => "list",
+            FilesLayout::Columns => "columns",
+            FilesLayout::Tree => "tree",
+        }
+    }
+}
+
 /// Download dialog state
 pub struct DownloadDialog
 {
+    pub mode: DownloadMode,
+    /// Snapshot the source is taken from
+    pub snapshot_id: String,
     /// Source file path to download
     pub source_path: String,
+    pub source_is_dir: bool,
+    pub source_size: Option<u64>,
     /// Current text in path input
     pub input_text: String,
     /// Cursor position in input text
@@ -82,6 +172,8 @@ pub struct DownloadDialog
     pub scroll: usize,
     /// Which control is focused
     pub focus: DialogFocus,
+    /// Size warning shown after the first Enter on Download; Enter again confirms
+    pub warning: Option<String>,
 }
 
 /// Simple directory entry for the picker
@@ -94,18 +186,25 @@ pub struct DirEntry
 
 impl DownloadDialog
 {
-    pub fn new(source_path: String,
-               initial_dir: &str)
+    pub fn new(snapshot_id: String,
+               source: &FileNode,
+               initial_dir: &str,
+               mode: DownloadMode)
                -> Self
     {
         let mut dialog = Self {
-            source_path,
+            mode,
+            snapshot_id,
+            source_path: source.path.clone(),
+            source_is_dir: source.is_dir(),
+            source_size: source.size,
             input_text: initial_dir.to_string(),
             cursor_pos: initial_dir.len(),
             entries: Vec::new(),
             selected: 0,
             scroll: 0,
             focus: DialogFocus::PathPicker,
+            warning: None,
         };
         dialog.refresh_entries();
         dialog
@@ -145,9 +244,9 @@ impl DownloadDialog
                 {
                     return home_str.to_string();
                 }
-                else if path.starts_with("~/")
+                else if let Some(rest) = path.strip_prefix("~/")
                 {
-                    return format!("{}{}", home_str, &path[1..]);
+                    return format!("{}/{}", home_str, rest);
                 }
             }
         }
@@ -202,7 +301,7 @@ impl DownloadDialog
                 })
                 .collect();
 
-            entries.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+            entries.sort_by_key(|e| e.name.to_lowercase());
             self.entries.extend(entries);
         }
     }
@@ -362,6 +461,26 @@ impl DownloadDialog
         }
     }
 
+    /// Source file name (last path component)
+    pub fn source_name(&self) -> String
+    {
+        std::path::Path::new(&self.source_path)
+            .file_name()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.source_path.clone())
+    }
+
+    /// File written by a dump: the source name (plus .tar for directories) in the target directory
+    pub fn dump_target_file(&self) -> String
+    {
+        let mut name = self.source_name();
+        if self.source_is_dir
+        {
+            name.push_str(".tar");
+        }
+        PathBuf::from(self.confirmed_path()).join(name).to_string_lossy().to_string()
+    }
+
     /// Adjust scroll for visible area
     pub fn adjust_scroll(&mut self,
                          visible_height: usize)
@@ -382,6 +501,50 @@ impl DownloadDialog
     }
 }
 
+/// Snapshot/path to open at startup (from --snapshot/--host/--path)
+#[derive(Debug, Clone, Default)]
+pub struct DeepLink
+{
+    pub snapshot: Option<String>,
+    pub host: Option<String>,
+    pub path: Option<String>,
+}
+
+impl DeepLink
+{
+    pub fn is_empty(&self) -> bool
+    {
+        self.snapshot.is_none() && self.host.is_none() && self.path.is_none()
+    }
+}
+
+/// Make a path absolute without a trailing slash ("/" stays "/")
+fn normalize_snapshot_path(path: &str) -> String
+{
+    let trimmed = path.trim().trim_end_matches('/');
+    if trimmed.is_empty()
+    {
+        "/".to_string()
+    }
+    else if trimmed.starts_with('/')
+    {
+        trimmed.to_string()
+    }
+    else
+    {
+        format!("/{}", trimmed)
+    }
+}
+
+/// Check if path equals dir or lies below it
+pub fn is_same_or_under(path: &str,
+                    dir: &str)
+                    -> bool
+{
+    let dir = dir.trim_end_matches('/');
+    path == dir || dir.is_empty() || path.strip_prefix(dir).is_some_and(|rest| rest.starts_with('/'))
+}
+
 /// Spinner frames for loading animation
 const SPINNER_FRAMES: &[char] = &[ '⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏' ];
 
@@ -413,18 +576,85 @@ pub struct App
     pub filtered_files: Vec<usize>,     // Indices into files that match search
     pub file_cursor: usize,             // Cursor in filtered list
     pub file_scroll: usize,
+    pub file_sort: FileSort,
+    pub files_layout: FilesLayout,
+
+    // Previews for the columns layout per (snapshot ID, path), and the entry
+    // under the cursor with the time it got there
+    pub previews: PreviewCache,
+    preview_target: Option<(String, Instant)>,
+
+    // Directories expanded in the tree layout with their sorted children
+    // (None while the listing loads). Kept apart from the bounded `previews`
+    // so an expanded directory never loses its children.
+    pub expanded: HashMap<String, Option<Vec<FileNode>>>,
+
+    // Recursive directory sizes per snapshot ID (computed in the background)
+    pub dir_sizes: HashMap<String, DirSizeIndex>,
+    next_dir_size_run: usize,
 
     // Navigation stack (for back navigation without re-fetching)
     pub nav_stack: Vec<DirCache>,
 
     // File search
     pub search_query: String,
-    pub search_cursor: usize,           // Cursor position in search input
+    pub search_cursor: usize,           // Byte offset of the cursor in search input
+    pub search_mode: SearchMode,
+    pub search_matcher: Matcher,        // Compiled from the query (last valid one while it has an error)
+    pub search_error: Option<String>,   // Why the query doesn't compile (glob/regex)
+
+    // Deep search (kept after closing so it can be reopened for the same directory)
+    pub deep_search: Option<DeepSearch>,
+    next_deep_search_id: usize,
+
+    // Go-to-path prompt
+    pub goto_prompt: Option<GoToPrompt>,
+
+    // Side-by-side comparison of two snapshots
+    pub compare: Option<Compare>,
+
+    // Text diff of a compared file
+    pub diff: Option<FileDiff>,
+
+    // Entry to put the cursor on once the directory being fetched arrives
+    pending_select: Option<String>,
+
+    // Back/forward history, and a location from it waiting for its directory
+    history: History,
+    pending_location: Option<Location>,
+
+    // Bookmarks (saved after every change; None = not saved)
+    pub bookmarks: Vec<Bookmark>,
+    pub bookmarks_path: Option<PathBuf>,
+    pub bookmark_cursor: usize,
+    pub bookmark_scroll: usize,
 
     // Download dialog
     pub download_dialog: Option<DownloadDialog>,
     pub last_download_dir: String,
 
+    // Retention policy simulation (never applied)
+    pub forget_dialog: Option<ForgetDialog>,
+    pub forget_policy: ForgetPolicy,       // Last policy entered
+    pub forget_plan: Option<ForgetPlan>,   // Result shown in the Snapshots panel
+
+    // Jobs (restores/dumps running in the background)
+    pub jobs: Vec<Job>,
+    pub next_job_id: usize,
+    pub max_jobs: usize,               // Concurrency limit
+    pub job_cursor: usize,
+    pub job_scroll: usize,
+    pub job_visible_height: usize,
+
+    // Repository check (runs in the background)
+    pub check: Option<CheckRun>,
+    pub check_subset_input: String,
+    pub check_visible_height: usize,
+
+    // Stats view (results are cached per target since snapshots are immutable)
+    pub stats_cache: HashMap<StatsTarget, StatsEntry>,
+    pub stats_snapshot_id: Option<String>,  // Snapshot shown in the stats view
+
     // Status message (with auto-expire)
     pub status_message: Option<String>,
     pub status_expires: Option<std::time::Instant>,
@@ -444,6 +674,18 @@ pub struct App
     pub log_auto_scroll: bool,  // True when scroll is at bottom (auto-scroll on new entry)
     pub log_file_path: Option<String>,
 
+    // Mouse hit-testing (areas updated by UI)
+    pub mouse_areas: MouseAreas,
+    clicks: ClickTracker,
+
+    // Preferences (see config.rs)
+    pub keymap: Keymap,
+    pub theme: Theme,
+    pub show_hidden: bool,
+    pub time_format: String,
+    pub confirm_download_bytes: Option<u64>,
+    pub confirm_download_files: Option<u64>,
+
     pub should_quit: bool,
 }
 
@@ -468,11 +710,47 @@ impl App
             filtered_files: Vec::new(),
             file_cursor: 0,
             file_scroll: 0,
+            file_sort: FileSort::default(),
+            files_layout: FilesLayout::List,
+            previews: PreviewCache::default(),
+            preview_target: None,
+            expanded: HashMap::new(),
+            dir_sizes: HashMap::new(),
+            next_dir_size_run: 1,
             nav_stack: Vec::new(),
             search_query: String::new(),
             search_cursor: 0,
+            search_mode: SearchMode::Fuzzy,
+            search_matcher: Matcher::default(),
+            search_error: None,
+            deep_search: None,
+            next_deep_search_id: 1,
+            goto_prompt: None,
+            compare: None,
+            diff: None,
+            pending_select: None,
+            history: History::default(),
+            pending_location: None,
+            bookmarks: Vec::new(),
+            bookmarks_path: None,
+            bookmark_cursor: 0,
+            bookmark_scroll: 0,
             download_dialog: None,
             last_download_dir: default_dir,
+            forget_dialog: None,
+            forget_policy: ForgetPolicy::default(),
+            forget_plan: None,
+            jobs: Vec::new(),
+            next_job_id: 1,
+            max_jobs: DEFAULT_MAX_JOBS,
+            job_cursor: 0,
+            job_scroll: 0,
+            job_visible_height: 5,
+            check: None,
+            check_subset_input: String::new(),
+            check_visible_height: 10,
+            stats_cache: HashMap::new(),
+            stats_snapshot_id: None,
             status_message: None,
             status_expires: None,
             spinner_frame: 0,
@@ -484,10 +762,38 @@ impl App
             log_visible_height: 5,
             log_auto_scroll: true,
             log_file_path: None,
+            mouse_areas: MouseAreas::default(),
+            clicks: ClickTracker::default(),
+            keymap: Keymap::default(),
+            theme: Theme::default(),
+            show_hidden: true,
+            time_format: DEFAULT_TIME_FORMAT.to_string(),
+            confirm_download_bytes: None,
+            confirm_download_files: None,
             should_quit: false,
         }
     }
 
+    /// Apply preferences from the config file
+    pub fn apply_config(&mut self,
+                        config: &Config)
+    {
+        if let Some(ref dir) = config.download_dir
+        {
+            self.last_download_dir = dir.clone();
+        }
+        self.log_file_path = config.log_file.clone();
+        self.keymap = config.keymap.clone();
+        self.theme = config.theme.clone();
+        self.show_hidden = config.show_hidden;
+        self.time_format = config.time_format.clone();
+        self.file_sort = config.sort;
+        self.files_layout = config.files_layout;
+        self.search_mode = config.search_mode;
+        self.confirm_download_bytes = config.confirm_download_bytes;
+        self.confirm_download_files = config.confirm_download_files;
+    }
+
     /// Advance spinner animation and check status expiration
     pub fn tick_spinner(&mut self)
     {
@@ -516,7 +822,6 @@ impl App
                       -> Option<Command>
     {
         let code = key.code;
-        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
 
         // Handle download dialog keys separately
         if self.state == AppState::DownloadDialog
@@ -530,8 +835,58 @@ impl App
             return self.handle_file_search_key(code);
         }
 
+        // Handle deep search keys separately
+        if self.state == AppState::DeepSearch
+        {
+            return self.handle_deep_search_key(code);
+        }
+
+        // Handle go-to-path prompt keys separately
+        if self.state == AppState::GoToPath
+        {
+            return self.handle_goto_key(key);
+        }
+
+        // Handle comparison keys separately
+        if self.state == AppState::Compare
+        {
+            return self.handle_compare_key(code);
+        }
+
+        // Handle diff view keys separately
+        if self.state == AppState::Diff
+        {
+            return self.handle_diff_key(code);
+        }
+
+        // Handle retention policy dialog keys separately
+        if self.state == AppState::ForgetDialog
+        {
+            return self.handle_forget_dialog_key(code);
+        }
+
+        // Handle check prompt keys separately
+        if self.state == AppState::CheckPrompt
+        {
+            return self.handle_check_prompt_key(code);
+        }
+
+        // Handle stats view keys separately
+        if self.state == AppState::Stats
+        {
+            return self.handle_stats_key(code);
+        }
+
+        // Handle bookmark picker keys separately
+        if self.state == AppState::Bookmarks
+        {
+            return self.handle_bookmarks_key(code);
+        }
+
+        let action = self.keymap.action(&key, self.focused_panel);
+
         // Handle global keys first
-        if is_quit(code)
+        if action == Some(Action::Quit)
         {
             if self.state == AppState::Help
             {
@@ -542,7 +897,7 @@ impl App
             return Some(Command::Quit);
         }
 
-        if is_help(code)
+        if action == Some(Action::Help)
         {
             self.state = if self.state == AppState::Help
             {
@@ -556,7 +911,7 @@ impl App
         }
 
         // Don't process keys in help or loading state
-        if matches!(self.state, AppState::Help | AppState::Loading | AppState::Downloading(_))
+        if matches!(self.state, AppState::Help | AppState::Loading)
         {
             return None;
         }
@@ -567,43 +922,126 @@ impl App
             self.state = AppState::Ready;
         }
 
+        let action = action?;
+
         // Handle movement (including vi-style Ctrl keys)
-        if let Some(movement) = event::key_to_movement(&key)
+        if let Some(movement) = action.movement()
         {
             self.apply_movement(movement);
             return None;
         }
 
-        // Handle panel switch
-        if is_panel_switch(code)
-        {
-            self.switch_panel();
-            return None;
-        }
-
-        // Handle selection
-        if is_select(code)
-        {
-            return self.select_item();
-        }
-
-        // Handle back navigation
-        if is_back(code)
-        {
-            return self.go_back();
-        }
-
-        // Handle download (only without Ctrl, since Ctrl-D is half-page down)
-        if !ctrl && is_download(code)
-        {
-            return self.open_download_dialog();
-        }
-
-        // Handle search (/ key in Files panel)
-        if code == KeyCode::Char('/') && self.focused_panel == Panel::Files
+        match action
         {
-            self.start_file_search();
-            return None;
+            Action::SwitchPanel => self.switch_panel(),
+            Action::Select => return self.select_item(),
+            Action::Open if self.files_layout == FilesLayout::Columns => return self.select_item(),
+            Action::Back => return self.go_back(),
+            Action::OlderSnapshot => return self.time_travel(false),
+            Action::NewerSnapshot => return self.time_travel(true),
+            Action::HistoryBack =>
+            {
+                let current = self.current_location();
+                match self.history.back(current)
+                {
+                    Some(location) => return self.restore_location(location),
+                    None => self.set_status("No earlier location".to_string()),
+                }
+            }
+            Action::HistoryForward =>
+            {
+                let current = self.current_location();
+                match self.history.forward(current)
+                {
+                    Some(location) => return self.restore_location(location),
+                    None => self.set_status("No later location".to_string()),
+                }
+            }
+            Action::Download => return self.open_download_dialog(DownloadMode::Restore),
+            // Write a file/tar via restic dump
+            Action::Dump => return self.open_download_dialog(DownloadMode::Dump),
+            Action::Bookmark => self.add_bookmark(false),
+            Action::BookmarkPinned => self.add_bookmark(true),
+            Action::Bookmarks =>
+            {
+                self.bookmark_cursor = self.bookmark_cursor.min(self.bookmarks.len().saturating_sub(1));
+                self.state = AppState::Bookmarks;
+            }
+            Action::Search => self.start_file_search(),
+            Action::DeepSearch => return self.open_deep_search(),
+            Action::Compare => return self.open_compare(),
+            Action::CompareLocal => self.open_local_compare_dialog(),
+            Action::GoToPath if self.current_snapshot_id.is_some() =>
+            {
+                self.goto_prompt = Some(GoToPrompt::new(&self.current_path));
+                self.state = AppState::GoToPath;
+            }
+            Action::SortNext | Action::SortReverse | Action::DirsFirst => self.handle_sort_action(action),
+            Action::Expand if self.tree_active() =>
+            {
+                let dir = self.file_at_cursor()
+                              .filter(|f| f.is_dir() && f.name != "..")
+                              .map(|f| f.path.clone());
+                if let Some(path) = dir
+                {
+                    return self.expand_dir(path);
+                }
+            }
+            Action::Collapse if self.tree_active() =>
+            {
+                self.collapse_at_cursor();
+            }
+            Action::Layout =>
+            {
+                self.files_layout = self.files_layout.next();
+                self.set_status(format!("Files layout: {}", self.files_layout.label()));
+            }
+            Action::ToggleHidden =>
+            {
+                self.show_hidden = !self.show_hidden;
+                self.update_filtered_files();
+                self.file_cursor = 0;
+                self.file_scroll = 0;
+                self.sort_files();
+                self.set_status(if self.show_hidden { "Showing hidden files" } else { "Hiding hidden files" }.to_string());
+            }
+            Action::RetryJob | Action::ClearJobs | Action::MoreJobs | Action::FewerJobs =>
+            {
+                self.handle_jobs_action(action);
+            }
+            // Retention policy simulation
+            Action::Forget =>
+            {
+                self.forget_dialog = Some(ForgetDialog::new(&self.forget_policy));
+                self.state = AppState::ForgetDialog;
+            }
+            Action::ClearForget =>
+            {
+                let had_plan = self.forget_plan.take().is_some();
+                if had_plan
+                {
+                    self.set_status("Cleared policy simulation".to_string());
+                }
+            }
+            Action::Check =>
+            {
+                if self.check.as_ref().is_some_and(|c| c.is_running())
+                {
+                    self.set_status("A repository check is already running".to_string());
+                }
+                else
+                {
+                    self.state = AppState::CheckPrompt;
+                }
+            }
+            // Dismiss finished check results
+            Action::DismissCheck if !self.check.as_ref().is_some_and(|c| c.is_running()) =>
+            {
+                self.check = None;
+                self.focused_panel = Panel::CommandLog;
+            }
+            Action::Stats => return self.open_stats(),
+            _ => {}
         }
 
         None
@@ -618,6 +1056,8 @@ impl App
             Panel::Snapshots => (self.snapshots.len(), self.snapshot_visible_height),
             Panel::Files => (self.visible_file_count(), self.file_visible_height),
             Panel::CommandLog => (self.command_logs.len(), self.log_visible_height),
+            Panel::Jobs => (self.jobs.len(), self.job_visible_height),
+            Panel::Check => (self.check.as_ref().map_or(0, |c| c.output.len()), self.check_visible_height),
         };
 
         if count == 0
@@ -628,8 +1068,8 @@ impl App
         let max = count - 1;
         let delta: i32 = match movement
         {
-            Movement::Up(n) => -(n as i32),
-            Movement::Down(n) => n as i32,
+            Movement::Up(n) => -n,
+            Movement::Down(n) => n,
             Movement::PageUp => -(visible_height as i32),
             Movement::PageDown => visible_height as i32,
             Movement::HalfPageUp => -(visible_height as i32 / 2).max(1),
@@ -638,14 +1078,34 @@ impl App
             Movement::Bottom => i32::MAX,
         };
 
-        let cursor = match self.focused_panel
+        if let Some(cursor) = self.cursor_mut()
         {
-            Panel::Snapshots => &mut self.snapshot_cursor,
-            Panel::Files => &mut self.file_cursor,
-            Panel::CommandLog => &mut self.log_cursor,
-        };
+            *cursor = Self::clamp_cursor(*cursor, delta, max);
+        }
+        self.cursor_moved();
+    }
+
+    /// Cursor of the focused panel
+    fn cursor_mut(&mut self) -> Option<&mut usize>
+    {
+        match self.focused_panel
+        {
+            Panel::Snapshots => Some(&mut self.snapshot_cursor),
+            Panel::Files => Some(&mut self.file_cursor),
+            Panel::CommandLog => Some(&mut self.log_cursor),
+            Panel::Jobs => Some(&mut self.job_cursor),
+            Panel::Check => self.check.as_mut().map(|c| &mut c.cursor),
+        }
+    }
 
-        *cursor = Self::clamp_cursor(*cursor, delta, max);
+    /// Update follow-mode flags after the focused panel's cursor moved
+    fn cursor_moved(&mut self)
+    {
+        // Follow new check output only while the cursor is on the last line
+        if let (Panel::Check, Some(check)) = (self.focused_panel, self.check.as_mut())
+        {
+            check.auto_scroll = check.cursor + 1 >= check.output.len();
+        }
 
         // Update auto-scroll flag for command log
         if self.focused_panel == Panel::CommandLog
@@ -654,6 +1114,110 @@ impl App
         }
     }
 
+    /// Handle a mouse event and return an optional command to execute
+    pub fn handle_mouse(&mut self,
+                        mouse: MouseEvent)
+                        -> Option<Command>
+    {
+        let (column, row) = (mouse.column, mouse.row);
+
+        // The wheel scrolls, a left click selects; everything else is ignored
+        let wheel = match mouse.kind
+        {
+            MouseEventKind::ScrollUp => Some(Movement::Up(WHEEL_LINES)),
+            MouseEventKind::ScrollDown => Some(Movement::Down(WHEEL_LINES)),
+            MouseEventKind::Down(MouseButton::Left) => None,
+            _ => return None,
+        };
+        let double_click = wheel.is_none() && self.clicks.click(column, row);
+
+        match self.state
+        {
+            AppState::DownloadDialog => self.handle_download_dialog_mouse(column, row, wheel, double_click),
+            AppState::DeepSearch => self.handle_deep_search_mouse(column, row, wheel, double_click),
+            AppState::Bookmarks => self.handle_bookmarks_mouse(column, row, wheel, double_click),
+            AppState::Compare => self.handle_compare_mouse(column, row, wheel, double_click),
+            AppState::Diff =>
+            {
+                match (wheel, self.diff.as_mut())
+                {
+                    (Some(Movement::Up(n)), Some(diff)) => diff.scroll_by(-n),
+                    (Some(Movement::Down(n)), Some(diff)) => diff.scroll_by(n),
+                    _ => {}
+                }
+                None
+            }
+            AppState::ForgetDialog =>
+            {
+                // Clicking a field focuses it
+                if let (None, Some(fields), Some(dialog)) = (wheel, self.mouse_areas.forget_fields, self.forget_dialog.as_mut())
+                {
+                    if fields.contains(Position::new(column, row))
+                    {
+                        dialog.focus = (row - fields.y) as usize;
+                    }
+                }
+                None
+            }
+            // Any click closes the help overlay, like any key
+            AppState::Help if wheel.is_none() =>
+            {
+                self.state = AppState::Ready;
+                None
+            }
+            AppState::Ready | AppState::Error(_) | AppState::FileSearch =>
+            {
+                self.handle_panel_mouse(column, row, wheel, double_click)
+            }
+            _ => None,
+        }
+    }
+
+    /// Mouse over the panels: click focuses and moves the cursor, double-click opens
+    fn handle_panel_mouse(&mut self,
+                          column: u16,
+                          row: u16,
+                          wheel: Option<Movement>,
+                          double_click: bool)
+                          -> Option<Command>
+    {
+        let (panel, item) = self.mouse_areas
+                                .panel_at(column, row)
+                                .map(|(panel, list)| (panel, list.item_at(column, row)))?;
+
+        // Clear error state on any click, like any key
+        if let AppState::Error(_) = self.state
+        {
+            self.state = AppState::Ready;
+        }
+        // Using another panel ends search mode but keeps the filter
+        if self.state == AppState::FileSearch && panel != Panel::Files
+        {
+            self.state = AppState::Ready;
+        }
+
+        self.focused_panel = panel;
+
+        if let Some(movement) = wheel
+        {
+            self.apply_movement(movement);
+            return None;
+        }
+
+        if let Some(cursor) = self.cursor_mut()
+        {
+            *cursor = item?;
+        }
+        self.cursor_moved();
+
+        if double_click && matches!(panel, Panel::Snapshots | Panel::Files)
+        {
+            self.state = AppState::Ready;
+            return self.select_item();
+        }
+        None
+    }
+
     /// Start file search mode
     fn start_file_search(&mut self)
     {
@@ -689,47 +1253,45 @@ impl App
                 return self.select_item();
             }
 
-            // Navigate filtered list
-            KeyCode::Up | KeyCode::Char('k') =>
+            // Cycle fuzzy / substring / glob / regex
+            KeyCode::Tab =>
+            {
+                self.search_mode = self.search_mode.next();
+                self.apply_search_filter();
+            }
+
+            // Navigate filtered list (letters go into the query, e.g. "*.jpg")
+            KeyCode::Up =>
             {
                 self.apply_movement(Movement::Up(1));
             }
-            KeyCode::Down | KeyCode::Char('j') =>
+            KeyCode::Down =>
             {
                 self.apply_movement(Movement::Down(1));
             }
 
-            // Text editing
-            KeyCode::Backspace =>
+            // Text editing (the cursor is a byte offset on a char boundary)
+            KeyCode::Backspace if self.search_cursor > 0 =>
             {
-                if self.search_cursor > 0
-                {
-                    self.search_cursor -= 1;
-                    self.search_query.remove(self.search_cursor);
-                    self.apply_search_filter();
-                }
+                let c = self.search_query[..self.search_cursor].chars().next_back().unwrap_or_default();
+                self.search_cursor -= c.len_utf8();
+                self.search_query.remove(self.search_cursor);
+                self.apply_search_filter();
             }
-            KeyCode::Delete =>
+            KeyCode::Delete if self.search_cursor < self.search_query.len() =>
             {
-                if self.search_cursor < self.search_query.len()
-                {
-                    self.search_query.remove(self.search_cursor);
-                    self.apply_search_filter();
-                }
+                self.search_query.remove(self.search_cursor);
+                self.apply_search_filter();
             }
-            KeyCode::Left =>
+            KeyCode::Left if self.search_cursor > 0 =>
             {
-                if self.search_cursor > 0
-                {
-                    self.search_cursor -= 1;
-                }
+                let c = self.search_query[..self.search_cursor].chars().next_back().unwrap_or_default();
+                self.search_cursor -= c.len_utf8();
             }
-            KeyCode::Right =>
+            KeyCode::Right if self.search_cursor < self.search_query.len() =>
             {
-                if self.search_cursor < self.search_query.len()
-                {
-                    self.search_cursor += 1;
-                }
+                let c = self.search_query[self.search_cursor..].chars().next().unwrap_or_default();
+                self.search_cursor += c.len_utf8();
             }
             KeyCode::Home =>
             {
@@ -744,7 +1306,7 @@ impl App
             KeyCode::Char(c) =>
             {
                 self.search_query.insert(self.search_cursor, c);
-                self.search_cursor += 1;
+                self.search_cursor += c.len_utf8();
                 self.apply_search_filter();
             }
 
@@ -757,190 +1319,2224 @@ impl App
     /// Apply search filter to files
     fn apply_search_filter(&mut self)
     {
-        self.filtered_files.clear();
-        self.file_cursor = 0;
-        self.file_scroll = 0;
-
-        let query = self.search_query.to_lowercase();
-
-        for (i, file) in self.files.iter().enumerate()
+        // Keep filtering with the last valid pattern while the query has an error
+        match Matcher::new(self.search_mode, &self.search_query)
         {
-            // Always include ".." entry
-            if file.name == ".."
+            Ok(matcher) =>
             {
-                self.filtered_files.push(i);
-                continue;
+                self.search_matcher = matcher;
+                self.search_error = None;
             }
-
-            // Match if query is empty or name contains query (case-insensitive)
-            if query.is_empty() || file.name.to_lowercase().contains(&query)
+            Err(e) =>
             {
-                self.filtered_files.push(i);
+                self.search_error = Some(e);
+                return;
             }
         }
+
+        self.file_cursor = 0;
+        self.file_scroll = 0;
+        self.update_filtered_files();
     }
 
-    /// Get the currently visible files (filtered or all)
-    pub fn visible_files(&self) -> Vec<&FileNode>
+    /// Open the deep search for the current directory. A search of the same
+    /// directory is reopened as it is (still running or finished).
+    fn open_deep_search(&mut self) -> Option<Command>
     {
-        if self.search_query.is_empty() && self.state != AppState::FileSearch
+        if self.focused_panel != Panel::Files
+        {
+            return None;
+        }
+        let snapshot_id = self.current_snapshot_id.clone()?;
+
+        if let Some(ref mut search) = self.deep_search
+        {
+            let same_dir = search.snapshot_id == snapshot_id && search.root == self.current_path;
+            if same_dir && !matches!(search.status, DeepSearchStatus::Failed(_))
+            {
+                if search.show_hidden != self.show_hidden
+                {
+                    search.show_hidden = self.show_hidden;
+                    search.update_query();
+                }
+                self.state = AppState::DeepSearch;
+                return None;
+            }
+            search.cancel();
+        }
+
+        // At the paths root, search all of the snapshot's paths
+        let paths = if self.current_path.is_empty()
+        {
+            self.snapshots.iter().find(|s| s.full_id == snapshot_id)?.paths.clone()
+        }
+        else
+        {
+            vec![self.current_path.clone()]
+        };
+
+        let search = DeepSearch::new(self.next_deep_search_id,
+                                     snapshot_id.clone(),
+                                     self.current_path.clone(),
+                                     self.search_mode,
+                                     self.show_hidden);
+        self.next_deep_search_id += 1;
+        let command = Command::DeepSearch {
+            id: search.id,
+            snapshot_id,
+            paths,
+            cancel: search.cancel_flag(),
+        };
+        self.deep_search = Some(search);
+        self.state = AppState::DeepSearch;
+        Some(command)
+    }
+
+    /// Handle key events in the deep search
+    fn handle_deep_search_key(&mut self,
+                              key: KeyCode)
+                              -> Option<Command>
+    {
+        let search = self.deep_search.as_mut()?;
+
+        match key
+        {
+            // Close; the search keeps running and can be reopened
+            KeyCode::Esc =>
+            {
+                self.state = AppState::Ready;
+            }
+            KeyCode::Enter => return self.open_deep_search_result(),
+            KeyCode::Tab =>
+            {
+                search.mode = search.mode.next();
+                search.update_query();
+            }
+
+            // Navigate results
+            KeyCode::Up => search.move_cursor(-1),
+            KeyCode::Down => search.move_cursor(1),
+            KeyCode::PageUp => search.move_cursor(-(search.visible_height as i32)),
+            KeyCode::PageDown => search.move_cursor(search.visible_height as i32),
+
+            // Text editing
+            KeyCode::Backspace if search.query_cursor > 0 =>
+            {
+                let c = search.query[..search.query_cursor].chars().next_back().unwrap_or_default();
+                search.query_cursor -= c.len_utf8();
+                search.query.remove(search.query_cursor);
+                search.update_query();
+            }
+            KeyCode::Delete if search.query_cursor < search.query.len() =>
+            {
+                search.query.remove(search.query_cursor);
+                search.update_query();
+            }
+            KeyCode::Left if search.query_cursor > 0 =>
+            {
+                let c = search.query[..search.query_cursor].chars().next_back().unwrap_or_default();
+                search.query_cursor -= c.len_utf8();
+            }
+            KeyCode::Right if search.query_cursor < search.query.len() =>
+            {
+                let c = search.query[search.query_cursor..].chars().next().unwrap_or_default();
+                search.query_cursor += c.len_utf8();
+            }
+            KeyCode::Home =>
+            {
+                search.query_cursor = 0;
+            }
+            KeyCode::End =>
+            {
+                search.query_cursor = search.query.len();
+            }
+            KeyCode::Char(c) =>
+            {
+                search.query.insert(search.query_cursor, c);
+                search.query_cursor += c.len_utf8();
+                search.update_query();
+            }
+
+            _ => {}
+        }
+
+        None
+    }
+
+    /// Mouse in the deep search: click selects, double-click opens, wheel scrolls
+    fn handle_deep_search_mouse(&mut self,
+                                column: u16,
+                                row: u16,
+                                wheel: Option<Movement>,
+                                double_click: bool)
+                                -> Option<Command>
+    {
+        let listing = self.mouse_areas.deep_search.as_ref()?;
+        if !listing.contains(column, row)
+        {
+            return None;
+        }
+        let item = listing.item_at(column, row);
+        let search = self.deep_search.as_mut()?;
+
+        match wheel
+        {
+            Some(Movement::Up(n)) => search.move_cursor(-n),
+            Some(Movement::Down(n)) => search.move_cursor(n),
+            _ =>
+            {
+                search.cursor = item?;
+                if double_click
+                {
+                    return self.open_deep_search_result();
+                }
+            }
+        }
+        None
+    }
+
+    /// Go to the directory of the deep search match under the cursor
+    fn open_deep_search_result(&mut self) -> Option<Command>
+    {
+        let search = self.deep_search.as_ref()?;
+        let path = search.selected()?.path.clone();
+        self.navigate_to(self.parent_dir(&path), path)
+    }
+
+    /// Store a batch of entries listed for a deep search
+    pub fn add_deep_search_entries(&mut self,
+                                   id: usize,
+                                   nodes: Vec<FileNode>)
+    {
+        if let Some(search) = self.deep_search.as_mut().filter(|s| s.id == id)
+        {
+            search.push_entries(nodes);
+        }
+    }
+
+    /// Mark the listing behind a deep search as finished
+    pub fn finish_deep_search(&mut self,
+                              id: usize,
+                              result: Result<(), String>)
+    {
+        if let Some(search) = self.deep_search.as_mut().filter(|s| s.id == id)
+        {
+            search.finish(result);
+        }
+    }
+
+    /// Handle key events in the go-to-path prompt
+    fn handle_goto_key(&mut self,
+                       key: KeyEvent)
+                       -> Option<Command>
+    {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let prompt = self.goto_prompt.as_mut()?;
+
+        match (key.code, ctrl)
+        {
+            (KeyCode::Esc, _) =>
+            {
+                self.goto_prompt = None;
+                self.state = AppState::Ready;
+            }
+
+            // Complete the last path component
+            (KeyCode::Tab, _) =>
+            {
+                let dir = prompt.completion_dir();
+                return self.goto_step(GoToWaiting::Complete(dir));
+            }
+
+            (KeyCode::Enter, _) => return self.confirm_goto(),
+
+            // Text editing
+            (KeyCode::Char('w'), true) => prompt.delete_component(),
+            (KeyCode::Char('u'), true) => prompt.delete_to_start(),
+            (KeyCode::Backspace, _) => prompt.backspace(),
+            (KeyCode::Delete, _) => prompt.delete(),
+            (KeyCode::Left, _) => prompt.cursor_left(),
+            (KeyCode::Right, _) => prompt.cursor_right(),
+            (KeyCode::Home, _) => prompt.cursor = 0,
+            (KeyCode::End, _) => prompt.cursor = prompt.input.len(),
+            (KeyCode::Char(c), false) => prompt.insert_char(c),
+
+            _ => {}
+        }
+
+        None
+    }
+
+    /// Check the typed path and go there. Paths within the snapshot are
+    /// looked up in their parent's listing to tell files from directories.
+    fn confirm_goto(&mut self) -> Option<Command>
+    {
+        let input = self.goto_prompt.as_ref()?.input.clone();
+        if input.trim().is_empty()
+        {
+            self.goto_prompt = None;
+            self.state = AppState::Ready;
+            return None;
+        }
+
+        let path = normalize_snapshot_path(&input);
+        let paths = self.current_snapshot_paths();
+
+        // A backed-up path is a directory; above one there is only the paths root
+        if paths.iter().any(|p| p.trim_end_matches('/') == path)
+        {
+            self.goto_prompt = None;
+            return self.navigate_to(path, String::new());
+        }
+        if let Some(below) = paths.iter().find(|p| is_same_or_under(p, &path))
+        {
+            let select = below.clone();
+            self.goto_prompt = None;
+            return self.navigate_to(String::new(), select);
+        }
+
+        let parent = self.parent_dir(&path);
+        self.goto_step(GoToWaiting::Open(parent))
+    }
+
+    /// Complete or open the prompt's path with the listing it needs, fetching
+    /// the listing first if it isn't cached
+    fn goto_step(&mut self,
+                 step: GoToWaiting)
+                 -> Option<Command>
+    {
+        let dir = match step
+        {
+            GoToWaiting::Complete(ref dir) | GoToWaiting::Open(ref dir) => dir.clone(),
+        };
+        let listing = self.snapshot_listing(&dir);
+        let show_hidden = self.show_hidden;
+        let prompt = self.goto_prompt.as_mut()?;
+
+        let entries = match listing
+        {
+            Ok(Some(entries)) => entries,
+            Ok(None) =>
+            {
+                prompt.waiting = Some(step);
+                let snapshot_id = self.current_snapshot_id.clone()?;
+                let key = (snapshot_id.clone(), dir.clone());
+                // Already being fetched (e.g. for the preview column)
+                if self.previews.contains_key(&key)
+                {
+                    return None;
+                }
+                self.previews.insert(key, Preview::Loading);
+                return Some(Command::LoadPreview { snapshot_id, path: dir, is_dir: true });
+            }
+            Err(e) =>
+            {
+                prompt.waiting = None;
+                prompt.error = Some(e);
+                return None;
+            }
+        };
+        prompt.waiting = None;
+
+        match step
+        {
+            GoToWaiting::Complete(_) =>
+            {
+                prompt.complete(&entries, show_hidden);
+                None
+            }
+            GoToWaiting::Open(_) =>
+            {
+                let path = normalize_snapshot_path(&prompt.input);
+                match entries.iter().find(|e| e.path.trim_end_matches('/') == path)
+                {
+                    Some(entry) =>
+                    {
+                        // Files are shown in their directory with the cursor on them
+                        let (dir, select) = if entry.is_dir() { (path, String::new()) } else { (dir, path) };
+                        self.goto_prompt = None;
+                        self.navigate_to(dir, select)
+                    }
+                    None =>
+                    {
+                        prompt.error = Some(format!("No such file or directory: {}", path));
+                        None
+                    }
+                }
+            }
+        }
+    }
+
+    /// Backed-up paths of the current snapshot
+    fn current_snapshot_paths(&self) -> Vec<String>
+    {
+        self.current_snapshot_id
+            .as_ref()
+            .and_then(|id| self.snapshots.iter().find(|s| &s.full_id == id))
+            .map(|s| s.paths.clone())
+            .unwrap_or_default()
+    }
+
+    /// Entries of a directory of the current snapshot, from the cached
+    /// listings (Ok(None) = not listed yet). Above the backed-up paths the
+    /// entries are their next path components.
+    fn snapshot_listing(&self,
+                        dir: &str)
+                        -> Result<Option<Vec<FileNode>>, String>
+    {
+        let snapshot_id = self.current_snapshot_id.clone().ok_or("No snapshot selected")?;
+        let paths = self.current_snapshot_paths();
+
+        if paths.iter().any(|p| is_same_or_under(dir, p))
+        {
+            return match self.previews.get(&(snapshot_id, dir.to_string()))
+            {
+                Some(Preview::Listing(files)) => Ok(Some(files.clone())),
+                Some(Preview::Content(_)) => Err(format!("Not a directory: {}", dir)),
+                Some(Preview::Failed(e)) => Err(e.clone()),
+                Some(Preview::Loading) | None => Ok(None),
+            };
+        }
+
+        let base = dir.trim_end_matches('/');
+        let mut entries: Vec<FileNode> = Vec::new();
+        for path in &paths
+        {
+            let name = match path.strip_prefix(base).and_then(|rest| rest.strip_prefix('/'))
+            {
+                Some(rest) => rest.split('/').next().unwrap_or(""),
+                None => continue,
+            };
+            if !name.is_empty() && !entries.iter().any(|e| e.name == name)
+            {
+                let mut entry = path_entry(&format!("{}/{}", base, name));
+                entry.name = name.to_string();
+                entries.push(entry);
+            }
+        }
+        if entries.is_empty()
+        {
+            return Err(format!("Not in this snapshot: {}", dir));
+        }
+        Ok(Some(entries))
+    }
+
+    /// Compare the current directory across two snapshots: the open one and
+    /// the one under the cursor in the Snapshots panel, or else the previous
+    /// snapshot of the same host and paths
+    fn open_compare(&mut self) -> Option<Command>
+    {
+        let current_id = match self.current_snapshot_id.clone()
+        {
+            Some(id) => id,
+            None =>
+            {
+                self.set_status("Open a snapshot to compare first".to_string());
+                return None;
+            }
+        };
+        let current = self.snapshots.iter().position(|s| s.full_id == current_id)?;
+
+        let other = if self.focused_panel == Panel::Snapshots
+        {
+            Some(self.snapshot_cursor).filter(|&i| i != current)
+        }
+        else
+        {
+            let series = &self.snapshots[current];
+            let same_series = |s: &Snapshot| s.hostname == series.hostname && s.paths == series.paths;
+            // Snapshots are sorted newest first: prefer the older neighbour
+            self.snapshots[current + 1..].iter().position(same_series).map(|i| current + 1 + i)
+                .or_else(|| self.snapshots[..current].iter().rposition(same_series))
+        };
+        let other = match other
+        {
+            Some(i) => i,
+            None =>
+            {
+                self.set_status("Select another snapshot in the Snapshots panel to compare with".to_string());
+                return None;
+            }
+        };
+
+        // Left is the older snapshot
+        let (left, right) = if other > current { (other, current) } else { (current, other) };
+        let select = self.file_at_cursor().filter(|f| f.name != "..").map(|f| f.name.clone());
+        self.compare = Some(Compare::new(self.snapshots[left].full_id.clone(),
+                                         self.snapshots[right].full_id.clone(),
+                                         self.current_path.clone(),
+                                         select));
+        self.state = AppState::Compare;
+        self.load_compare()
+    }
+
+    /// Ask for the local directory to compare the Files panel directory with,
+    /// starting with the same path on this machine
+    fn open_local_compare_dialog(&mut self)
+    {
+        let snapshot_id = match self.current_snapshot_id.clone()
+        {
+            Some(id) if !self.current_path.is_empty() => id,
+            _ =>
+            {
+                self.set_status("Open a directory of a snapshot to compare first".to_string());
+                return;
+            }
+        };
+
+        let initial_dir = if Path::new(&self.current_path).is_dir() { &self.current_path } else { &self.last_download_dir };
+        self.download_dialog = Some(DownloadDialog::new(snapshot_id,
+                                                        &path_entry(&self.current_path),
+                                                        initial_dir,
+                                                        DownloadMode::CompareLocal));
+        self.state = AppState::DownloadDialog;
+    }
+
+    /// Compare the Files panel directory with a local directory
+    fn open_local_compare(&mut self,
+                          snapshot_id: String,
+                          path: String,
+                          root: PathBuf)
+                          -> Option<Command>
+    {
+        self.compare = Some(Compare::local(snapshot_id, path, root));
+        self.state = AppState::Compare;
+        self.load_compare()
+    }
+
+    /// Fill in both sides of the comparison from the cached listings, fetching
+    /// the ones that are missing
+    fn load_compare(&mut self) -> Option<Command>
+    {
+        let (left_id, right_id, path, local_dir) = {
+            let compare = self.compare.as_ref()?;
+            let local_dir = compare.local.as_ref().map(|l| l.dir_for(&compare.path));
+            (compare.left_id.clone(), compare.right_id.clone(), compare.path.clone(), local_dir)
+        };
+
+        let mut commands = Vec::new();
+        let left = self.compare_listing(&left_id, &path, &mut commands);
+        let right = match local_dir
+        {
+            Some(_) => None,
+            None => Some(self.compare_listing(&right_id, &path, &mut commands)),
+        };
+
+        let compare = self.compare.as_mut()?;
+        compare.left = left;
+        match right
+        {
+            Some(right) => compare.right = right,
+            // The local directory is read in the background when first shown
+            None if matches!(compare.right, Listing::Loading) && !compare.local_reading =>
+            {
+                commands.extend(Self::read_local_compare(compare));
+            }
+            None => {}
+        }
+        compare.rebuild();
+
+        match commands.len()
+        {
+            0 => None,
+            1 => commands.pop(),
+            _ => Some(Command::Batch(commands)),
+        }
+    }
+
+    /// Read the local side of a comparison (again)
+    fn read_local_compare(compare: &mut Compare) -> Option<Command>
+    {
+        let dir = compare.local.as_ref()?.dir_for(&compare.path);
+        compare.local_reading = true;
+        Some(Command::LoadLocalListing { path: compare.path.clone(), dir })
+    }
+
+    /// Show the local side of a comparison read in the background
+    pub fn set_local_listing(&mut self,
+                             path: &str,
+                             listing: Listing)
+    {
+        if let Some(compare) = self.compare.as_mut().filter(|c| c.local.is_some() && c.path == path)
+        {
+            compare.local_reading = false;
+            compare.right = listing;
+            compare.rebuild();
+        }
+    }
+
+    /// A directory of one of the compared snapshots ("" = its paths root)
+    fn compare_listing(&mut self,
+                       snapshot_id: &str,
+                       path: &str,
+                       commands: &mut Vec<Command>)
+                       -> Listing
+    {
+        if path.is_empty()
+        {
+            let paths = self.snapshots.iter().find(|s| s.full_id == snapshot_id).map(|s| s.paths.clone()).unwrap_or_default();
+            return Listing::Entries(paths.iter().map(|p| path_entry(p)).collect());
+        }
+
+        let key = (snapshot_id.to_string(), path.to_string());
+        if let Some(reason) = self.compare.as_ref().and_then(|c| c.absent.get(&key))
+        {
+            return Listing::Absent(reason.clone());
+        }
+        match self.previews.get(&key)
+        {
+            Some(Preview::Listing(files)) => Listing::Entries(files.clone()),
+            Some(Preview::Content(_)) => Listing::Absent("Not a directory".to_string()),
+            Some(Preview::Failed(e)) => Listing::Absent(e.clone()),
+            Some(Preview::Loading) => Listing::Loading,
+            None =>
+            {
+                self.previews.insert(key, Preview::Loading);
+                commands.push(Command::LoadPreview { snapshot_id: snapshot_id.to_string(), path: path.to_string(), is_dir: true });
+                Listing::Loading
+            }
+        }
+    }
+
+    /// Handle key events in the comparison view
+    fn handle_compare_key(&mut self,
+                          key: KeyCode)
+                          -> Option<Command>
+    {
+        let compare = self.compare.as_mut()?;
+        let page = compare.visible_height.max(1) as i32;
+        // Any other key cancels a restore waiting for confirmation
+        let confirming = compare.confirm.take();
+
+        match key
+        {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('=') =>
+            {
+                self.compare = None;
+                self.state = AppState::Ready;
+            }
+
+            KeyCode::Up | KeyCode::Char('k') => compare.move_cursor(-1),
+            KeyCode::Down | KeyCode::Char('j') => compare.move_cursor(1),
+            KeyCode::PageUp => compare.move_cursor(-page),
+            KeyCode::PageDown => compare.move_cursor(page),
+            KeyCode::Home | KeyCode::Char('g') => compare.move_cursor(i32::MIN / 2),
+            KeyCode::End | KeyCode::Char('G') => compare.move_cursor(i32::MAX / 2),
+
+            // Both sides follow into a directory and back out; files are diffed
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => return self.open_compared(),
+            KeyCode::Backspace | KeyCode::Left | KeyCode::Char('h') => return self.leave_compare_dir(),
+
+            // Only show entries that differ
+            KeyCode::Char('c') =>
+            {
+                compare.changes_only = !compare.changes_only;
+                compare.rebuild();
+            }
+
+            // Restore one side's version of the entry
+            KeyCode::Char('<') => self.restore_compared(true, confirming),
+            KeyCode::Char('>') => self.restore_compared(false, confirming),
+
+            // Restore everything missing or different on the local side
+            KeyCode::Char('R') => self.restore_all_locally(confirming),
+
+            // Read the local directory again (e.g. after restoring elsewhere)
+            KeyCode::Char('r') =>
+            {
+                return match self.compare.as_mut()
+                {
+                    Some(compare) if compare.local.is_some() => Self::read_local_compare(compare),
+                    _ => self.load_compare(),
+                };
+            }
+
+            _ => {}
+        }
+
+        None
+    }
+
+    /// Mouse in the comparison: select rows, scroll, double-click opens entries
+    fn handle_compare_mouse(&mut self,
+                            column: u16,
+                            row: u16,
+                            wheel: Option<Movement>,
+                            double_click: bool)
+                            -> Option<Command>
+    {
+        let listing = self.mouse_areas.compare.as_ref()?;
+        if !listing.contains(column, row)
+        {
+            return None;
+        }
+        let item = listing.item_at(column, row);
+        let compare = self.compare.as_mut()?;
+
+        match wheel
+        {
+            Some(Movement::Up(n)) => compare.move_cursor(-n),
+            Some(Movement::Down(n)) => compare.move_cursor(n),
+            _ =>
+            {
+                compare.cursor = item?;
+                if double_click
+                {
+                    return self.open_compared();
+                }
+            }
+        }
+        None
+    }
+
+    /// Open the entry under the comparison cursor: directories in both
+    /// snapshots, files in the diff view
+    fn open_compared(&mut self) -> Option<Command>
+    {
+        if self.compare.as_ref()?.selected()?.is_dir()
+        {
+            self.enter_compare_dir()
+        }
+        else
+        {
+            self.open_diff()
+        }
+    }
+
+    /// Diff the file under the comparison cursor between the two snapshots
+    fn open_diff(&mut self) -> Option<Command>
+    {
+        let compare = self.compare.as_ref()?;
+        let row = compare.selected()?;
+        let (left, right) = match (&row.left, &row.right)
+        {
+            (Some(left), Some(right)) => (left, right),
+            _ =>
+            {
+                let message = format!("{} is only in one snapshot", row.name);
+                self.set_status(message);
+                return None;
+            }
+        };
+        if left.is_dir() || right.is_dir()
+        {
+            let message = format!("{} is a directory in one snapshot", row.name);
+            self.set_status(message);
+            return None;
+        }
+
+        let path = left.path.clone();
+        let diff = FileDiff::new(path.clone(),
+                                 compare.left_id.clone(),
+                                 compare.right_id.clone(),
+                                 left.size,
+                                 right.size);
+        // The local version is read from disk instead of the snapshot
+        let local_file = compare.local.as_ref().map(|local| local.dir_for(&compare.path).join(&row.name));
+        let fetch = |snapshot_id: String| match local_file
+        {
+            Some(ref file) if snapshot_id == compare.right_id =>
+            {
+                Command::LoadLocalFileVersion { snapshot_id, path: path.clone(), file: file.clone() }
+            }
+            _ => Command::LoadFileVersion { snapshot_id, path: path.clone() },
+        };
+        let mut commands: Vec<Command> = diff.pending().into_iter().map(fetch).collect();
+        self.diff = Some(diff);
+        self.state = AppState::Diff;
+        commands.extend(self.diff_command());
+
+        match commands.len()
+        {
+            0 => None,
+            1 => commands.pop(),
+            _ => Some(Command::Batch(commands)),
+        }
+    }
+
+    /// Store a file version fetched for the diff view; once both are there
+    /// the diff is computed in the background
+    pub fn set_file_version(&mut self,
+                            snapshot_id: &str,
+                            path: &str,
+                            result: Result<Vec<u8>, String>)
+                            -> Option<Command>
+    {
+        // The diff may have been closed or replaced meanwhile
+        let diff = self.diff.as_mut().filter(|d| d.path == path)?;
+        diff.set_version(snapshot_id, result);
+        self.diff_command()
+    }
+
+    /// Compute the open diff if both versions are there
+    fn diff_command(&mut self) -> Option<Command>
+    {
+        let diff = self.diff.as_mut()?;
+        let input = diff.take_input()?;
+        Some(Command::ComputeDiff {
+            path: diff.path.clone(),
+            left_id: diff.left_id.clone(),
+            right_id: diff.right_id.clone(),
+            input,
+        })
+    }
+
+    /// Show a computed diff unless the diff view moved on meanwhile
+    pub fn set_diff_body(&mut self,
+                         path: &str,
+                         left_id: &str,
+                         right_id: &str,
+                         body: DiffBody)
+    {
+        if let Some(diff) = self.diff
+                                .as_mut()
+                                .filter(|d| d.path == path && d.left_id == left_id && d.right_id == right_id)
+        {
+            diff.set_body(body);
+        }
+    }
+
+    /// Handle keys in the diff view
+    fn handle_diff_key(&mut self,
+                       key: KeyCode)
+                       -> Option<Command>
+    {
+        let diff = self.diff.as_mut()?;
+        let page = diff.visible_height.max(1) as i32;
+
+        match key
+        {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Left | KeyCode::Char('h') | KeyCode::Backspace =>
+            {
+                self.diff = None;
+                self.state = AppState::Compare;
+            }
+
+            KeyCode::Up | KeyCode::Char('k') => diff.scroll_by(-1),
+            KeyCode::Down | KeyCode::Char('j') => diff.scroll_by(1),
+            KeyCode::PageUp => diff.scroll_by(-page),
+            KeyCode::PageDown | KeyCode::Char(' ') => diff.scroll_by(page),
+            KeyCode::Home | KeyCode::Char('g') => diff.scroll_by(i32::MIN / 2),
+            KeyCode::End | KeyCode::Char('G') => diff.scroll_by(i32::MAX / 2),
+
+            // Hunk navigation
+            KeyCode::Char('n') | KeyCode::Char(']') => diff.next_hunk(),
+            KeyCode::Char('N') | KeyCode::Char('p') | KeyCode::Char('[') => diff.prev_hunk(),
+
+            _ => {}
+        }
+
+        None
+    }
+
+    /// Open the directory under the comparison cursor in both snapshots
+    fn enter_compare_dir(&mut self) -> Option<Command>
+    {
+        let compare = self.compare.as_mut()?;
+        let row = compare.selected().filter(|r| r.is_dir())?.clone();
+        let path = row.node()?.path.clone();
+
+        // A side without the directory has nothing to list
+        let absent = |node: &Option<FileNode>| match node
+        {
+            Some(n) if n.is_dir() => None,
+            Some(_) => Some("Not a directory"),
+            None => Some("Not in this snapshot"),
+        };
+        let mut sides = vec![(compare.left_id.clone(), absent(&row.left))];
+        if compare.local.is_none()
+        {
+            sides.push((compare.right_id.clone(), absent(&row.right)));
+        }
+        for (snapshot_id, reason) in sides
+        {
+            if let Some(reason) = reason
+            {
+                compare.absent.insert((snapshot_id, path.clone()), reason.to_string());
+            }
+        }
+        compare.set_path(path, None);
+        self.load_compare()
+    }
+
+    /// Go to the parent directory in both snapshots
+    fn leave_compare_dir(&mut self) -> Option<Command>
+    {
+        let compare = self.compare.as_ref()?;
+        if compare.path.is_empty()
+        {
+            return None;
+        }
+        if compare.local.as_ref().is_some_and(|l| l.base == compare.path)
+        {
+            self.set_status("The comparison with the local directory started here".to_string());
+            return None;
+        }
+
+        // A backed-up path of either snapshot goes back to the paths root
+        let path = compare.path.clone();
+        let is_snapshot_path = self.snapshots
+                                   .iter()
+                                   .filter(|s| s.full_id == compare.left_id || s.full_id == compare.right_id)
+                                   .any(|s| s.paths.iter().any(|p| p.trim_end_matches('/') == path));
+        let (parent, select) = if is_snapshot_path
+        {
+            (String::new(), path)
+        }
+        else
+        {
+            let name = path.rsplit('/').next().unwrap_or_default().to_string();
+            (parent_entry(&path).path, name)
+        };
+
+        self.compare.as_mut()?.set_path(parent, Some(select));
+        self.load_compare()
+    }
+
+    /// Open the download dialog for the left or right version of the entry
+    /// under the comparison cursor
+    fn restore_compared(&mut self,
+                        left: bool,
+                        confirming: Option<char>)
+    {
+        let compare = match self.compare
+        {
+            Some(ref c) => c,
+            None => return,
+        };
+        let row = match compare.selected()
+        {
+            Some(r) => r,
+            None => return,
+        };
+
+        // Against a local directory the snapshot version goes back in its place
+        if compare.local.is_some()
+        {
+            if !left
+            {
+                self.set_status("The right side is the local directory".to_string());
+            }
+            else if row.left.is_none()
+            {
+                self.set_status(format!("{} is not in the snapshot", row.name));
+            }
+            else
+            {
+                let rows = vec![row.clone()];
+                self.restore_locally(rows, '<', confirming);
+            }
+            return;
+        }
+
+        let (snapshot_id, node) = if left { (&compare.left_id, &row.left) } else { (&compare.right_id, &row.right) };
+        let node = match node
+        {
+            Some(n) => n,
+            None =>
+            {
+                self.set_status(format!("{} is not in the {} snapshot", row.name, if left { "left" } else { "right" }));
+                return;
+            }
+        };
+
+        self.download_dialog = Some(DownloadDialog::new(snapshot_id.clone(),
+                                                        node,
+                                                        &self.last_download_dir,
+                                                        DownloadMode::Restore));
+        self.state = AppState::DownloadDialog;
+    }
+
+    /// Restore every entry missing or different in the local directory
+    fn restore_all_locally(&mut self,
+                           confirming: Option<char>)
+    {
+        let compare = match self.compare
+        {
+            Some(ref c) if c.local.is_some() => c,
+            _ =>
+            {
+                self.set_status("Restoring all differences works against a local directory (L)".to_string());
+                return;
+            }
+        };
+        let rows: Vec<_> = compare.restorable().into_iter().cloned().collect();
+        if rows.is_empty()
+        {
+            self.set_status("Nothing missing or different here".to_string());
+            return;
+        }
+        self.restore_locally(rows, 'R', confirming);
+    }
+
+    /// Restore snapshot entries into the compared local directory, once
+    /// `key` has been pressed twice (local files are overwritten)
+    fn restore_locally(&mut self,
+                       rows: Vec<CompareRow>,
+                       key: char,
+                       confirming: Option<char>)
+    {
+        let compare = match self.compare
+        {
+            Some(ref mut c) => c,
+            None => return,
+        };
+        let target = match compare.local
+        {
+            Some(ref local) => local.dir_for(&compare.path),
+            None => return,
+        };
+
+        if confirming != Some(key)
+        {
+            compare.confirm = Some(key);
+            let what = match rows.as_slice()
+            {
+                [row] => row.name.clone(),
+                _ => format!("{} entries", rows.len()),
+            };
+            self.set_status(format!("Restore {} into {}? Press {} again to confirm", what, target.display(), key));
+            return;
+        }
+
+        // Restoring from the directory itself puts the entries right into the
+        // target; one run for all of them loads the index and locks only once
+        let kind = JobKind::Restore {
+            snapshot_id: format!("{}:{}", compare.left_id, compare.path),
+            paths: rows.iter().map(|row| format!("/{}", row.name)).collect(),
+            target: target.to_string_lossy().to_string(),
+        };
+        let id = self.queue_job(kind);
+        if let Some(ref mut compare) = self.compare
+        {
+            compare.restore_jobs.push(id);
+        }
+    }
+
+    /// True when the Files panel shows a subset of `files` (search or hidden dotfiles)
+    fn filter_active(&self) -> bool
+    {
+        !self.search_query.is_empty() || self.state == AppState::FileSearch || !self.show_hidden
+    }
+
+    /// Recompute filtered indices for the current query (cursor untouched).
+    ///
+    /// Matches are ranked by fuzzy score, best first; without a query the
+    /// directory order is kept.
+    fn update_filtered_files(&mut self)
+    {
+        self.filtered_files.clear();
+
+        let mut ranked: Vec<(i32, usize)> = Vec::new();
+        for (i, file) in self.files.iter().enumerate()
+        {
+            // Always include ".." entry
+            if file.name == ".."
+            {
+                self.filtered_files.push(i);
+                continue;
+            }
+
+            if !self.show_hidden && file.name.starts_with('.')
+            {
+                continue;
+            }
+
+            if let Some(m) = self.search_match(&file.name)
+            {
+                ranked.push((m.score, i));
+            }
+        }
+
+        // Stable: equal scores keep the directory order
+        ranked.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+        self.filtered_files.extend(ranked.into_iter().map(|(_, i)| i));
+    }
+
+    /// Match a name against the search query (everything matches an empty query)
+    pub fn search_match(&self,
+                        name: &str)
+                        -> Option<SearchMatch>
+    {
+        if self.search_query.is_empty()
+        {
+            return Some(SearchMatch { score: 0, positions: Vec::new() });
+        }
+        self.search_matcher.find(name)
+    }
+
+    /// Change the sort order: next key, reverse, or directories first
+    fn handle_sort_action(&mut self,
+                          action: Action)
+    {
+        match action
+        {
+            Action::SortNext => self.file_sort.key = self.file_sort.key.next(),
+            Action::SortReverse => self.file_sort.reverse = !self.file_sort.reverse,
+            Action::DirsFirst => self.file_sort.dirs_first = !self.file_sort.dirs_first,
+            _ => return,
+        }
+        self.sort_files();
+    }
+
+    /// Sort files by the current sort order, keeping the cursor on the same entry
+    fn sort_files(&mut self)
+    {
+        let selected = self.file_at_cursor().map(|f| f.path.clone());
+
+        // ".." always stays on top
+        let start = if self.files.first().is_some_and(|f| f.name == "..") { 1 } else { 0 };
+
+        let mut files = std::mem::take(&mut self.files);
+        self.sort_listing(&mut files[start..]);
+        self.files = files;
+
+        // Expanded tree directories follow the same order
+        let mut expanded = std::mem::take(&mut self.expanded);
+        for children in expanded.values_mut().flatten()
+        {
+            self.sort_listing(children);
+        }
+        self.expanded = expanded;
+
+        if self.filter_active()
+        {
+            self.update_filtered_files();
+        }
+
+        if let Some(path) = selected
+        {
+            if let Some(pos) = self.visible_files().iter().position(|f| f.path == path)
+            {
+                self.file_cursor = pos;
+            }
+        }
+    }
+
+    /// Recursive size of a directory in the current snapshot, if known
+    pub fn dir_size(&self,
+                    path: &str)
+                    -> Option<&DirSize>
+    {
+        self.current_snapshot_id.as_ref()
+            .and_then(|id| self.dir_sizes.get(id))
+            .and_then(|idx| idx.sizes.get(path))
+    }
+
+    /// True while directory sizes for the current snapshot are still being computed
+    pub fn dir_sizes_pending(&self) -> bool
+    {
+        self.current_snapshot_id.as_ref()
+            .and_then(|id| self.dir_sizes.get(id))
+            .is_some_and(|idx| !idx.complete)
+    }
+
+    /// Store a batch of directory sizes from the background listing
+    pub fn add_dir_sizes(&mut self,
+                         run: usize,
+                         snapshot_id: &str,
+                         sizes: Vec<(String, DirSize)>)
+    {
+        // Ignore batches from a listing that was cancelled
+        match self.dir_sizes.get_mut(snapshot_id)
+        {
+            Some(index) if index.run == run => index.sizes.extend(sizes),
+            _ => return,
+        }
+
+        if self.file_sort.key == SortKey::Size && self.current_snapshot_id.as_deref() == Some(snapshot_id)
+        {
+            self.sort_files();
+        }
+    }
+
+    /// Mark the background listing for a snapshot as finished
+    pub fn finish_dir_sizes(&mut self,
+                            run: usize,
+                            snapshot_id: &str,
+                            result: Result<(), String>)
+    {
+        if !self.dir_sizes.get(snapshot_id).is_some_and(|idx| idx.run == run)
+        {
+            return;
+        }
+        match result
+        {
+            Ok(()) =>
+            {
+                if let Some(index) = self.dir_sizes.get_mut(snapshot_id)
+                {
+                    index.complete = true;
+                }
+            }
+            Err(e) =>
+            {
+                // Drop partial results so selecting the snapshot again retries
+                self.dir_sizes.remove(snapshot_id);
+                self.set_status(e);
+            }
+        }
+    }
+
+    /// Get the currently visible files (filtered or all)
+    pub fn visible_files(&self) -> Vec<&FileNode>
+    {
+        if self.tree_active()
+        {
+            self.tree_rows().into_iter().map(|row| row.node).collect()
+        }
+        else if !self.filter_active()
         {
             self.files.iter().collect()
         }
         else
         {
-            self.filtered_files
-                .iter()
-                .filter_map(|&i| self.files.get(i))
-                .collect()
+            self.filtered_files
+                .iter()
+                .filter_map(|&i| self.files.get(i))
+                .collect()
+        }
+    }
+
+    /// Get file at cursor position (respecting filter)
+    pub fn file_at_cursor(&self) -> Option<&FileNode>
+    {
+        if self.tree_active()
+        {
+            self.tree_rows().get(self.file_cursor).map(|row| row.node)
+        }
+        else if !self.filter_active()
+        {
+            self.files.get(self.file_cursor)
+        }
+        else
+        {
+            self.filtered_files
+                .get(self.file_cursor)
+                .and_then(|&i| self.files.get(i))
+        }
+    }
+
+    /// Handle key events in download dialog
+    fn handle_download_dialog_key(&mut self,
+                                   key: KeyEvent)
+                                   -> Option<Command>
+    {
+        let size_warning = self.download_dialog.as_ref().and_then(|d| self.download_warning(d));
+
+        let dialog = match &mut self.download_dialog
+        {
+            Some(d) => d,
+            None => return None,
+        };
+
+        // Any key dismisses a pending size warning; Enter on Download confirms it
+        let confirmed = dialog.warning.take().is_some();
+
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+
+        // Global keys (work regardless of focus)
+        match key.code
+        {
+            // Esc: cancel dialog
+            KeyCode::Esc =>
+            {
+                self.close_download_dialog();
+                return None;
+            }
+
+            // Tab / Shift+Tab: cycle focus
+            KeyCode::Tab | KeyCode::BackTab =>
+            {
+                if shift || key.code == KeyCode::BackTab
+                {
+                    dialog.focus_prev();
+                }
+                else
+                {
+                    dialog.focus_next();
+                }
+                return None;
+            }
+
+            _ => {}
+        }
+
+        // Focus-specific keys
+        match dialog.focus
+        {
+            DialogFocus::PathPicker =>
+            {
+                match (key.code, ctrl)
+                {
+                    // Navigate directory listing
+                    (KeyCode::Down, _) => dialog.select_next(),
+                    (KeyCode::Up, _) => dialog.select_prev(),
+
+                    // Enter (without Ctrl): navigate into selected directory
+                    (KeyCode::Enter, false) => dialog.enter_selected(),
+
+                    // Text cursor movement
+                    (KeyCode::Left, _) => dialog.cursor_left(),
+                    (KeyCode::Right, _) => dialog.cursor_right(),
+                    (KeyCode::Home, _) => dialog.cursor_home(),
+                    (KeyCode::End, _) => dialog.cursor_end(),
+
+                    // Text editing
+                    (KeyCode::Backspace, _) => dialog.backspace(),
+                    (KeyCode::Delete, _) => dialog.delete(),
+                    (KeyCode::Char(c), false) => dialog.insert_char(c),
+
+                    _ => {}
+                }
+            }
+
+            DialogFocus::DownloadButton =>
+            {
+                if key.code == KeyCode::Enter
+                {
+                    if !confirmed && size_warning.is_some()
+                    {
+                        dialog.warning = size_warning;
+                        return None;
+                    }
+
+                    let snapshot_id = dialog.snapshot_id.clone();
+                    let target = dialog.confirmed_path();
+                    let path = dialog.source_path.clone();
+                    let kind = match dialog.mode
+                    {
+                        // The typed directory as is: a missing one shows everything as missing
+                        DownloadMode::CompareLocal =>
+                        {
+                            let root = PathBuf::from(DownloadDialog::expand_tilde(&dialog.input_text));
+                            self.close_download_dialog();
+                            return self.open_local_compare(snapshot_id, path, root);
+                        }
+                        DownloadMode::Restore => JobKind::Restore { snapshot_id, paths: vec![path], target: target.clone() },
+                        DownloadMode::Dump => JobKind::Dump {
+                            snapshot_id,
+                            path,
+                            target_file: dialog.dump_target_file(),
+                            size: if dialog.source_is_dir { None } else { dialog.source_size },
+                        },
+                    };
+                    self.last_download_dir = target;
+                    self.close_download_dialog();
+                    self.queue_job(kind);
+                }
+            }
+
+            DialogFocus::CancelButton =>
+            {
+                if key.code == KeyCode::Enter
+                {
+                    self.close_download_dialog();
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Close the download dialog, back to the comparison it was opened from if any
+    fn close_download_dialog(&mut self)
+    {
+        self.download_dialog = None;
+        self.state = if self.compare.is_some() { AppState::Compare } else { AppState::Ready };
+    }
+
+    /// Mouse in the download dialog: pick directories and click buttons
+    fn handle_download_dialog_mouse(&mut self,
+                                    column: u16,
+                                    row: u16,
+                                    wheel: Option<Movement>,
+                                    double_click: bool)
+                                    -> Option<Command>
+    {
+        let button = self.mouse_areas.button_at(column, row);
+        let dialog = self.download_dialog.as_mut()?;
+
+        // Clicking a button is the same as focusing it and pressing Enter
+        if let (None, Some(focus)) = (wheel, button)
+        {
+            dialog.focus = focus;
+            return self.handle_download_dialog_key(KeyEvent::from(KeyCode::Enter));
+        }
+
+        if let Some(ref listing) = self.mouse_areas.dialog_listing
+        {
+            if listing.contains(column, row)
+            {
+                match wheel
+                {
+                    Some(Movement::Up(n)) => (0..n).for_each(|_| dialog.select_prev()),
+                    Some(Movement::Down(n)) => (0..n).for_each(|_| dialog.select_next()),
+                    _ =>
+                    {
+                        dialog.focus = DialogFocus::PathPicker;
+                        if let Some(i) = listing.item_at(column, row)
+                        {
+                            dialog.selected = i;
+                            if double_click
+                            {
+                                dialog.enter_selected();
+                            }
+                        }
+                    }
+                }
+                return None;
+            }
+        }
+
+        if wheel.is_none() && self.mouse_areas.dialog_input.is_some_and(|a| a.contains(Position::new(column, row)))
+        {
+            dialog.focus = DialogFocus::PathPicker;
+        }
+        None
+    }
+
+    /// Warning text if a download exceeds the configured confirmation thresholds
+    fn download_warning(&self,
+                        dialog: &DownloadDialog)
+                        -> Option<String>
+    {
+        if dialog.mode == DownloadMode::CompareLocal
+        {
+            return None;
+        }
+        let (bytes, files) = if dialog.source_is_dir
+        {
+            let size = self.dir_sizes.get(&dialog.snapshot_id).and_then(|idx| idx.sizes.get(&dialog.source_path));
+            match size
+            {
+                Some(size) => (size.bytes, Some(size.files)),
+                None => return None, // Size not known (yet), nothing to compare
+            }
+        }
+        else
+        {
+            (dialog.source_size.unwrap_or(0), None)
+        };
+
+        if self.confirm_download_bytes.is_some_and(|limit| bytes > limit)
+        {
+            return Some(format!("{} is {}. Press Enter again to confirm",
+                                dialog.source_name(), format_bytes(bytes)));
+        }
+        if let (Some(limit), Some(files)) = (self.confirm_download_files, files)
+        {
+            if files > limit
+            {
+                return Some(format!("{} has {} files. Press Enter again to confirm",
+                                    dialog.source_name(), files));
+            }
+        }
+        None
+    }
+
+    /// Open the download dialog
+    fn open_download_dialog(&mut self,
+                            mode: DownloadMode)
+                            -> Option<Command>
+    {
+        if self.focused_panel != Panel::Files
+        {
+            return None;
+        }
+
+        let snapshot_id = self.current_snapshot_id.clone()?;
+        if let Some(file) = self.file_at_cursor()
+        {
+            // Don't download ".." entry
+            if file.name == ".."
+            {
+                return None;
+            }
+
+            self.download_dialog = Some(DownloadDialog::new(
+                snapshot_id,
+                file,
+                &self.last_download_dir,
+                mode,
+            ));
+            self.state = AppState::DownloadDialog;
+        }
+
+        None
+    }
+
+    /// Add a job to the queue (it starts once a slot is free)
+    pub fn queue_job(&mut self,
+                     kind: JobKind)
+                     -> usize
+    {
+        let job = Job::new(self.next_job_id, kind);
+        self.next_job_id += 1;
+        self.set_status(format!("Queued job #{}: {} {}", job.id, job.kind.verb(), job.kind.description()));
+        let id = job.id;
+        self.jobs.push(job);
+        id
+    }
+
+    /// Mark queued jobs as running up to the concurrency limit and return them for spawning
+    pub fn start_ready_jobs(&mut self) -> Vec<(usize, JobKind)>
+    {
+        let running = self.jobs.iter().filter(|j| j.status == JobStatus::Running).count();
+        let mut free = self.max_jobs.saturating_sub(running);
+        let mut started = Vec::new();
+
+        for job in self.jobs.iter_mut()
+        {
+            if free == 0
+            {
+                break;
+            }
+            if job.status == JobStatus::Queued
+            {
+                job.start();
+                started.push((job.id, job.kind.clone()));
+                free -= 1;
+            }
+        }
+
+        started
+    }
+
+    /// Update progress of a running job
+    pub fn set_job_progress(&mut self,
+                            id: usize,
+                            progress: f64)
+    {
+        if let Some(job) = self.jobs.iter_mut().find(|j| j.id == id)
+        {
+            job.progress = Some(progress);
+        }
+    }
+
+    /// Read the local side of a comparison again once a restore into it finishes
+    pub fn reload_local_compare(&mut self,
+                                job_id: usize)
+                                -> Option<Command>
+    {
+        let compare = self.compare.as_mut()?;
+        let pos = compare.restore_jobs.iter().position(|&id| id == job_id)?;
+        compare.restore_jobs.remove(pos);
+        Self::read_local_compare(compare)
+    }
+
+    /// Record the result of a job
+    pub fn finish_job(&mut self,
+                      id: usize,
+                      result: Result<(), String>,
+                      duration: std::time::Duration)
+    {
+        let message = match &result
+        {
+            Ok(()) => format!("Job #{} done", id),
+            Err(e) => format!("Job #{} failed: {}", id, e.lines().next().unwrap_or("")),
+        };
+        if let Some(job) = self.jobs.iter_mut().find(|j| j.id == id)
+        {
+            job.finish(result, duration);
+        }
+        self.set_status(message);
+    }
+
+    /// Handle Jobs panel actions: retry failed, clear finished, adjust concurrency
+    fn handle_jobs_action(&mut self,
+                          action: Action)
+    {
+        match action
+        {
+            Action::RetryJob =>
+            {
+                if let Some(job) = self.jobs.get_mut(self.job_cursor)
+                {
+                    if job.retry()
+                    {
+                        let id = job.id;
+                        self.set_status(format!("Retrying job #{}", id));
+                    }
+                }
+            }
+            Action::ClearJobs =>
+            {
+                self.jobs.retain(|j| !j.is_finished());
+                self.job_cursor = self.job_cursor.min(self.jobs.len().saturating_sub(1));
+                if self.jobs.is_empty()
+                {
+                    self.focused_panel = Panel::CommandLog;
+                }
+            }
+            Action::MoreJobs =>
+            {
+                self.max_jobs += 1;
+                self.set_status(format!("Running up to {} jobs at once", self.max_jobs));
+            }
+            Action::FewerJobs =>
+            {
+                self.max_jobs = (self.max_jobs - 1).max(1);
+                self.set_status(format!("Running up to {} jobs at once", self.max_jobs));
+            }
+            _ => {}
+        }
+    }
+
+    /// Handle key events in the retention policy dialog
+    fn handle_forget_dialog_key(&mut self,
+                                key: KeyCode)
+                                -> Option<Command>
+    {
+        let dialog = self.forget_dialog.as_mut()?;
+
+        match key
+        {
+            KeyCode::Esc =>
+            {
+                self.forget_dialog = None;
+                self.state = AppState::Ready;
+            }
+            KeyCode::Tab | KeyCode::Down => dialog.focus_next(),
+            KeyCode::BackTab | KeyCode::Up => dialog.focus_prev(),
+            KeyCode::Backspace => dialog.backspace(),
+            KeyCode::Char(c) => dialog.insert_char(c),
+            KeyCode::Enter =>
+            {
+                match dialog.policy()
+                {
+                    Ok(policy) =>
+                    {
+                        self.forget_policy = policy.clone();
+                        self.forget_dialog = None;
+                        self.state = AppState::Loading;
+                        return Some(Command::SimulateForget { policy });
+                    }
+                    Err(e) => dialog.error = Some(e),
+                }
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    /// Store the result of a policy simulation
+    pub fn set_forget_plan(&mut self,
+                           policy: ForgetPolicy,
+                           groups: Vec<ForgetGroup>)
+    {
+        let plan = ForgetPlan::from_groups(policy, groups);
+        self.set_status(format!("Policy would keep {} and remove {} snapshots (dry run)",
+                                plan.kept.len(),
+                                plan.removed.len()));
+        self.forget_plan = Some(plan);
+        self.state = AppState::Ready;
+    }
+
+    /// Handle key events in the check prompt (--read-data-subset value)
+    fn handle_check_prompt_key(&mut self,
+                               key: KeyCode)
+                               -> Option<Command>
+    {
+        match key
+        {
+            KeyCode::Esc =>
+            {
+                self.state = AppState::Ready;
+            }
+            KeyCode::Backspace =>
+            {
+                self.check_subset_input.pop();
+            }
+            KeyCode::Char(c) if c.is_ascii_digit() || matches!(c, '%' | '/' | '.' | 'K' | 'M' | 'G' | 'T') =>
+            {
+                self.check_subset_input.push(c);
+            }
+            KeyCode::Enter =>
+            {
+                let subset = self.check_subset_input.trim();
+                let read_data_subset = if subset.is_empty() { None } else { Some(subset.to_string()) };
+
+                self.check = Some(CheckRun::new(read_data_subset.clone()));
+                self.focused_panel = Panel::Check;
+                self.state = AppState::Ready;
+                return Some(Command::Check { read_data_subset });
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    /// Record the end of a repository check
+    pub fn finish_check(&mut self,
+                        result: Result<(), String>)
+    {
+        if let Some(ref mut check) = self.check
+        {
+            check.finish(result);
+            let message = match &check.status
+            {
+                CheckStatus::Clean => "Repository check finished: no errors found".to_string(),
+                CheckStatus::ErrorsFound => format!("Repository check found {} errors", check.error_count),
+                CheckStatus::Failed(e) => format!("Repository check failed: {}", e.lines().next().unwrap_or("")),
+                CheckStatus::Running => return,
+            };
+            self.set_status(message);
+        }
+    }
+
+    /// Open the stats view for the selected snapshot and the repository
+    fn open_stats(&mut self) -> Option<Command>
+    {
+        // Files/log panels show the snapshot being browsed, Snapshots panel the one under the cursor
+        let snapshot_id = if self.focused_panel == Panel::Snapshots
+        {
+            self.snapshots.get(self.snapshot_cursor).map(|s| s.full_id.clone())
+        }
+        else
+        {
+            self.current_snapshot_id.clone()
+                .or_else(|| self.snapshots.get(self.snapshot_cursor).map(|s| s.full_id.clone()))
+        };
+
+        let mut targets = vec![StatsTarget::Repository];
+        if let Some(ref id) = snapshot_id
+        {
+            targets.insert(0, StatsTarget::Snapshot(id.clone()));
         }
+
+        self.stats_snapshot_id = snapshot_id;
+        self.state = AppState::Stats;
+        self.request_stats(targets)
     }
 
-    /// Get file at cursor position (respecting filter)
-    pub fn file_at_cursor(&self) -> Option<&FileNode>
+    /// Build a stats command for every target/mode that isn't loaded or loading
+    /// (failed results are retried)
+    fn request_stats(&mut self,
+                     targets: Vec<StatsTarget>)
+                     -> Option<Command>
     {
-        if self.search_query.is_empty() && self.state != AppState::FileSearch
+        let mut requests = Vec::new();
+
+        for target in targets
         {
-            self.files.get(self.file_cursor)
+            let entry = self.stats_cache.entry(target.clone()).or_default();
+            for mode in [StatsMode::RestoreSize, StatsMode::RawData]
+            {
+                let slot = entry.slot_mut(mode);
+                if matches!(slot, None | Some(StatsStatus::Failed(_)))
+                {
+                    *slot = Some(StatsStatus::Loading);
+                    requests.push((target.clone(), mode));
+                }
+            }
+        }
+
+        if requests.is_empty()
+        {
+            None
         }
         else
         {
-            self.filtered_files
-                .get(self.file_cursor)
-                .and_then(|&i| self.files.get(i))
+            Some(Command::LoadStats { requests })
         }
     }
 
-    /// Handle key events in download dialog
-    fn handle_download_dialog_key(&mut self,
-                                   key: KeyEvent)
-                                   -> Option<Command>
+    /// Handle key events in the stats view
+    fn handle_stats_key(&mut self,
+                        key: KeyCode)
+                        -> Option<Command>
     {
-        let dialog = match &mut self.download_dialog
+        match key
         {
-            Some(d) => d,
-            None => return None,
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('s') =>
+            {
+                self.state = AppState::Ready;
+                None
+            }
+            // Repository stats change when snapshots are added/forgotten
+            KeyCode::Char('r') =>
+            {
+                self.stats_cache.remove(&StatsTarget::Repository);
+                self.request_stats(vec![StatsTarget::Repository])
+            }
+            _ => None,
+        }
+    }
+
+    /// Store a stats result from a background task
+    pub fn set_stats(&mut self,
+                     target: StatsTarget,
+                     mode: StatsMode,
+                     result: Result<ResticStats, String>)
+    {
+        let status = match result
+        {
+            Ok(stats) => StatsStatus::Loaded(stats),
+            Err(e) => StatsStatus::Failed(e),
         };
+        let entry = self.stats_cache.entry(target).or_default();
+        *entry.slot_mut(mode) = Some(status);
+    }
 
-        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
-        let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+    /// Load saved bookmarks. If the file can't be read it is left alone
+    /// (bookmarks made in this session aren't saved).
+    pub fn load_bookmarks(&mut self,
+                          path: Option<PathBuf>)
+    {
+        let path = match path
+        {
+            Some(p) => p,
+            None => return,
+        };
+        match bookmark::load(&path)
+        {
+            Ok(bookmarks) =>
+            {
+                self.bookmarks = bookmarks;
+                self.bookmarks_path = Some(path);
+            }
+            Err(e) => self.set_status(format!("Bookmarks not loaded: {}", e)),
+        }
+    }
 
-        // Global keys (work regardless of focus)
-        match key.code
+    /// Write the bookmarks file after a change
+    fn save_bookmarks(&mut self)
+    {
+        if let Some(ref path) = self.bookmarks_path
         {
-            // Esc: cancel dialog
-            KeyCode::Esc =>
+            if let Err(e) = bookmark::save(path, &self.bookmarks)
             {
-                self.download_dialog = None;
-                self.state = AppState::Ready;
-                return None;
+                self.set_status(format!("Bookmarks not saved: {}", e));
             }
+        }
+    }
 
-            // Tab / Shift+Tab: cycle focus
-            KeyCode::Tab | KeyCode::BackTab =>
+    /// Bookmark the current directory (at the paths root: the path under the
+    /// cursor), optionally pinned to the snapshot being browsed
+    fn add_bookmark(&mut self,
+                    pinned: bool)
+    {
+        let snapshot = match self.current_snapshot_id
+                                 .as_ref()
+                                 .and_then(|id| self.snapshots.iter().find(|s| &s.full_id == id))
+        {
+            Some(s) => s,
+            None => return,
+        };
+        let path = if self.current_path.is_empty()
+        {
+            match self.file_at_cursor()
             {
-                if shift || key.code == KeyCode::BackTab
-                {
-                    dialog.focus_prev();
-                }
-                else
-                {
-                    dialog.focus_next();
-                }
-                return None;
+                Some(f) => f.path.clone(),
+                None => return,
             }
+        }
+        else
+        {
+            self.current_path.clone()
+        };
+
+        let bookmark = Bookmark {
+            host: snapshot.hostname.clone(),
+            path,
+            snapshot: if pinned { Some(snapshot.full_id.clone()) } else { None },
+        };
+        let label = match bookmark.snapshot
+        {
+            Some(_) => format!("{}:{} in snapshot {}", bookmark.host, bookmark.path, snapshot.display_id()),
+            None => format!("{}:{}", bookmark.host, bookmark.path),
+        };
 
+        if self.bookmarks.contains(&bookmark)
+        {
+            self.set_status(format!("Already bookmarked: {}", label));
+            return;
+        }
+        self.bookmarks.push(bookmark);
+        self.set_status(format!("Bookmarked {}", label));
+        self.save_bookmarks();
+    }
+
+    /// Handle key events in the bookmark picker
+    fn handle_bookmarks_key(&mut self,
+                            key: KeyCode)
+                            -> Option<Command>
+    {
+        match key
+        {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('\'') =>
+            {
+                self.state = AppState::Ready;
+            }
+            KeyCode::Up | KeyCode::Char('k') =>
+            {
+                self.bookmark_cursor = self.bookmark_cursor.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') =>
+            {
+                self.bookmark_cursor = (self.bookmark_cursor + 1).min(self.bookmarks.len().saturating_sub(1));
+            }
+            KeyCode::Enter => return self.open_bookmark(self.bookmark_cursor, false),
+            KeyCode::Char('c') => return self.open_bookmark(self.bookmark_cursor, true),
+            KeyCode::Char('d') | KeyCode::Delete if self.bookmark_cursor < self.bookmarks.len() =>
+            {
+                let removed = self.bookmarks.remove(self.bookmark_cursor);
+                self.bookmark_cursor = self.bookmark_cursor.min(self.bookmarks.len().saturating_sub(1));
+                self.set_status(format!("Removed bookmark {}:{}", removed.host, removed.path));
+                self.save_bookmarks();
+            }
             _ => {}
         }
 
-        // Focus-specific keys
-        match dialog.focus
+        None
+    }
+
+    /// Mouse in the bookmark picker: click selects, double-click opens
+    fn handle_bookmarks_mouse(&mut self,
+                              column: u16,
+                              row: u16,
+                              wheel: Option<Movement>,
+                              double_click: bool)
+                              -> Option<Command>
+    {
+        let listing = self.mouse_areas.bookmarks.as_ref()?;
+        if !listing.contains(column, row)
         {
-            DialogFocus::PathPicker =>
+            return None;
+        }
+        let max = self.bookmarks.len().saturating_sub(1);
+
+        match wheel
+        {
+            Some(Movement::Up(n)) => self.bookmark_cursor = self.bookmark_cursor.saturating_sub(n as usize),
+            Some(Movement::Down(n)) => self.bookmark_cursor = (self.bookmark_cursor + n as usize).min(max),
+            _ =>
             {
-                match (key.code, ctrl)
+                self.bookmark_cursor = listing.item_at(column, row)?;
+                if double_click
                 {
-                    // Navigate directory listing
-                    (KeyCode::Down, _) => dialog.select_next(),
-                    (KeyCode::Up, _) => dialog.select_prev(),
+                    return self.open_bookmark(self.bookmark_cursor, false);
+                }
+            }
+        }
+        None
+    }
 
-                    // Enter (without Ctrl): navigate into selected directory
-                    (KeyCode::Enter, false) => dialog.enter_selected(),
+    /// Open a bookmark in its snapshot (pinned, else the latest of its host
+    /// containing the path), or in the currently selected snapshot
+    fn open_bookmark(&mut self,
+                     index: usize,
+                     in_current: bool)
+                     -> Option<Command>
+    {
+        let bookmark = self.bookmarks.get(index)?.clone();
+        let link = if in_current
+        {
+            let current = self.current_snapshot_id
+                              .clone()
+                              .or_else(|| self.snapshots.get(self.snapshot_cursor).map(|s| s.full_id.clone()));
+            DeepLink {
+                snapshot: Some(current?),
+                host: None,
+                path: Some(bookmark.path),
+            }
+        }
+        else
+        {
+            DeepLink {
+                snapshot: bookmark.snapshot,
+                host: Some(bookmark.host),
+                path: Some(bookmark.path),
+            }
+        };
 
-                    // Text cursor movement
-                    (KeyCode::Left, _) => dialog.cursor_left(),
-                    (KeyCode::Right, _) => dialog.cursor_right(),
-                    (KeyCode::Home, _) => dialog.cursor_home(),
-                    (KeyCode::End, _) => dialog.cursor_end(),
+        self.state = AppState::Ready;
+        let commands = self.open_deep_link(&link);
+        if commands.is_empty()
+        {
+            None
+        }
+        else
+        {
+            Some(Command::Batch(commands))
+        }
+    }
 
-                    // Text editing
-                    (KeyCode::Backspace, _) => dialog.backspace(),
-                    (KeyCode::Delete, _) => dialog.delete(),
-                    (KeyCode::Char(c), false) => dialog.insert_char(c),
+    /// Preview to fetch for the entry under the cursor in the columns layout.
+    ///
+    /// Waits until the cursor has rested on the entry for PREVIEW_DELAY so
+    /// scrolling through a directory doesn't start a restic run per entry.
+    pub fn preview_command(&mut self) -> Option<Command>
+    {
+        if self.files_layout != FilesLayout::Columns
+            || !matches!(self.state, AppState::Ready | AppState::FileSearch)
+        {
+            return None;
+        }
 
-                    _ => {}
-                }
-            }
+        let snapshot_id = self.current_snapshot_id.clone()?;
+        if let Some(cmd) = self.parent_listing_command(&snapshot_id)
+        {
+            return Some(cmd);
+        }
 
-            DialogFocus::DownloadButton =>
+        let (path, is_dir, size) = self.file_at_cursor()
+                                       .filter(|f| f.name != ".." && (f.is_dir() || f.node_type == "file"))
+                                       .map(|f| (f.path.clone(), f.is_dir(), f.size))?;
+        let key = (snapshot_id.clone(), path.clone());
+        if self.previews.contains_key(&key)
+        {
+            return None;
+        }
+
+        match self.preview_target
+        {
+            Some((ref target, since)) if *target == path =>
             {
-                if key.code == KeyCode::Enter
+                if since.elapsed() < PREVIEW_DELAY
                 {
-                    let target = dialog.confirmed_path();
-                    let source = dialog.source_path.clone();
-                    self.last_download_dir = target.clone();
-                    self.download_dialog = None;
-                    return Some(Command::Download {
-                        path: source,
-                        target,
-                    });
+                    return None;
                 }
             }
+            _ =>
+            {
+                self.preview_target = Some((path, Instant::now()));
+                return None;
+            }
+        }
 
-            DialogFocus::CancelButton =>
+        // Nothing to fetch for an empty file
+        if size == Some(0)
+        {
+            self.previews.insert(key, Preview::Content(Vec::new()));
+            return None;
+        }
+
+        self.previews.insert(key, Preview::Loading);
+        Some(Command::LoadPreview { snapshot_id, path, is_dir })
+    }
+
+    /// Store a preview fetched in the background (and carry on with the
+    /// go-to-path prompt if it was waiting for this listing)
+    pub fn set_preview(&mut self,
+                       snapshot_id: String,
+                       path: String,
+                       result: Result<Preview, String>)
+                       -> Option<Command>
+    {
+        let preview = match result
+        {
+            Ok(Preview::Listing(mut files)) =>
             {
-                if key.code == KeyCode::Enter
+                self.sort_listing(&mut files);
+                Preview::Listing(files)
+            }
+            Ok(preview) => preview,
+            Err(e) =>
+            {
+                // A tree directory that can't be listed folds back up
+                if self.expanded.remove(&path).is_some()
                 {
-                    self.download_dialog = None;
-                    self.state = AppState::Ready;
+                    self.set_status(format!("Cannot expand {}: {}", path, e));
                 }
+                Preview::Failed(e)
+            }
+        };
+        let current = self.current_snapshot_id.as_deref() == Some(snapshot_id.as_str());
+        let compared = self.compare
+                           .as_ref()
+                           .is_some_and(|c| c.path == path && (c.left_id == snapshot_id || c.right_id == snapshot_id));
+        if current
+        {
+            if let (Some(children), Preview::Listing(files)) = (self.expanded.get_mut(&path), &preview)
+            {
+                *children = Some(files.clone());
+            }
+        }
+        self.previews.insert((snapshot_id, path.clone()), preview);
+        if compared
+        {
+            return self.load_compare();
+        }
+
+        let waiting = self.goto_prompt.as_ref().and_then(|p| p.waiting.clone());
+        match waiting
+        {
+            Some(GoToWaiting::Complete(ref dir)) | Some(GoToWaiting::Open(ref dir))
+                if current && *dir == path && self.state == AppState::GoToPath =>
+            {
+                self.goto_step(waiting?)
             }
+            _ => None,
         }
+    }
 
-        None
+    /// Sort a directory listing in the Files panel order
+    fn sort_listing(&self,
+                    files: &mut [FileNode])
+    {
+        let sizes = self.current_snapshot_id.as_ref().and_then(|id| self.dir_sizes.get(id));
+        let size_of = |f: &FileNode| -> u64 {
+            if f.is_dir()
+            {
+                sizes.and_then(|idx| idx.sizes.get(&f.path)).map(|d| d.bytes).unwrap_or(0)
+            }
+            else
+            {
+                f.size.unwrap_or(0)
+            }
+        };
+        let sort = self.file_sort;
+        files.sort_by(|a, b| sort.compare(a, b, size_of));
     }
 
-    /// Open the download dialog
-    fn open_download_dialog(&mut self) -> Option<Command>
+    /// Listing of the current directory's parent for the columns layout
+    pub fn parent_listing(&self) -> Option<&[FileNode]>
     {
-        if self.focused_panel != Panel::Files
+        if self.current_path.is_empty()
         {
             return None;
         }
 
-        if let Some(file) = self.file_at_cursor()
+        // Prefer the navigation stack; fall back to an earlier fetch of the parent
+        let parent = self.parent_dir(&self.current_path);
+        if let Some(cached) = self.nav_stack.last().filter(|c| c.path == parent)
         {
-            // Don't download ".." entry
-            if file.name == ".."
+            return Some(&cached.files);
+        }
+        match self.previews.get(&(self.current_snapshot_id.clone()?, parent))
+        {
+            Some(Preview::Listing(files)) => Some(files),
+            _ => None,
+        }
+    }
+
+    /// Fetch the parent column again once its listing has dropped out of
+    /// the preview cache
+    fn parent_listing_command(&mut self,
+                              snapshot_id: &str)
+                              -> Option<Command>
+    {
+        if self.current_path.is_empty()
+        {
+            return None;
+        }
+
+        let parent = self.parent_dir(&self.current_path);
+        if self.nav_stack.last().is_some_and(|c| c.path == parent)
+        {
+            return None;
+        }
+        let key = (snapshot_id.to_string(), parent.clone());
+        if self.previews.contains_key(&key)
+        {
+            return None;
+        }
+        self.previews.insert(key, Preview::Loading);
+        Some(Command::LoadPreview { snapshot_id: snapshot_id.to_string(), path: parent, is_dir: true })
+    }
+
+    /// True when the Files panel shows a snapshot directory as a tree
+    fn tree_active(&self) -> bool
+    {
+        self.files_layout == FilesLayout::Tree && self.current_snapshot_id.is_some()
+    }
+
+    /// Rows of the tree layout: the current directory with expanded
+    /// subdirectories, filtered by the search query and hidden files
+    pub fn tree_rows(&self) -> Vec<TreeRow<'_>>
+    {
+        let children = |path: &str| -> Option<&[FileNode]> { self.expanded.get(path)?.as_deref() };
+        let keep = |file: &FileNode| self.show_hidden || !file.name.starts_with('.');
+        let matches = |file: &FileNode| self.search_match(&file.name).is_some();
+        let searching = !self.search_query.is_empty();
+
+        tree::flatten(&self.files,
+                      &children,
+                      &keep,
+                      if searching { Some(&matches) } else { None })
+    }
+
+    /// Expand a directory in the tree layout, fetching its children if needed
+    fn expand_dir(&mut self,
+                  path: String)
+                  -> Option<Command>
+    {
+        let snapshot_id = self.current_snapshot_id.clone()?;
+
+        let key = (snapshot_id.clone(), path.clone());
+        match self.previews.get(&key)
+        {
+            Some(Preview::Listing(files)) =>
+            {
+                let mut children = files.clone();
+                self.sort_listing(&mut children);
+                self.expanded.insert(path, Some(children));
+                return None;
+            }
+            Some(Preview::Loading) =>
+            {
+                self.expanded.insert(path, None);
+                return None;
+            }
+            _ => {}
+        }
+        self.expanded.insert(path.clone(), None);
+        self.previews.insert(key, Preview::Loading);
+        Some(Command::LoadPreview { snapshot_id, path, is_dir: true })
+    }
+
+    /// Tree layout: collapse the directory under the cursor, or else its
+    /// parent (moving the cursor there). False at the top level.
+    fn collapse_at_cursor(&mut self) -> bool
+    {
+        let (path, parent) = {
+            let rows = self.tree_rows();
+            let row = match rows.get(self.file_cursor)
+            {
+                Some(r) => r,
+                None => return false,
+            };
+
+            if row.node.is_dir() && self.expanded.contains_key(&row.node.path)
+            {
+                (row.node.path.clone(), None)
+            }
+            else if row.depth == 0
             {
-                return None;
+                return false;
+            }
+            else
+            {
+                let index = rows[..self.file_cursor].iter().rposition(|r| r.depth + 1 == row.depth);
+                match index
+                {
+                    Some(i) => (rows[i].node.path.clone(), Some(i)),
+                    None => return false,
+                }
             }
+        };
 
-            let path = file.path.clone();
-            self.download_dialog = Some(DownloadDialog::new(
-                path,
-                &self.last_download_dir,
-            ));
-            self.state = AppState::DownloadDialog;
+        self.expanded.remove(&path);
+        if let Some(i) = parent
+        {
+            self.file_cursor = i;
         }
-
-        None
+        true
     }
 
     /// Get count of visible files (respecting filter)
-    fn visible_file_count(&self) -> usize
+    pub fn visible_file_count(&self) -> usize
     {
-        if self.search_query.is_empty() && self.state != AppState::FileSearch
+        if self.tree_active()
+        {
+            self.tree_rows().len()
+        }
+        else if !self.filter_active()
         {
             self.files.len()
         }
@@ -990,6 +3586,31 @@ impl App
             {
                 self.adjust_log_scroll(visible_height);
             }
+            Panel::Jobs =>
+            {
+                if self.job_cursor < self.job_scroll
+                {
+                    self.job_scroll = self.job_cursor;
+                }
+                else if self.job_cursor >= self.job_scroll + visible_height
+                {
+                    self.job_scroll = self.job_cursor - visible_height + 1;
+                }
+            }
+            Panel::Check =>
+            {
+                if let Some(ref mut check) = self.check
+                {
+                    if check.cursor < check.scroll
+                    {
+                        check.scroll = check.cursor;
+                    }
+                    else if check.cursor >= check.scroll + visible_height
+                    {
+                        check.scroll = check.cursor - visible_height + 1;
+                    }
+                }
+            }
         }
     }
 
@@ -1018,7 +3639,9 @@ impl App
         {
             Panel::Snapshots => Panel::Files,
             Panel::Files => Panel::CommandLog,
-            Panel::CommandLog => Panel::Snapshots,
+            Panel::CommandLog if !self.jobs.is_empty() => Panel::Jobs,
+            Panel::CommandLog | Panel::Jobs if self.check.is_some() => Panel::Check,
+            Panel::CommandLog | Panel::Jobs | Panel::Check => Panel::Snapshots,
         };
     }
 
@@ -1029,26 +3652,7 @@ impl App
         {
             Panel::Snapshots =>
             {
-                if let Some(snapshot) = self.snapshots.get(self.snapshot_cursor)
-                {
-                    self.current_snapshot_id = Some(snapshot.full_id.clone());
-                    self.current_path = String::new(); // Empty = at paths root
-                    self.focused_panel = Panel::Files;
-                    self.file_cursor = 0;
-                    self.file_scroll = 0;
-                    self.nav_stack.clear();
-                    self.search_query.clear();
-                    self.filtered_files.clear();
-
-                    // Populate files with snapshot paths as directory entries
-                    self.files = snapshot.paths
-                        .iter()
-                        .map(|p| path_entry(p))
-                        .collect();
-
-                    self.state = AppState::Ready;
-                    return None; // No restic command needed
-                }
+                return self.select_snapshot(self.snapshot_cursor);
             }
             Panel::Files =>
             {
@@ -1067,6 +3671,18 @@ impl App
                             return self.go_back();
                         }
 
+                        // The tree layout expands and collapses in place
+                        if self.tree_active()
+                        {
+                            if self.expanded.remove(&path).is_some()
+                            {
+                                return None;
+                            }
+                            return self.expand_dir(path);
+                        }
+
+                        self.remember_location();
+
                         // Push current state to navigation stack
                         self.nav_stack.push(DirCache {
                             path: self.current_path.clone(),
@@ -1078,19 +3694,529 @@ impl App
                         self.current_path = path.clone();
                         self.file_cursor = 0;
                         self.search_query.clear(); // Clear search when navigating
+
+                        // Already listed for the preview column
+                        let key = (self.current_snapshot_id.clone().unwrap_or_default(), path.clone());
+                        if let Some(Preview::Listing(files)) = self.previews.get(&key)
+                        {
+                            self.set_files(files.clone());
+                            return None;
+                        }
+
                         self.state = AppState::Loading;
                         return Some(Command::NavigateDir { path });
                     }
                 }
             }
-            Panel::CommandLog =>
+            Panel::CommandLog | Panel::Jobs | Panel::Check =>
+            {
+                // No action on Enter in command log/jobs/check panels
+            }
+        }
+        None
+    }
+
+    /// Open a snapshot at its paths root in the Files panel
+    fn select_snapshot(&mut self,
+                       index: usize)
+                       -> Option<Command>
+    {
+        self.remember_location();
+        self.open_snapshot(index)
+    }
+
+    /// Show the current directory in the next older/newer snapshot of the
+    /// same host and paths, keeping the filter and the entry under the cursor
+    fn time_travel(&mut self,
+                   newer: bool)
+                   -> Option<Command>
+    {
+        let current_id = self.current_snapshot_id.clone()?;
+        let index = self.snapshots.iter().position(|s| s.full_id == current_id)?;
+        let current = &self.snapshots[index];
+        let same_series = |s: &Snapshot| s.hostname == current.hostname && s.paths == current.paths;
+
+        // Snapshots are sorted newest first
+        let target = if newer
+        {
+            self.snapshots[..index].iter().rposition(same_series)
+        }
+        else
+        {
+            self.snapshots[index + 1..].iter().position(same_series).map(|i| index + 1 + i)
+        };
+        let target = match target
+        {
+            Some(t) => &self.snapshots[t],
+            None =>
+            {
+                self.set_status(format!("No {} snapshot of {}:{}",
+                                        if newer { "newer" } else { "older" },
+                                        current.hostname,
+                                        current.paths.join(",")));
+                return None;
+            }
+        };
+        let status = format!("Snapshot {} ({})", target.display_id(), target.formatted_time(&self.time_format));
+
+        let mut location = self.current_location()?;
+        location.snapshot_id = target.full_id.clone();
+        // ".." leads to the same place anyway; let the cursor start at the top
+        location.selected = self.file_at_cursor().filter(|f| f.name != "..").map(|f| f.path.clone());
+
+        self.remember_location();
+        self.set_status(status);
+        self.restore_location(location)
+    }
+
+    /// Show a snapshot's paths root (without recording the location left)
+    fn open_snapshot(&mut self,
+                     index: usize)
+                     -> Option<Command>
+    {
+        let snapshot = self.snapshots.get(index)?;
+        let snapshot_id = snapshot.full_id.clone();
+
+        // Populate files with snapshot paths as directory entries
+        self.files = snapshot.paths
+            .iter()
+            .map(|p| path_entry(p))
+            .collect();
+
+        self.snapshot_cursor = index;
+        self.current_snapshot_id = Some(snapshot_id.clone());
+        self.current_path = String::new(); // Empty = at paths root
+        self.focused_panel = Panel::Files;
+        self.file_cursor = 0;
+        self.file_scroll = 0;
+        self.nav_stack.clear();
+        self.expanded.clear();
+        self.previews.clear();
+        self.search_query.clear();
+        self.filtered_files.clear();
+        self.sort_files();
+
+        self.state = AppState::Ready;
+
+        // Stop listings for other snapshots; partial results are dropped so
+        // opening those snapshots again starts over
+        self.dir_sizes.retain(|id, idx| {
+            let keep = idx.complete || *id == snapshot_id;
+            if !keep
+            {
+                idx.cancel();
+            }
+            keep
+        });
+
+        // Start computing directory sizes unless already done or running
+        if !self.dir_sizes.contains_key(&snapshot_id)
+        {
+            let index = DirSizeIndex::new(self.next_dir_size_run);
+            self.next_dir_size_run += 1;
+            let command = Command::ComputeDirSizes {
+                run: index.run,
+                snapshot_id: snapshot_id.clone(),
+                cancel: index.cancel_flag(),
+            };
+            self.dir_sizes.insert(snapshot_id, index);
+            return Some(command);
+        }
+        None
+    }
+
+    /// Open the snapshot/path given on the command line.
+    ///
+    /// `latest` (the default) picks the newest snapshot matching the host and
+    /// containing the path; other values are matched as snapshot ID prefixes.
+    pub fn open_deep_link(&mut self,
+                          link: &DeepLink)
+                          -> Vec<Command>
+    {
+        let mut commands = Vec::new();
+
+        let path = link.path.as_deref().map(normalize_snapshot_path);
+        let wanted = link.snapshot.as_deref().unwrap_or("latest");
+
+        let host_matches = |s: &Snapshot| link.host.as_ref().map_or(true, |h| &s.hostname == h);
+        let covers_path = |s: &Snapshot| match path
+        {
+            Some(ref p) => s.paths.iter().any(|sp| is_same_or_under(p, sp)),
+            None => true,
+        };
+
+        let index = if wanted == "latest"
+        {
+            // Snapshots are sorted newest first
+            self.snapshots.iter().position(|s| host_matches(s) && covers_path(s))
+        }
+        else
+        {
+            self.snapshots.iter().position(|s| {
+                host_matches(s) && (s.full_id.starts_with(wanted) || s.short_id.starts_with(wanted))
+            })
+        };
+
+        let index = match index
+        {
+            Some(i) => i,
+            None =>
             {
-                // No action on Enter in command log panel
+                let mut filters = vec![format!("snapshot {}", wanted)];
+                if let Some(ref host) = link.host
+                {
+                    filters.push(format!("host {}", host));
+                }
+                if let Some(ref p) = path
+                {
+                    filters.push(format!("path {}", p));
+                }
+                self.set_error(format!("No snapshot matches {}", filters.join(", ")));
+                return commands;
+            }
+        };
+
+        commands.extend(self.select_snapshot(index));
+
+        if let Some(path) = path
+        {
+            self.current_path = path.clone();
+            self.state = AppState::Loading;
+            commands.push(Command::NavigateDir { path });
+        }
+
+        commands
+    }
+
+    /// Recover from a directory that isn't in the snapshot: go back to where
+    /// we came from (or the snapshot's paths root) and show the error. Other
+    /// failures (repository, password, ...) just show the error dialog.
+    ///
+    /// A location restored from the history or in another snapshot falls back
+    /// to its nearest parent there instead.
+    pub fn navigation_failed(&mut self,
+                             message: String)
+                             -> Option<Command>
+    {
+        if !is_not_found(&message)
+        {
+            self.pending_select = None;
+            self.pending_location = None;
+            self.set_error(message);
+            return None;
+        }
+
+        if let Some(mut location) = self.pending_location.take().filter(|_| !self.current_path.is_empty())
+        {
+            // Report the directory asked for, not the parents tried after it
+            if location.path == self.current_path
+            {
+                let snapshot = self.current_snapshot_id
+                                   .as_ref()
+                                   .and_then(|id| self.snapshots.iter().find(|s| &s.full_id == id))
+                                   .map(|s| s.display_id().to_string())
+                                   .unwrap_or_default();
+                self.set_status(format!("{}: {}", snapshot, message));
             }
+            location.selected = None;
+            location.scroll = 0;
+            location.search_query.clear();
+            self.pending_location = Some(location);
+            self.pending_select = None;
+
+            let parent = self.parent_dir(&self.current_path);
+            let cmd = self.show_dir(parent, String::new());
+            if cmd.is_none()
+            {
+                self.apply_pending_location();
+            }
+            return cmd;
+        }
+
+        if let Some(cached) = self.nav_stack.pop()
+        {
+            self.current_path = cached.path;
+            self.files = cached.files;
+            self.file_cursor = cached.cursor;
+            self.file_scroll = cached.scroll;
+            self.filtered_files.clear();
+            self.search_query.clear();
+            self.sort_files();
         }
+        else
+        {
+            self.show_paths_root();
+        }
+        self.pending_select = None;
+        self.pending_location = None;
+        self.set_error(message);
         None
     }
 
+    /// Show the current snapshot's paths root (its backed-up paths)
+    fn show_paths_root(&mut self)
+    {
+        let paths = match self.current_snapshot_id
+                              .as_ref()
+                              .and_then(|id| self.snapshots.iter().find(|s| &s.full_id == id))
+        {
+            Some(snapshot) => snapshot.paths.clone(),
+            None => return,
+        };
+        self.files = paths.iter().map(|p| path_entry(p)).collect();
+        self.current_path = String::new();
+        self.file_cursor = 0;
+        self.file_scroll = 0;
+        self.filtered_files.clear();
+        self.search_query.clear();
+        self.sort_files();
+    }
+
+    /// Directory that lists `path`: its parent, or the paths root ("") for
+    /// one of the current snapshot's paths
+    fn parent_dir(&self,
+                  path: &str)
+                  -> String
+    {
+        let is_snapshot_path = self.current_snapshot_id
+                                   .as_ref()
+                                   .and_then(|id| self.snapshots.iter().find(|s| &s.full_id == id))
+                                   .is_some_and(|s| s.paths.iter().any(|p| p.trim_end_matches('/') == path));
+        if is_snapshot_path
+        {
+            String::new()
+        }
+        else
+        {
+            parent_entry(path).path
+        }
+    }
+
+    /// Show `dir` of the current snapshot with the cursor on the entry `select`.
+    ///
+    /// The navigation stack is rebuilt from the ancestors of `dir` that are
+    /// cached, so going back from there is instant where possible and
+    /// fetches otherwise.
+    fn navigate_to(&mut self,
+                   dir: String,
+                   select: String)
+                   -> Option<Command>
+    {
+        self.remember_location();
+        self.show_dir(dir, select)
+    }
+
+    /// `navigate_to` without recording the location left
+    fn show_dir(&mut self,
+                dir: String,
+                select: String)
+                -> Option<Command>
+    {
+        self.rebuild_nav_stack(&dir);
+
+        self.focused_panel = Panel::Files;
+        self.state = AppState::Ready;
+        self.pending_select = Some(select);
+
+        if dir.is_empty()
+        {
+            self.show_paths_root();
+            self.select_pending();
+            return None;
+        }
+
+        self.current_path = dir.clone();
+        self.search_query.clear();
+
+        // Already listed for the preview column or the tree
+        let key = (self.current_snapshot_id.clone().unwrap_or_default(), dir.clone());
+        if let Some(Preview::Listing(files)) = self.previews.get(&key)
+        {
+            self.set_files(files.clone());
+            return None;
+        }
+
+        self.file_cursor = 0;
+        self.state = AppState::Loading;
+        Some(Command::NavigateDir { path: dir })
+    }
+
+    /// The Files panel's current directory, cursor and filter (None before a
+    /// snapshot is opened)
+    fn current_location(&self) -> Option<Location>
+    {
+        Some(Location {
+            snapshot_id: self.current_snapshot_id.clone()?,
+            path: self.current_path.clone(),
+            selected: self.file_at_cursor().map(|f| f.path.clone()),
+            scroll: self.file_scroll,
+            search_query: self.search_query.clone(),
+            search_mode: self.search_mode,
+        })
+    }
+
+    /// Record the current location before going somewhere else
+    fn remember_location(&mut self)
+    {
+        if let Some(location) = self.current_location()
+        {
+            self.history.push(location);
+        }
+    }
+
+    /// Go to a location from the history: its snapshot and directory, then
+    /// (once the directory is shown) its filter, cursor and scroll
+    fn restore_location(&mut self,
+                        location: Location)
+                        -> Option<Command>
+    {
+        let mut commands = Vec::new();
+        if self.current_snapshot_id.as_deref() != Some(location.snapshot_id.as_str())
+        {
+            let index = match self.snapshots.iter().position(|s| s.full_id == location.snapshot_id)
+            {
+                Some(i) => i,
+                None =>
+                {
+                    self.history.remove_snapshot(&location.snapshot_id);
+                    self.set_status("Snapshot no longer available".to_string());
+                    return None;
+                }
+            };
+            commands.extend(self.open_snapshot(index));
+        }
+
+        let path = location.path.clone();
+        self.pending_location = Some(location);
+        match self.show_dir(path, String::new())
+        {
+            Some(cmd) => commands.push(cmd),
+            None => self.apply_pending_location(),
+        }
+
+        match commands.len()
+        {
+            0 => None,
+            1 => commands.pop(),
+            _ => Some(Command::Batch(commands)),
+        }
+    }
+
+    /// Restore the filter, cursor and scroll of a location from the history
+    /// once its directory is shown
+    fn apply_pending_location(&mut self)
+    {
+        let location = match self.pending_location.take()
+        {
+            Some(l) => l,
+            None => return,
+        };
+
+        self.search_mode = location.search_mode;
+        self.search_query = location.search_query;
+        self.search_cursor = self.search_query.len();
+        self.apply_search_filter();
+
+        if let Some(path) = location.selected
+        {
+            if let Some(pos) = self.visible_files().iter().position(|f| f.path == path)
+            {
+                self.file_cursor = pos;
+            }
+        }
+        self.file_scroll = location.scroll.min(self.file_cursor);
+    }
+
+    /// Replace the navigation stack with the directories above `dir`, each
+    /// with the cursor on the entry leading down. Directories neither on the
+    /// stack, shown now nor listed before are left out.
+    fn rebuild_nav_stack(&mut self,
+                         dir: &str)
+    {
+        let mut known = std::mem::take(&mut self.nav_stack);
+        known.push(DirCache {
+            path: self.current_path.clone(),
+            files: self.files.clone(),
+            cursor: self.file_cursor,
+            scroll: self.file_scroll,
+        });
+
+        // (ancestor, entry below it), nearest first
+        let mut chain = Vec::new();
+        let mut child = dir.to_string();
+        while !child.is_empty()
+        {
+            let parent = self.parent_dir(&child);
+            if parent == child
+            {
+                break;
+            }
+            chain.push((parent.clone(), child));
+            child = parent;
+        }
+
+        for (path, child) in chain.into_iter().rev()
+        {
+            let mut cache = match known.iter().position(|c| c.path == path)
+            {
+                Some(i) => known.remove(i),
+                None => match self.display_listing(&path)
+                {
+                    Some(files) => DirCache { path, files, cursor: 0, scroll: 0 },
+                    None => continue,
+                },
+            };
+            let pos = cache.files
+                           .iter()
+                           .filter(|f| f.name == ".." || self.show_hidden || !f.name.starts_with('.'))
+                           .position(|f| f.path == child);
+            if let Some(pos) = pos
+            {
+                cache.cursor = pos;
+            }
+            self.nav_stack.push(cache);
+        }
+    }
+
+    /// Entries the Files panel would show for a cached directory ("" = the paths root)
+    fn display_listing(&self,
+                       path: &str)
+                       -> Option<Vec<FileNode>>
+    {
+        let mut files = if path.is_empty()
+        {
+            self.current_snapshot_paths().iter().map(|p| path_entry(p)).collect()
+        }
+        else
+        {
+            let key = (self.current_snapshot_id.clone()?, path.to_string());
+            match self.previews.get(&key)
+            {
+                Some(Preview::Listing(files)) => files.clone(),
+                _ => return None,
+            }
+        };
+
+        self.sort_listing(&mut files);
+        if !path.is_empty()
+        {
+            files.insert(0, parent_entry(path));
+        }
+        Some(files)
+    }
+
+    /// Put the cursor on the entry waiting for its directory, if it is shown
+    fn select_pending(&mut self)
+    {
+        if let Some(path) = self.pending_select.take()
+        {
+            if let Some(pos) = self.visible_files().iter().position(|f| f.path == path)
+            {
+                self.file_cursor = pos;
+            }
+        }
+    }
+
     /// Navigate back (parent directory)
     fn go_back(&mut self) -> Option<Command>
     {
@@ -1099,9 +4225,19 @@ impl App
             return None;
         }
 
-        // Try to pop from navigation stack first (instant, no fetch needed)
-        if let Some(cached) = self.nav_stack.pop()
+        // In the tree layout, collapse before leaving the directory
+        if self.tree_active() && self.collapse_at_cursor()
         {
+            return None;
+        }
+
+        // Try to pop from navigation stack first (instant, no fetch needed).
+        // After a jump (deep search) the stack may skip directories in between.
+        let parent_dir = self.parent_dir(&self.current_path);
+        if self.nav_stack.last().is_some_and(|c| c.path == parent_dir)
+        {
+            self.remember_location();
+            let cached = self.nav_stack.pop()?;
             self.current_path = cached.path;
             self.files = cached.files;
             self.file_cursor = cached.cursor;
@@ -1109,6 +4245,7 @@ impl App
             self.filtered_files.clear();
             self.search_query.clear();
             self.state = AppState::Ready;
+            self.sort_files();
             return None; // No command needed, we restored from cache
         }
 
@@ -1126,7 +4263,10 @@ impl App
             return None;
         }
 
-        self.current_path = parent.path.clone();
+        self.remember_location();
+
+        // Land on the directory we came from
+        self.pending_select = Some(std::mem::replace(&mut self.current_path, parent.path.clone()));
         self.file_cursor = 0;
         self.state = AppState::Loading;
         Some(Command::NavigateDir { path: parent.path })
@@ -1137,6 +4277,14 @@ impl App
     pub fn set_files(&mut self,
                      files: Vec<FileNode>)
     {
+        // The listing doubles as the parent column once we go deeper
+        if let Some(ref snapshot_id) = self.current_snapshot_id
+        {
+            let mut listing = files.clone();
+            self.sort_listing(&mut listing);
+            self.previews.insert((snapshot_id.clone(), self.current_path.clone()), Preview::Listing(listing));
+        }
+
         let mut display_files = files;
 
         // Add parent directory entry if not at paths root
@@ -1152,6 +4300,9 @@ impl App
         self.file_cursor = 0;
         self.file_scroll = 0;
         self.state = AppState::Ready;
+        self.sort_files();
+        self.select_pending();
+        self.apply_pending_location();
     }
 
     /// Set error state
@@ -1175,7 +4326,29 @@ impl App
                            success: bool,
                            error_output: Option<String>)
     {
-        let entry = CommandLogEntry::new(command.clone(), success, error_output.clone());
+        let entry = CommandLogEntry::new(command, success, error_output);
+        self.push_command_log(entry);
+    }
+
+    /// Add a command log entry for a long-running command, with its duration
+    pub fn add_timed_command_log(&mut self,
+                                 command: String,
+                                 success: bool,
+                                 error_output: Option<String>,
+                                 duration: std::time::Duration)
+    {
+        let mut entry = CommandLogEntry::new(command, success, error_output);
+        entry.duration = Some(duration);
+        self.push_command_log(entry);
+    }
+
+    /// Append a log entry (and write it to the log file if configured)
+    fn push_command_log(&mut self,
+                        entry: CommandLogEntry)
+    {
+        let command = &entry.command;
+        let success = entry.success;
+        let error_output = &entry.error_output;
 
         // Write to log file if configured
         if let Some(ref path) = self.log_file_path
@@ -1188,7 +4361,11 @@ impl App
             {
                 let status = if success { "OK" } else { "FAIL" };
                 let timestamp = entry.timestamp.format("%Y-%m-%d %H:%M:%S");
-                let _ = writeln!(file, "[{}] [{}] {}", timestamp, status, command);
+                let _ = match entry.duration
+                {
+                    Some(d) => writeln!(file, "[{}] [{}] {} ({})", timestamp, status, command, format_duration(d)),
+                    None => writeln!(file, "[{}] [{}] {}", timestamp, status, command),
+                };
                 if let Some(ref err) = error_output
                 {
                     for line in err.lines()
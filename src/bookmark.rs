@@ -0,0 +1,90 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::is_same_or_under;
+use crate::snapshot::Snapshot;
+
+/// A saved location: a directory of a host's snapshots
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Bookmark
+{
+    pub host: String,
+    pub path: String,
+    /// Always open this snapshot instead of the latest one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<String>,
+}
+
+impl Bookmark
+{
+    /// Index of the snapshot the bookmark opens: the pinned one, else the
+    /// newest snapshot of the host that contains the path
+    pub fn find_snapshot(&self,
+                         snapshots: &[Snapshot])
+                         -> Option<usize>
+    {
+        match self.snapshot
+        {
+            Some(ref id) => snapshots.iter().position(|s| &s.full_id == id),
+            // Snapshots are sorted newest first
+            None => snapshots.iter().position(|s| {
+                s.hostname == self.host && s.paths.iter().any(|p| is_same_or_under(&self.path, p))
+            }),
+        }
+    }
+}
+
+/// The bookmarks file as written
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct BookmarkFile
+{
+    #[serde(default, rename = "bookmark")]
+    bookmarks: Vec<Bookmark>,
+}
+
+/// Default location: $XDG_DATA_HOME/rest-snapview/bookmarks.toml
+/// (falling back to ~/.local/share)
+pub fn default_path() -> Option<PathBuf>
+{
+    let base = std::env::var_os("XDG_DATA_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".local").join("share")))?;
+    Some(base.join("rest-snapview").join("bookmarks.toml"))
+}
+
+/// Load bookmarks; a missing file means none
+pub fn load(path: &Path) -> Result<Vec<Bookmark>, String>
+{
+    let text = match std::fs::read_to_string(path)
+    {
+        Ok(t) => t,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+
+    toml::from_str::<BookmarkFile>(&text)
+        .map(|file| file.bookmarks)
+        .map_err(|e| format!("{}: {}", path.display(), e.to_string().trim_end()))
+}
+
+/// Write bookmarks, replacing the file in one step so a crash can't truncate it
+pub fn save(path: &Path,
+            bookmarks: &[Bookmark])
+            -> Result<(), String>
+{
+    let file = BookmarkFile { bookmarks: bookmarks.to_vec() };
+    let text = toml::to_string(&file).map_err(|e| e.to_string())?;
+
+    if let Some(dir) = path.parent()
+    {
+        std::fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    let temp = path.with_extension("toml.tmp");
+    std::fs::write(&temp, text)
+        .and_then(|_| std::fs::rename(&temp, path))
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
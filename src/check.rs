@@ -0,0 +1,124 @@
+use std::time::Instant;
+
+/// Outcome of a repository check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckStatus
+{
+    Running,
+    Clean,
+    ErrorsFound,
+    /// restic failed without reporting check errors (e.g. repository locked)
+    Failed(String),
+}
+
+/// A `restic check` run and its streamed output
+pub struct CheckRun
+{
+    /// Value passed to --read-data-subset, if any
+    pub read_data_subset: Option<String>,
+    pub started: Instant,
+    /// Set when the run finishes
+    pub elapsed: Option<std::time::Duration>,
+    pub output: Vec<String>,
+    pub error_count: usize,
+    pub status: CheckStatus,
+
+    // Results panel cursor/scroll
+    pub cursor: usize,
+    pub scroll: usize,
+    pub auto_scroll: bool,
+}
+
+impl CheckRun
+{
+    pub fn new(read_data_subset: Option<String>) -> Self
+    {
+        Self {
+            read_data_subset,
+            started: Instant::now(),
+            elapsed: None,
+            output: Vec::new(),
+            error_count: 0,
+            status: CheckStatus::Running,
+            cursor: 0,
+            scroll: 0,
+            auto_scroll: true,
+        }
+    }
+
+    /// Append a line of output
+    pub fn push_line(&mut self,
+                     line: String)
+    {
+        if is_error_line(&line)
+        {
+            self.error_count += 1;
+        }
+        self.output.push(line);
+
+        if self.auto_scroll
+        {
+            self.cursor = self.output.len() - 1;
+        }
+    }
+
+    /// Record the final result
+    pub fn finish(&mut self,
+                  result: Result<(), String>)
+    {
+        self.elapsed = Some(self.started.elapsed());
+        self.status = match result
+        {
+            Ok(()) if self.error_count == 0 => CheckStatus::Clean,
+            Ok(()) => CheckStatus::ErrorsFound,
+            Err(_) if self.error_count > 0 => CheckStatus::ErrorsFound,
+            Err(e) => CheckStatus::Failed(e),
+        };
+    }
+
+    pub fn is_running(&self) -> bool
+    {
+        self.status == CheckStatus::Running
+    }
+
+    /// One-line summary for the panel title
+    pub fn summary(&self) -> String
+    {
+        let elapsed = format_duration(self.elapsed.unwrap_or_else(|| self.started.elapsed()));
+        match &self.status
+        {
+            CheckStatus::Running => format!("running {}", elapsed),
+            CheckStatus::Clean => format!("no errors, {}", elapsed),
+            CheckStatus::ErrorsFound => format!("{} errors found, {}", self.error_count, elapsed),
+            CheckStatus::Failed(_) => format!("failed, {}", elapsed),
+        }
+    }
+}
+
+/// Check if a line of `restic check` output reports a problem
+pub fn is_error_line(line: &str) -> bool
+{
+    let lower = line.trim_start().to_lowercase();
+    lower.starts_with("error")
+        || lower.starts_with("fatal")
+        || lower.contains("error:")
+        || lower.contains("is damaged")
+}
+
+/// Format a duration as "4.2s" or "3m 07s"
+pub fn format_duration(duration: std::time::Duration) -> String
+{
+    let secs = duration.as_secs();
+    if secs < 60
+    {
+        format!("{:.1}s", duration.as_secs_f64())
+    }
+    else if secs < 3600
+    {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    }
+    else
+    {
+        format!("{}h {:02}m", secs / 3600, (secs % 3600) / 60)
+    }
+}
@@ -0,0 +1,245 @@
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+use crate::config::Config;
+use crate::file::{FileNode, parent_entry};
+use crate::restic::{ResticClient, is_not_found};
+use crate::snapshot::Snapshot;
+
+/// Exit code: success
+pub const EXIT_OK: i32 = 0;
+/// Exit code: restic failed or its output couldn't be used
+pub const EXIT_FAILURE: i32 = 1;
+/// Exit code: invalid command line
+pub const EXIT_USAGE: i32 = 2;
+/// Exit code: snapshot or path doesn't exist
+pub const EXIT_NOT_FOUND: i32 = 3;
+
+/// Non-interactive subcommands (run instead of the TUI)
+#[derive(Debug, Clone)]
+pub enum Subcommand
+{
+    /// List snapshots
+    Snapshots,
+    /// List a directory in a snapshot
+    Ls
+    {
+        snapshot: String,
+        path: String,
+    },
+    /// Restore a file or directory from a snapshot into a local directory
+    Get
+    {
+        snapshot: String,
+        path: String,
+        target: String,
+    },
+}
+
+impl Subcommand
+{
+    /// Parse a subcommand name and its positional arguments
+    pub fn parse(args: &[String]) -> Result<Self, String>
+    {
+        let (name, rest) = match args.split_first()
+        {
+            Some((name, rest)) => (name.as_str(), rest),
+            None => return Err("missing subcommand".to_string()),
+        };
+
+        match (name, rest)
+        {
+            ("snapshots", []) => Ok(Subcommand::Snapshots),
+            ("ls", [snapshot]) => Ok(Subcommand::Ls {
+                snapshot: snapshot.clone(),
+                path: "/".to_string(),
+            }),
+            ("ls", [snapshot, path]) => Ok(Subcommand::Ls {
+                snapshot: snapshot.clone(),
+                path: path.clone(),
+            }),
+            ("get", [snapshot, path, target]) => Ok(Subcommand::Get {
+                snapshot: snapshot.clone(),
+                path: path.clone(),
+                target: target.clone(),
+            }),
+            ("snapshots", _) => Err("usage: snapshots".to_string()),
+            ("ls", _) => Err("usage: ls <snapshot> [path]".to_string()),
+            ("get", _) => Err("usage: get <snapshot> <path> <target>".to_string()),
+            (other, _) => Err(format!("unknown subcommand: {}", other)),
+        }
+    }
+}
+
+/// Output format for subcommands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat
+{
+    Table,
+    Json,
+}
+
+impl OutputFormat
+{
+    pub fn parse(value: &str) -> Option<Self>
+    {
+        match value
+        {
+            "table" => Some(OutputFormat::Table),
+            "json" => Some(OutputFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Result of `get`, for JSON output
+#[derive(Serialize)]
+struct GetResult<'a>
+{
+    snapshot: &'a str,
+    path: &'a str,
+    target: &'a str,
+}
+
+/// Run a subcommand and return the process exit code
+pub async fn run(client: &ResticClient,
+                 subcommand: Subcommand,
+                 format: OutputFormat,
+                 config: &Config)
+                 -> i32
+{
+    match subcommand
+    {
+        Subcommand::Snapshots =>
+        {
+            let cmd_result = client.list_snapshots().await;
+            match cmd_result.result
+            {
+                Ok(snapshots) =>
+                {
+                    match format
+                    {
+                        OutputFormat::Table => print_snapshots(&snapshots, &config.time_format),
+                        OutputFormat::Json => print_json(&snapshots),
+                    }
+                    EXIT_OK
+                }
+                Err(e) => fail(&e.to_string()),
+            }
+        }
+        Subcommand::Ls { snapshot, path } =>
+        {
+            let cmd_result = client.list_files(&snapshot, &path).await;
+            match cmd_result.result
+            {
+                Ok(mut files) =>
+                {
+                    // A file lists as its own entry
+                    if files.is_empty()
+                    {
+                        files.extend(file_entry(client, &snapshot, &path).await);
+                    }
+                    let sort = config.sort;
+                    files.sort_by(|a, b| sort.compare(a, b, |f| f.size.unwrap_or(0)));
+                    match format
+                    {
+                        OutputFormat::Table => print_files(&files, &config.time_format),
+                        OutputFormat::Json => print_json(&files),
+                    }
+                    EXIT_OK
+                }
+                Err(e) => fail(&e.to_string()),
+            }
+        }
+        Subcommand::Get { snapshot, path, target } =>
+        {
+            // Progress isn't shown in non-interactive mode
+            let (progress_tx, _progress_rx) = mpsc::unbounded_channel();
+            let cmd_result = client.restore(&snapshot, std::slice::from_ref(&path), &target, progress_tx).await;
+            match cmd_result.result
+            {
+                Ok(()) =>
+                {
+                    match format
+                    {
+                        OutputFormat::Table => println!("Restored {} from {} to {}", path, snapshot, target),
+                        OutputFormat::Json => print_json(&GetResult {
+                            snapshot: &snapshot,
+                            path: &path,
+                            target: &target,
+                        }),
+                    }
+                    EXIT_OK
+                }
+                Err(e) => fail(&e.to_string()),
+            }
+        }
+    }
+}
+
+/// Entry of a non-directory path, looked up in its parent's listing
+async fn file_entry(client: &ResticClient,
+                    snapshot: &str,
+                    path: &str)
+                    -> Option<FileNode>
+{
+    let path = path.trim_end_matches('/');
+    if path.is_empty()
+    {
+        return None;
+    }
+
+    let parent = parent_entry(path).path;
+    let files = client.list_files(snapshot, &parent).await.result.ok()?;
+    files.into_iter().find(|f| f.path.trim_end_matches('/') == path && !f.is_dir())
+}
+
+/// Print an error and pick the exit code from restic's message
+fn fail(message: &str) -> i32
+{
+    eprintln!("Error: {}", message.trim_end());
+
+    if is_not_found(message)
+    {
+        EXIT_NOT_FOUND
+    }
+    else
+    {
+        EXIT_FAILURE
+    }
+}
+
+fn print_json<T: Serialize + ?Sized>(value: &T)
+{
+    match serde_json::to_string_pretty(value)
+    {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("Error: Failed to encode JSON: {}", e),
+    }
+}
+
+fn print_snapshots(snapshots: &[Snapshot],
+                   time_format: &str)
+{
+    println!("{:8}  {:16}  {:16}  {:8}  {:20}  TAGS", "ID", "TIME", "HOST", "USER", "PATHS");
+    for snapshot in snapshots
+    {
+        println!("{:8}  {:16}  {:16}  {:8}  {:20}  {}",
+                 snapshot.display_id(),
+                 snapshot.formatted_time(time_format),
+                 snapshot.hostname,
+                 snapshot.username,
+                 snapshot.paths.join(","),
+                 snapshot.tags.join(","));
+    }
+}
+
+fn print_files(files: &[FileNode],
+               time_format: &str)
+{
+    for file in files
+    {
+        let name = if file.is_dir() { format!("{}/", file.name) } else { file.name.clone() };
+        println!("{:>10}  {:16}  {}", file.formatted_size(), file.formatted_mtime(time_format), name);
+    }
+}
@@ -0,0 +1,387 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+
+use crate::file::FileNode;
+
+/// How an entry differs between the two snapshots
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change
+{
+    Same,
+    OnlyLeft,
+    OnlyRight,
+    Size,       // Different size, or a different type of entry on each side
+    Mtime,      // Same size, different modification time
+}
+
+/// Local directory a snapshot directory is compared with (the right side)
+#[derive(Debug, Clone)]
+pub struct LocalDir
+{
+    /// Local directory matching `base`
+    pub root: PathBuf,
+    /// Snapshot directory the comparison started in; it can't go above it
+    pub base: String,
+}
+
+impl LocalDir
+{
+    /// Local directory matching a directory below `base` in the snapshot
+    pub fn dir_for(&self,
+                   path: &str)
+                   -> PathBuf
+    {
+        let relative = path.strip_prefix(self.base.as_str()).unwrap_or_default().trim_start_matches('/');
+        if relative.is_empty()
+        {
+            self.root.clone()
+        }
+        else
+        {
+            self.root.join(relative)
+        }
+    }
+}
+
+/// A directory as listed in one of the snapshots
+#[derive(Debug, Clone)]
+pub enum Listing
+{
+    Loading,
+    Entries(Vec<FileNode>),
+    /// Not in this snapshot (or listing failed), with the reason
+    Absent(String),
+}
+
+/// One name in the directory, as found in each snapshot
+#[derive(Debug, Clone)]
+pub struct CompareRow
+{
+    pub name: String,
+    pub left: Option<FileNode>,
+    pub right: Option<FileNode>,
+    pub change: Change,
+}
+
+impl CompareRow
+{
+    /// Either side's entry (for the path, which is the same on both)
+    pub fn node(&self) -> Option<&FileNode>
+    {
+        self.left.as_ref().or(self.right.as_ref())
+    }
+
+    pub fn is_dir(&self) -> bool
+    {
+        self.left.iter().chain(self.right.iter()).any(|n| n.is_dir())
+    }
+}
+
+/// The same directory of two snapshots side by side, aligned by name.
+///
+/// Left is the older snapshot.
+pub struct Compare
+{
+    pub left_id: String,
+    pub right_id: String,
+    pub path: String,
+    pub left: Listing,
+    pub right: Listing,
+    /// Aligned entries: directories first, then by name
+    pub rows: Vec<CompareRow>,
+    /// Hide entries that are the same on both sides
+    pub changes_only: bool,
+    /// Name to put the cursor on once both sides are listed
+    select: Option<String>,
+    /// Compared with a local directory instead of the right snapshot
+    pub local: Option<LocalDir>,
+    /// Key to press again to confirm a restore
+    pub confirm: Option<char>,
+    /// Directories entered that one side doesn't have, per (snapshot ID,
+    /// path), with the reason shown instead of a listing
+    pub absent: HashMap<(String, String), String>,
+    /// Restore jobs into the local directory; it is read again when one finishes
+    pub restore_jobs: Vec<usize>,
+    /// The local directory is being read in the background
+    pub local_reading: bool,
+
+    pub cursor: usize,
+    pub scroll: usize,
+    pub visible_height: usize,  // Updated by UI
+}
+
+impl Compare
+{
+    pub fn new(left_id: String,
+               right_id: String,
+               path: String,
+               select: Option<String>)
+               -> Self
+    {
+        Self {
+            left_id,
+            right_id,
+            path,
+            left: Listing::Loading,
+            right: Listing::Loading,
+            rows: Vec::new(),
+            changes_only: false,
+            select,
+            local: None,
+            confirm: None,
+            absent: HashMap::new(),
+            restore_jobs: Vec::new(),
+            local_reading: false,
+            cursor: 0,
+            scroll: 0,
+            visible_height: 10,
+        }
+    }
+
+    /// Compare a snapshot directory with the local directory `root`
+    pub fn local(snapshot_id: String,
+                 path: String,
+                 root: PathBuf)
+                 -> Self
+    {
+        let mut compare = Self::new(snapshot_id, String::new(), path.clone(), None);
+        compare.local = Some(LocalDir { root, base: path });
+        compare
+    }
+
+    /// How a change is called, depending on what the right side is
+    pub fn change_label(&self,
+                        change: Change)
+                        -> &'static str
+    {
+        match (change, self.local.is_some())
+        {
+            (Change::Same, _) => "",
+            (Change::OnlyLeft, false) => "only left",
+            (Change::OnlyRight, false) => "only right",
+            (Change::OnlyLeft, true) => "missing locally",
+            (Change::OnlyRight, true) => "new locally",
+            (Change::Size, _) => "size",
+            (Change::Mtime, _) => "mtime",
+        }
+    }
+
+    /// Entries whose left version can replace the right one: missing on the
+    /// right, or different files (directories on both sides are left alone)
+    pub fn restorable(&self) -> Vec<&CompareRow>
+    {
+        self.rows
+            .iter()
+            .filter(|row| match (row.change, &row.left, &row.right)
+            {
+                (Change::OnlyLeft, _, _) => true,
+                (Change::Size | Change::Mtime, Some(l), Some(r)) => !l.is_dir() && !r.is_dir(),
+                _ => false,
+            })
+            .collect()
+    }
+
+    /// True until both sides are listed (or known to be absent)
+    pub fn is_loading(&self) -> bool
+    {
+        matches!(self.left, Listing::Loading) || matches!(self.right, Listing::Loading)
+    }
+
+    /// Show another directory; the cursor goes to `select` once it is listed
+    pub fn set_path(&mut self,
+                    path: String,
+                    select: Option<String>)
+    {
+        self.path = path;
+        self.select = select;
+        self.left = Listing::Loading;
+        self.right = Listing::Loading;
+        self.local_reading = false;
+        self.rows.clear();
+        self.cursor = 0;
+        self.scroll = 0;
+    }
+
+    /// Realign the rows, keeping the cursor on the same entry (or the one
+    /// waiting to be selected)
+    pub fn rebuild(&mut self)
+    {
+        let pending = if self.is_loading() { None } else { self.select.take() };
+        let selected = pending.or_else(|| self.selected().map(|r| r.name.clone()));
+
+        // File systems differ in timestamp precision, so local mtimes match to the second
+        let whole_seconds = self.local.is_some();
+        let mut by_name: BTreeMap<String, (Option<FileNode>, Option<FileNode>)> = BTreeMap::new();
+        if let Listing::Entries(ref files) = self.left
+        {
+            for f in files
+            {
+                by_name.entry(f.name.clone()).or_default().0 = Some(f.clone());
+            }
+        }
+        if let Listing::Entries(ref files) = self.right
+        {
+            for f in files
+            {
+                by_name.entry(f.name.clone()).or_default().1 = Some(f.clone());
+            }
+        }
+
+        self.rows = by_name.into_iter()
+                           .map(|(name, (left, right))| {
+                               let change = change_of(left.as_ref(), right.as_ref(), whole_seconds);
+                               CompareRow { name, left, right, change }
+                           })
+                           .filter(|row| !self.changes_only || row.change != Change::Same)
+                           .collect();
+        self.rows.sort_by(|a, b| b.is_dir().cmp(&a.is_dir()).then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase())));
+
+        self.cursor = selected.and_then(|name| self.rows.iter().position(|r| r.name == name))
+                              .unwrap_or(self.cursor)
+                              .min(self.rows.len().saturating_sub(1));
+    }
+
+    pub fn selected(&self) -> Option<&CompareRow>
+    {
+        self.rows.get(self.cursor)
+    }
+
+    /// Number of rows that differ
+    pub fn change_count(&self) -> usize
+    {
+        self.rows.iter().filter(|r| r.change != Change::Same).count()
+    }
+
+    /// Move the cursor by `delta` rows, clamped to the rows
+    pub fn move_cursor(&mut self,
+                       delta: i32)
+    {
+        let max = self.rows.len().saturating_sub(1) as i32;
+        self.cursor = (self.cursor as i32 + delta).clamp(0, max) as usize;
+    }
+
+    /// Adjust scroll for visible area
+    pub fn adjust_scroll(&mut self,
+                         visible_height: usize)
+    {
+        if visible_height == 0
+        {
+            return;
+        }
+
+        if self.cursor < self.scroll
+        {
+            self.scroll = self.cursor;
+        }
+        else if self.cursor >= self.scroll + visible_height
+        {
+            self.scroll = self.cursor - visible_height + 1;
+        }
+    }
+}
+
+/// Classify an entry by its two sides
+fn change_of(left: Option<&FileNode>,
+             right: Option<&FileNode>,
+             whole_seconds: bool)
+             -> Change
+{
+    match (left, right)
+    {
+        (Some(_), None) => Change::OnlyLeft,
+        (None, Some(_)) => Change::OnlyRight,
+        (Some(l), Some(r)) if l.node_type != r.node_type || (l.node_type == "file" && l.size != r.size) => Change::Size,
+        (Some(l), Some(r)) if !same_mtime(l.mtime, r.mtime, whole_seconds) => Change::Mtime,
+        _ => Change::Same,
+    }
+}
+
+/// Modification times match (to the second if `whole_seconds`)
+fn same_mtime(a: Option<DateTime<Utc>>,
+              b: Option<DateTime<Utc>>,
+              whole_seconds: bool)
+              -> bool
+{
+    match (a, b)
+    {
+        (Some(a), Some(b)) if whole_seconds => a.timestamp() == b.timestamp(),
+        _ => a == b,
+    }
+}
+
+/// restic's node type for a FIFO, socket or device node
+#[cfg(unix)]
+fn special_type(file_type: std::fs::FileType) -> Option<&'static str>
+{
+    use std::os::unix::fs::FileTypeExt;
+
+    if file_type.is_fifo()
+    {
+        Some("fifo")
+    }
+    else if file_type.is_socket()
+    {
+        Some("socket")
+    }
+    else if file_type.is_block_device()
+    {
+        Some("dev")
+    }
+    else if file_type.is_char_device()
+    {
+        Some("chardev")
+    }
+    else
+    {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+fn special_type(_file_type: std::fs::FileType) -> Option<&'static str>
+{
+    None
+}
+
+/// List a local directory as the entries of the snapshot directory `path`
+/// (blocking, so it is run off the UI thread)
+pub fn local_listing(path: &str,
+                     dir: &Path)
+                     -> Listing
+{
+    let read_dir = match std::fs::read_dir(dir)
+    {
+        Ok(r) => r,
+        Err(e) => return Listing::Absent(format!("{}: {}", dir.display(), e)),
+    };
+
+    let entries = read_dir.filter_map(|entry| entry.ok())
+                          .filter_map(|entry| {
+                              // Symlinks are compared as links, like restic stores them
+                              let metadata = entry.path().symlink_metadata().ok()?;
+                              let name = entry.file_name().to_string_lossy().to_string();
+                              let node_type = if metadata.is_dir()
+                              {
+                                  "dir"
+                              }
+                              else if metadata.file_type().is_symlink()
+                              {
+                                  "symlink"
+                              }
+                              else
+                              {
+                                  special_type(metadata.file_type()).unwrap_or("file")
+                              };
+                              Some(FileNode {
+                                  path: format!("{}/{}", path.trim_end_matches('/'), name),
+                                  name,
+                                  node_type: node_type.to_string(),
+                                  size: metadata.is_file().then_some(metadata.len()),
+                                  mtime: metadata.modified().ok().map(DateTime::<Utc>::from),
+                              })
+                          })
+                          .collect();
+    Listing::Entries(entries)
+}
@@ -0,0 +1,335 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use chrono::format::{Item, StrftimeItems};
+use serde::Deserialize;
+
+use crate::app::FilesLayout;
+use crate::file::{FileSort, SortKey};
+use crate::keymap::{KeyList, Keymap};
+use crate::restic::GlobalOptions;
+use crate::search::SearchMode;
+use crate::theme::{self, Theme};
+
+/// Time format used when the config doesn't set one
+pub const DEFAULT_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Repository profile: where the repository is and how to unlock it
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile
+{
+    pub repository: String,
+    #[serde(default)]
+    pub password_file: Option<String>,
+    #[serde(default)]
+    pub password_command: Option<String>,
+}
+
+/// `[sort]` table
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SortSection
+{
+    key: String,
+    reverse: bool,
+    dirs_first: bool,
+}
+
+impl Default for SortSection
+{
+    fn default() -> Self
+    {
+        Self {
+            key: "name".to_string(),
+            reverse: false,
+            dirs_first: true,
+        }
+    }
+}
+
+/// `[confirm]` table
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfirmSection
+{
+    download_size: Option<String>,
+    download_files: Option<u64>,
+}
+
+/// The config file as written
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile
+{
+    download_dir: Option<String>,
+    log_file: Option<String>,
+    bookmarks_file: Option<String>,
+    show_hidden: Option<bool>,
+    time_format: Option<String>,
+    theme: Option<String>,
+    mouse: Option<bool>,
+    files_layout: Option<String>,
+    search_mode: Option<String>,
+    default_profile: Option<String>,
+    sort: SortSection,
+    confirm: ConfirmSection,
+    restic: GlobalOptions,
+    keys: BTreeMap<String, KeyList>,
+    colors: BTreeMap<String, String>,
+    profiles: BTreeMap<String, Profile>,
+}
+
+/// Validated configuration
+#[derive(Debug, Clone)]
+pub struct Config
+{
+    /// Initial target directory for downloads (default: current directory)
+    pub download_dir: Option<String>,
+    pub log_file: Option<String>,
+    /// Where bookmarks are saved (default: $XDG_DATA_HOME/rest-snapview/bookmarks.toml)
+    pub bookmarks_file: Option<String>,
+    /// Show dotfiles in the Files panel and download dialog
+    pub show_hidden: bool,
+    pub time_format: String,
+    pub sort: FileSort,
+    pub files_layout: FilesLayout,
+    /// Initial mode of the file filter
+    pub search_mode: SearchMode,
+    /// Ask before downloading more than this many bytes
+    pub confirm_download_bytes: Option<u64>,
+    /// Ask before downloading a directory with more than this many files
+    pub confirm_download_files: Option<u64>,
+    /// restic options applied to every command (CLI options are added on top)
+    pub restic: GlobalOptions,
+    /// Built-in key bindings with the `[keys]` overrides applied
+    pub keymap: Keymap,
+    /// Built-in theme with the `[colors]` overrides applied (monochrome under NO_COLOR)
+    pub theme: Theme,
+    /// Capture the mouse (off keeps the terminal's own text selection)
+    pub mouse: bool,
+    pub default_profile: Option<String>,
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl Default for Config
+{
+    fn default() -> Self
+    {
+        Self {
+            download_dir: None,
+            log_file: None,
+            bookmarks_file: None,
+            show_hidden: true,
+            time_format: DEFAULT_TIME_FORMAT.to_string(),
+            sort: FileSort::default(),
+            files_layout: FilesLayout::List,
+            search_mode: SearchMode::Fuzzy,
+            confirm_download_bytes: None,
+            confirm_download_files: None,
+            restic: GlobalOptions::default(),
+            keymap: Keymap::default(),
+            theme: if theme::no_color() { Theme::mono() } else { Theme::default() },
+            mouse: true,
+            default_profile: None,
+            profiles: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config
+{
+    /// Default location: $XDG_CONFIG_HOME/rest-snapview/config.toml
+    /// (falling back to ~/.config)
+    pub fn default_path() -> Option<PathBuf>
+    {
+        let base = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+        Some(base.join("rest-snapview").join("config.toml"))
+    }
+
+    /// Load the config from `path`, or from the default location if None.
+    ///
+    /// A missing file at the default location means defaults; a missing
+    /// file given explicitly is an error.
+    pub fn load(path: Option<&str>) -> Result<Self, String>
+    {
+        let (path, explicit) = match path
+        {
+            Some(p) => (PathBuf::from(expand_tilde(p)), true),
+            None => match Self::default_path()
+            {
+                Some(p) => (p, false),
+                None => return Ok(Self::default()),
+            },
+        };
+
+        let text = match std::fs::read_to_string(&path)
+        {
+            Ok(t) => t,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && !explicit => return Ok(Self::default()),
+            Err(e) => return Err(format!("{}: {}", path.display(), e)),
+        };
+
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e.trim_end()))
+    }
+
+    /// Parse and validate config file contents
+    pub fn parse(text: &str) -> Result<Self, String>
+    {
+        let file: ConfigFile = toml::from_str(text).map_err(|e| e.to_string())?;
+
+        let time_format = file.time_format.unwrap_or_else(|| DEFAULT_TIME_FORMAT.to_string());
+        if StrftimeItems::new(&time_format).any(|item| matches!(item, Item::Error))
+        {
+            return Err(format!("time_format: '{}' is not a valid strftime format", time_format));
+        }
+
+        let key = SortKey::parse(&file.sort.key).ok_or_else(|| {
+            format!("sort.key: '{}' is not one of name, natural, size, mtime, type", file.sort.key)
+        })?;
+
+        let files_layout = match file.files_layout
+        {
+            Some(ref label) => FilesLayout::parse(label).ok_or_else(|| {
+                format!("files_layout: '{}' is not one of list, columns, tree", label)
+            })?,
+            None => FilesLayout::List,
+        };
+
+        let search_mode = match file.search_mode
+        {
+            Some(ref label) => SearchMode::parse(label).ok_or_else(|| {
+                format!("search_mode: '{}' is not one of fuzzy, substring, glob, regex", label)
+            })?,
+            None => SearchMode::Fuzzy,
+        };
+
+        let confirm_download_bytes = match file.confirm.download_size
+        {
+            Some(ref size) => Some(parse_size(size).ok_or_else(|| {
+                format!("confirm.download_size: '{}' is not a size like 500M or 2GiB", size)
+            })?),
+            None => None,
+        };
+
+        let download_dir = file.download_dir.map(|d| expand_tilde(&d));
+        if let Some(ref dir) = download_dir
+        {
+            if !Path::new(dir).is_dir()
+            {
+                return Err(format!("download_dir: '{}' is not a directory", dir));
+            }
+        }
+
+        for option in &file.restic.options
+        {
+            if !option.contains('=')
+            {
+                return Err(format!("restic.options: expected key=value, got '{}'", option));
+            }
+        }
+
+        let keymap = Keymap::with_overrides(&file.keys)?;
+
+        // Validate the theme even when NO_COLOR means it won't be used
+        let theme = Theme::from_config(file.theme.as_deref().unwrap_or("default"), &file.colors)?;
+        let theme = if theme::no_color() { Theme::mono() } else { theme };
+
+        let mut profiles = file.profiles;
+        for (name, profile) in profiles.iter_mut()
+        {
+            profile.password_file = profile.password_file.as_deref().map(expand_tilde);
+            if profile.repository.trim().is_empty()
+            {
+                return Err(format!("profiles.{}: repository is empty", name));
+            }
+            if profile.password_file.is_some() && profile.password_command.is_some()
+            {
+                return Err(format!("profiles.{}: set password_file or password_command, not both", name));
+            }
+        }
+
+        if let Some(ref name) = file.default_profile
+        {
+            if !profiles.contains_key(name)
+            {
+                return Err(format!("default_profile: no profile named '{}'", name));
+            }
+        }
+
+        Ok(Self {
+            download_dir,
+            log_file: file.log_file.map(|f| expand_tilde(&f)),
+            bookmarks_file: file.bookmarks_file.map(|f| expand_tilde(&f)),
+            show_hidden: file.show_hidden.unwrap_or(true),
+            time_format,
+            sort: FileSort {
+                key,
+                reverse: file.sort.reverse,
+                dirs_first: file.sort.dirs_first,
+            },
+            files_layout,
+            search_mode,
+            confirm_download_bytes,
+            confirm_download_files: file.confirm.download_files,
+            restic: file.restic,
+            keymap,
+            theme,
+            mouse: file.mouse.unwrap_or(true),
+            default_profile: file.default_profile,
+            profiles,
+        })
+    }
+
+    /// Profile to use: the one named on the command line, else the default
+    pub fn profile(&self,
+                   name: Option<&str>)
+                   -> Result<Option<&Profile>, String>
+    {
+        match name.or(self.default_profile.as_deref())
+        {
+            Some(name) => self.profiles
+                              .get(name)
+                              .map(Some)
+                              .ok_or_else(|| format!("no profile named '{}' in the config file", name)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Parse a size such as "1048576", "500M", "2GiB" or "1.5 GB" (binary units)
+fn parse_size(s: &str) -> Option<u64>
+{
+    let s = s.trim();
+    let split = s.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number.parse().ok()?;
+
+    let multiplier: u64 = match unit.trim().to_lowercase().as_str()
+    {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        "t" | "tb" | "tib" => 1 << 40,
+        _ => return None,
+    };
+
+    Some((number * multiplier as f64) as u64)
+}
+
+/// Expand a leading ~ to the home directory
+fn expand_tilde(path: &str) -> String
+{
+    match (path.strip_prefix('~'), std::env::var_os("HOME"))
+    {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') =>
+        {
+            format!("{}{}", home.to_string_lossy(), rest)
+        }
+        _ => path.to_string(),
+    }
+}
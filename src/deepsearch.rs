@@ -0,0 +1,243 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+use crate::file::FileNode;
+use crate::search::{Matcher, SearchMatch, SearchMode};
+
+/// State of the recursive listing behind a deep search
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeepSearchStatus
+{
+    Running,
+    Done,
+    Failed(String),
+}
+
+/// A search over every entry below a directory of a snapshot.
+///
+/// Entries stream in from `restic ls --recursive`; matches against the
+/// query are kept ranked as they arrive.
+pub struct DeepSearch
+{
+    /// Tells stale batches from a previous search apart
+    pub id: usize,
+    pub snapshot_id: String,
+    /// Directory searched from ("" = the whole snapshot)
+    pub root: String,
+    /// Everything listed so far, in listing order
+    pub entries: Vec<FileNode>,
+    /// (score, index into entries) of the matches, best first
+    pub results: Vec<(i32, usize)>,
+    pub status: DeepSearchStatus,
+    pub started: Instant,
+    /// Set when the listing finishes
+    pub elapsed: Option<std::time::Duration>,
+
+    // Query input, as in the file filter
+    pub query: String,
+    pub query_cursor: usize,
+    pub mode: SearchMode,
+    pub matcher: Matcher,
+    pub error: Option<String>,
+    pub show_hidden: bool,
+
+    // Result list cursor/scroll
+    pub cursor: usize,
+    pub scroll: usize,
+    pub visible_height: usize,  // Updated by UI
+
+    /// Set to stop the listing when the search is replaced
+    cancel: Arc<AtomicBool>,
+}
+
+impl DeepSearch
+{
+    pub fn new(id: usize,
+               snapshot_id: String,
+               root: String,
+               mode: SearchMode,
+               show_hidden: bool)
+               -> Self
+    {
+        Self {
+            id,
+            snapshot_id,
+            root,
+            entries: Vec::new(),
+            results: Vec::new(),
+            status: DeepSearchStatus::Running,
+            started: Instant::now(),
+            elapsed: None,
+            query: String::new(),
+            query_cursor: 0,
+            mode,
+            matcher: Matcher::default(),
+            error: None,
+            show_hidden,
+            cursor: 0,
+            scroll: 0,
+            visible_height: 10,
+            cancel: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Flag shared with the background listing
+    pub fn cancel_flag(&self) -> Arc<AtomicBool>
+    {
+        self.cancel.clone()
+    }
+
+    /// Stop the background listing (it ends at its next batch)
+    pub fn cancel(&self)
+    {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_running(&self) -> bool
+    {
+        self.status == DeepSearchStatus::Running
+    }
+
+    /// Add a batch of listed entries, keeping the cursor on the same match
+    pub fn push_entries(&mut self,
+                        nodes: Vec<FileNode>)
+    {
+        let selected = self.selected_index();
+        let start = self.entries.len();
+        // The listing includes the directory searched from
+        let root = self.root.trim_end_matches('/');
+        self.entries.extend(nodes.into_iter().filter(|n| root.is_empty() || n.path.trim_end_matches('/') != root));
+
+        for i in start..self.entries.len()
+        {
+            if let Some(m) = self.find(&self.entries[i])
+            {
+                // After equal scores, so earlier entries stay first
+                let at = self.results.partition_point(|&(score, _)| score >= m.score);
+                self.results.insert(at, (m.score, i));
+            }
+        }
+
+        self.restore_cursor(selected);
+    }
+
+    /// Record the end of the listing
+    pub fn finish(&mut self,
+                  result: Result<(), String>)
+    {
+        self.elapsed = Some(self.started.elapsed());
+        self.status = match result
+        {
+            Ok(()) => DeepSearchStatus::Done,
+            Err(e) => DeepSearchStatus::Failed(e),
+        };
+    }
+
+    /// Recompile the query and rematch everything listed so far.
+    ///
+    /// An invalid pattern is reported in `error` and the previous results stay.
+    pub fn update_query(&mut self)
+    {
+        match Matcher::new(self.mode, &self.query)
+        {
+            Ok(matcher) =>
+            {
+                self.matcher = matcher;
+                self.error = None;
+            }
+            Err(e) =>
+            {
+                self.error = Some(e);
+                return;
+            }
+        }
+
+        self.results = self.entries
+                           .iter()
+                           .enumerate()
+                           .filter_map(|(i, node)| self.find(node).map(|m| (m.score, i)))
+                           .collect();
+        // Stable: equal scores keep the listing order
+        self.results.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+        self.cursor = 0;
+        self.scroll = 0;
+    }
+
+    /// Match an entry's name against the query (nothing matches an empty
+    /// query; hidden entries and everything below them are skipped)
+    pub fn find(&self,
+                node: &FileNode)
+                -> Option<SearchMatch>
+    {
+        if self.query.is_empty()
+        {
+            return None;
+        }
+        if !self.show_hidden && self.relative_path(node).split('/').any(|c| c.starts_with('.'))
+        {
+            return None;
+        }
+        self.matcher.find(&node.name)
+    }
+
+    /// Path of an entry relative to the search root
+    pub fn relative_path<'a>(&self,
+                             node: &'a FileNode)
+                             -> &'a str
+    {
+        let root = self.root.trim_end_matches('/');
+        node.path
+            .strip_prefix(root)
+            .unwrap_or(&node.path)
+            .trim_start_matches('/')
+    }
+
+    /// Index into `entries` of the match under the cursor
+    pub fn selected_index(&self) -> Option<usize>
+    {
+        self.results.get(self.cursor).map(|&(_, i)| i)
+    }
+
+    pub fn selected(&self) -> Option<&FileNode>
+    {
+        self.selected_index().map(|i| &self.entries[i])
+    }
+
+    /// Move the cursor by `delta` rows, clamped to the results
+    pub fn move_cursor(&mut self,
+                       delta: i32)
+    {
+        let max = self.results.len().saturating_sub(1) as i32;
+        self.cursor = (self.cursor as i32 + delta).clamp(0, max) as usize;
+    }
+
+    /// Adjust scroll for visible area
+    pub fn adjust_scroll(&mut self,
+                         visible_height: usize)
+    {
+        if visible_height == 0
+        {
+            return;
+        }
+
+        if self.cursor < self.scroll
+        {
+            self.scroll = self.cursor;
+        }
+        else if self.cursor >= self.scroll + visible_height
+        {
+            self.scroll = self.cursor - visible_height + 1;
+        }
+    }
+
+    /// Put the cursor back on an entry after the results changed
+    fn restore_cursor(&mut self,
+                      selected: Option<usize>)
+    {
+        if let Some(pos) = selected.and_then(|s| self.results.iter().position(|&(_, i)| i == s))
+        {
+            self.cursor = pos;
+        }
+    }
+}
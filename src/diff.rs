@@ -0,0 +1,373 @@
+use std::time::Duration;
+
+use sha2::{Digest, Sha256};
+use similar::udiff::UnifiedHunkHeader;
+use similar::{ChangeTag, TextDiff};
+
+use crate::file::format_bytes;
+
+/// Largest file version fetched for a diff
+pub const DIFF_MAX_BYTES: usize = 8 * 1024 * 1024;
+
+/// Unchanged lines shown around each change
+const CONTEXT_LINES: usize = 3;
+
+/// Stop searching for the smallest diff after this long (the diff stays correct, just longer)
+const DIFF_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// One version of the file as fetched with `restic dump`
+#[derive(Debug, Clone)]
+enum Version
+{
+    Loading,
+    Loaded(Vec<u8>),
+    /// Size of the node (None if only known to exceed DIFF_MAX_BYTES)
+    TooLarge(Option<u64>),
+    Failed(String),
+    /// Handed over to `compute`
+    Taken,
+}
+
+/// Size and content hash of a version that isn't diffed as text
+#[derive(Debug, Clone)]
+pub struct Summary
+{
+    /// None when only known to exceed DIFF_MAX_BYTES
+    pub size: Option<u64>,
+    /// None when the version was too large to fetch
+    pub sha256: Option<String>,
+}
+
+impl Summary
+{
+    pub fn formatted_size(&self) -> String
+    {
+        match self.size
+        {
+            Some(size) => format_bytes(size),
+            None => format!("> {}", format_bytes(DIFF_MAX_BYTES as u64)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind
+{
+    Hunk,       // "@@ -a,b +c,d @@" header
+    Context,
+    Removed,
+    Added,
+}
+
+/// A line of the unified diff with its line numbers in the old and new version
+#[derive(Debug, Clone)]
+pub struct DiffLine
+{
+    pub kind: LineKind,
+    pub old_no: Option<usize>,
+    pub new_no: Option<usize>,
+    pub text: String,
+}
+
+/// What the diff view shows
+#[derive(Debug, Clone)]
+pub enum DiffBody
+{
+    Loading,
+    /// Both versions are there; the diff is computed in the background
+    Computing,
+    Failed(String),
+    Identical,
+    Text(Vec<DiffLine>),
+    /// Not diffed as text (binary or too large), with the reason
+    Summary
+    {
+        reason: String,
+        left: Summary,
+        right: Summary,
+    },
+}
+
+/// Both versions of a file, to be diffed off the UI thread
+#[derive(Debug, Clone)]
+pub struct DiffInput
+{
+    left: Version,
+    right: Version,
+}
+
+/// Unified diff of one file between two snapshots.
+///
+/// Left is the older snapshot.
+pub struct FileDiff
+{
+    pub path: String,
+    pub left_id: String,
+    pub right_id: String,
+    left: Version,
+    right: Version,
+    pub body: DiffBody,
+    /// Indices of the hunk headers in the text lines
+    pub hunks: Vec<usize>,
+
+    pub scroll: usize,
+    pub visible_height: usize,  // Updated by UI
+}
+
+impl FileDiff
+{
+    /// Start a diff; versions larger than DIFF_MAX_BYTES aren't fetched at all
+    pub fn new(path: String,
+               left_id: String,
+               right_id: String,
+               left_size: Option<u64>,
+               right_size: Option<u64>)
+               -> Self
+    {
+        let version = |size: Option<u64>| match size
+        {
+            Some(s) if s > DIFF_MAX_BYTES as u64 => Version::TooLarge(Some(s)),
+            _ => Version::Loading,
+        };
+        let mut diff = Self {
+            path,
+            left_id,
+            right_id,
+            left: version(left_size),
+            right: version(right_size),
+            body: DiffBody::Loading,
+            hunks: Vec::new(),
+            scroll: 0,
+            visible_height: 10,
+        };
+        diff.rebuild();
+        diff
+    }
+
+    /// Snapshots whose version still has to be fetched
+    pub fn pending(&self) -> Vec<String>
+    {
+        let mut ids = Vec::new();
+        if matches!(self.left, Version::Loading)
+        {
+            ids.push(self.left_id.clone());
+        }
+        if matches!(self.right, Version::Loading)
+        {
+            ids.push(self.right_id.clone());
+        }
+        ids
+    }
+
+    /// Store a fetched version; the diff is computed once both are there
+    pub fn set_version(&mut self,
+                       snapshot_id: &str,
+                       result: Result<Vec<u8>, String>)
+    {
+        let version = match result
+        {
+            // dump_head stops one byte past the limit, and a node whose size
+            // was known is only fetched when it's within the limit
+            Ok(content) if content.len() > DIFF_MAX_BYTES => Version::TooLarge(None),
+            Ok(content) => Version::Loaded(content),
+            Err(e) => Version::Failed(e),
+        };
+        if snapshot_id == self.left_id && matches!(self.left, Version::Loading)
+        {
+            self.left = version;
+        }
+        else if snapshot_id == self.right_id && matches!(self.right, Version::Loading)
+        {
+            self.right = version;
+        }
+        else
+        {
+            return;
+        }
+        self.rebuild();
+    }
+
+    fn rebuild(&mut self)
+    {
+        self.body = match (&self.left, &self.right)
+        {
+            (Version::Loading, _) | (_, Version::Loading) => DiffBody::Loading,
+            _ => DiffBody::Computing,
+        };
+        self.hunks = Vec::new();
+        self.scroll = 0;
+    }
+
+    /// Both versions once they are fetched, for `compute` (only handed out once)
+    pub fn take_input(&mut self) -> Option<DiffInput>
+    {
+        if !matches!(self.body, DiffBody::Computing) || matches!(self.left, Version::Taken)
+        {
+            return None;
+        }
+        Some(DiffInput {
+            left: std::mem::replace(&mut self.left, Version::Taken),
+            right: std::mem::replace(&mut self.right, Version::Taken),
+        })
+    }
+
+    /// Show the diff computed from `take_input`
+    pub fn set_body(&mut self,
+                    body: DiffBody)
+    {
+        self.body = body;
+        self.hunks = match self.body
+        {
+            DiffBody::Text(ref lines) => lines.iter()
+                                              .enumerate()
+                                              .filter(|(_, l)| l.kind == LineKind::Hunk)
+                                              .map(|(i, _)| i)
+                                              .collect(),
+            _ => Vec::new(),
+        };
+        self.scroll = 0;
+    }
+
+    /// Lines removed and added
+    pub fn counts(&self) -> (usize, usize)
+    {
+        match self.body
+        {
+            DiffBody::Text(ref lines) => (lines.iter().filter(|l| l.kind == LineKind::Removed).count(),
+                                          lines.iter().filter(|l| l.kind == LineKind::Added).count()),
+            _ => (0, 0),
+        }
+    }
+
+    fn line_count(&self) -> usize
+    {
+        match self.body
+        {
+            DiffBody::Text(ref lines) => lines.len(),
+            _ => 0,
+        }
+    }
+
+    /// Scroll by `delta` lines, keeping the last page full (unless the last
+    /// hunk was jumped to the top)
+    pub fn scroll_by(&mut self,
+                     delta: i32)
+    {
+        let max = self.line_count()
+                      .saturating_sub(self.visible_height)
+                      .max(self.hunks.last().copied().unwrap_or(0)) as i32;
+        self.scroll = (self.scroll as i32 + delta).clamp(0, max) as usize;
+    }
+
+    /// Scroll the next hunk to the top
+    pub fn next_hunk(&mut self)
+    {
+        if let Some(&i) = self.hunks.iter().find(|&&i| i > self.scroll)
+        {
+            self.scroll = i;
+        }
+    }
+
+    /// Scroll the previous hunk to the top
+    pub fn prev_hunk(&mut self)
+    {
+        if let Some(&i) = self.hunks.iter().rev().find(|&&i| i < self.scroll)
+        {
+            self.scroll = i;
+        }
+    }
+
+    /// 1-based number of the hunk at the top of the view
+    pub fn current_hunk(&self) -> usize
+    {
+        self.hunks.iter().filter(|&&i| i <= self.scroll).count().max(1)
+    }
+}
+
+/// Diff two versions, or summarize them when they can't be diffed as text.
+///
+/// Hashing and diffing several MiB takes a while, so this runs off the UI thread.
+pub fn compute(input: DiffInput) -> DiffBody
+{
+    let DiffInput { left, right } = input;
+    match (&left, &right)
+    {
+        (Version::Failed(e), _) | (_, Version::Failed(e)) => DiffBody::Failed(e.clone()),
+        (Version::Loaded(old), Version::Loaded(new)) if old == new => DiffBody::Identical,
+        (Version::Loaded(old), Version::Loaded(new)) => match (as_text(old), as_text(new))
+        {
+            (Some(old), Some(new)) => DiffBody::Text(diff_lines(old, new)),
+            _ => DiffBody::Summary {
+                reason: "Binary file, not diffed".to_string(),
+                left: summary(&left),
+                right: summary(&right),
+            },
+        },
+        _ => DiffBody::Summary {
+            reason: format!("Larger than {}, not diffed", format_bytes(DIFF_MAX_BYTES as u64)),
+            left: summary(&left),
+            right: summary(&right),
+        },
+    }
+}
+
+/// Content as text, or None if it looks binary
+fn as_text(content: &[u8]) -> Option<&str>
+{
+    if content.contains(&0)
+    {
+        return None;
+    }
+    std::str::from_utf8(content).ok()
+}
+
+fn summary(version: &Version) -> Summary
+{
+    match version
+    {
+        Version::Loaded(content) => Summary {
+            size: Some(content.len() as u64),
+            sha256: Some(Sha256::digest(content).iter().map(|b| format!("{:02x}", b)).collect()),
+        },
+        Version::TooLarge(size) => Summary { size: *size, sha256: None },
+        _ => Summary { size: Some(0), sha256: None },
+    }
+}
+
+/// Unified diff lines of two texts, hunks with CONTEXT_LINES of context
+fn diff_lines(old: &str,
+              new: &str)
+              -> Vec<DiffLine>
+{
+    let diff = TextDiff::configure().timeout(DIFF_TIMEOUT).diff_lines(old, new);
+    let mut lines = Vec::new();
+
+    for group in diff.grouped_ops(CONTEXT_LINES)
+    {
+        lines.push(DiffLine {
+            kind: LineKind::Hunk,
+            old_no: None,
+            new_no: None,
+            text: UnifiedHunkHeader::new(&group).to_string(),
+        });
+        for op in &group
+        {
+            for change in diff.iter_changes(op)
+            {
+                let kind = match change.tag()
+                {
+                    ChangeTag::Equal => LineKind::Context,
+                    ChangeTag::Delete => LineKind::Removed,
+                    ChangeTag::Insert => LineKind::Added,
+                };
+                lines.push(DiffLine {
+                    kind,
+                    old_no: change.old_index().map(|i| i + 1),
+                    new_no: change.new_index().map(|i| i + 1),
+                    text: change.value().trim_end_matches(['\n', '\r']).replace('\t', "    "),
+                });
+            }
+        }
+    }
+    lines
+}
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::file::{FileNode, format_bytes};
+
+/// Recursive size of a directory
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DirSize
+{
+    pub bytes: u64,
+    pub files: u64,
+}
+
+impl DirSize
+{
+    /// Format size for display
+    pub fn formatted_size(&self) -> String
+    {
+        format_bytes(self.bytes)
+    }
+
+    /// Format file count for display
+    pub fn formatted_files(&self) -> String
+    {
+        if self.files == 1
+        {
+            "1 file".to_string()
+        }
+        else
+        {
+            format!("{} files", self.files)
+        }
+    }
+}
+
+/// Directory sizes known so far for one snapshot
+#[derive(Debug, Default)]
+pub struct DirSizeIndex
+{
+    /// Identifies the listing that fills this index
+    pub run: usize,
+    pub sizes: HashMap<String, DirSize>,
+    /// True once the recursive listing has finished
+    pub complete: bool,
+    /// Set to stop the listing when another snapshot is opened
+    cancel: Arc<AtomicBool>,
+}
+
+impl DirSizeIndex
+{
+    pub fn new(run: usize) -> Self
+    {
+        Self {
+            run,
+            ..Self::default()
+        }
+    }
+
+    /// Flag shared with the background listing
+    pub fn cancel_flag(&self) -> Arc<AtomicBool>
+    {
+        self.cancel.clone()
+    }
+
+    /// Stop the background listing (it ends at its next batch)
+    pub fn cancel(&self)
+    {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Open directory on the accumulator stack
+struct OpenDir
+{
+    path: String,
+    size: DirSize,
+}
+
+/// Computes directory sizes from a depth-first recursive listing.
+///
+/// `restic ls` emits a directory before its contents and finishes a subtree
+/// before moving to the next sibling, so a directory is complete as soon as
+/// an entry outside of it shows up.
+#[derive(Default)]
+pub struct DirSizeAccumulator
+{
+    stack: Vec<OpenDir>,
+}
+
+impl DirSizeAccumulator
+{
+    /// Add a node, returning directories that are now complete
+    pub fn push(&mut self,
+                node: &FileNode)
+                -> Vec<(String, DirSize)>
+    {
+        let mut done = Vec::new();
+
+        while let Some(top) = self.stack.last()
+        {
+            if is_under(&node.path, &top.path)
+            {
+                break;
+            }
+            self.close_top(&mut done);
+        }
+
+        if node.is_dir()
+        {
+            self.stack.push(OpenDir {
+                path: node.path.clone(),
+                size: DirSize::default(),
+            });
+        }
+        else if let Some(top) = self.stack.last_mut()
+        {
+            top.size.bytes += node.size.unwrap_or(0);
+            top.size.files += 1;
+        }
+
+        done
+    }
+
+    /// Close all remaining directories at the end of the listing
+    pub fn finish(mut self) -> Vec<(String, DirSize)>
+    {
+        let mut done = Vec::new();
+        while !self.stack.is_empty()
+        {
+            self.close_top(&mut done);
+        }
+        done
+    }
+
+    /// Pop the innermost directory and roll its totals into the parent
+    fn close_top(&mut self,
+                 done: &mut Vec<(String, DirSize)>)
+    {
+        if let Some(dir) = self.stack.pop()
+        {
+            if let Some(parent) = self.stack.last_mut()
+            {
+                parent.size.bytes += dir.size.bytes;
+                parent.size.files += dir.size.files;
+            }
+            done.push((dir.path, dir.size));
+        }
+    }
+}
+
+/// Check if path is strictly below dir
+fn is_under(path: &str,
+            dir: &str)
+            -> bool
+{
+    let dir = dir.trim_end_matches('/');
+    path.strip_prefix(dir)
+        .is_some_and(|rest| rest.starts_with('/') && rest.len() > 1)
+}
@@ -1,4 +1,10 @@
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+
+use crate::diff::DiffInput;
+use crate::forget::ForgetPolicy;
+use crate::stats::{StatsMode, StatsTarget};
 
 /// Commands that result from user input
 #[derive(Debug, Clone)]
@@ -9,12 +15,75 @@ pub enum Command
     {
         path: String
     },
-    /// Download the selected file/directory
-    Download
+    /// Fetch the preview column contents for an entry (directory listing or file head)
+    LoadPreview
+    {
+        snapshot_id: String,
+        path: String,
+        is_dir: bool,
+    },
+    /// Fetch one version of a file for a diff (up to DIFF_MAX_BYTES + 1 bytes)
+    LoadFileVersion
+    {
+        snapshot_id: String,
+        path: String,
+    },
+    /// Read the local file standing in for one version of a diff (up to
+    /// DIFF_MAX_BYTES + 1 bytes)
+    LoadLocalFileVersion
+    {
+        snapshot_id: String,
+        path: String,
+        file: PathBuf,
+    },
+    /// Read the local directory compared with the snapshot directory `path`
+    LoadLocalListing
+    {
+        path: String,
+        dir: PathBuf,
+    },
+    /// Diff both fetched versions of a file off the UI thread
+    ComputeDiff
     {
         path: String,
-        target: String,
+        left_id: String,
+        right_id: String,
+        input: DiffInput,
     },
+    /// Compute recursive directory sizes for a snapshot in the background
+    ComputeDirSizes
+    {
+        run: usize,
+        snapshot_id: String,
+        /// Set by the app when another snapshot is opened
+        cancel: Arc<AtomicBool>,
+    },
+    /// List everything below `paths` for a deep search, streaming the entries
+    DeepSearch
+    {
+        id: usize,
+        snapshot_id: String,
+        paths: Vec<String>,
+        /// Set by the app when the search is replaced
+        cancel: Arc<AtomicBool>,
+    },
+    /// Simulate a retention policy with `restic forget --dry-run`
+    SimulateForget
+    {
+        policy: ForgetPolicy,
+    },
+    /// Run `restic check` in the background
+    Check
+    {
+        read_data_subset: Option<String>,
+    },
+    /// Run `restic stats` for each (target, mode) pair
+    LoadStats
+    {
+        requests: Vec<(StatsTarget, StatsMode)>,
+    },
+    /// Several commands at once (e.g. opening a snapshot at a path)
+    Batch(Vec<Command>),
     /// Quit the application
     Quit,
 }
@@ -32,65 +101,3 @@ pub enum Movement
     Top,         // Go to top (Home, gg)
     Bottom,      // Go to bottom (End, G)
 }
-
-/// Convert a key event to movement
-pub fn key_to_movement(key: &KeyEvent) -> Option<Movement>
-{
-    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
-
-    match (key.code, ctrl)
-    {
-        // Vi-style Ctrl navigation
-        (KeyCode::Char('f'), true) => Some(Movement::PageDown),
-        (KeyCode::Char('b'), true) => Some(Movement::PageUp),
-        (KeyCode::Char('d'), true) => Some(Movement::HalfPageDown),
-        (KeyCode::Char('u'), true) => Some(Movement::HalfPageUp),
-
-        // Standard navigation
-        (KeyCode::Up, _) | (KeyCode::Char('k'), false) => Some(Movement::Up(1)),
-        (KeyCode::Down, _) | (KeyCode::Char('j'), false) => Some(Movement::Down(1)),
-        (KeyCode::PageUp, _) => Some(Movement::PageUp),
-        (KeyCode::PageDown, _) => Some(Movement::PageDown),
-        (KeyCode::Home, _) | (KeyCode::Char('g'), false) => Some(Movement::Top),
-        (KeyCode::End, _) | (KeyCode::Char('G'), false) => Some(Movement::Bottom),
-
-        _ => None,
-    }
-}
-
-
-/// Check if key is a panel switch
-pub fn is_panel_switch(key: KeyCode) -> bool
-{
-    matches!(key, KeyCode::Tab | KeyCode::BackTab)
-}
-
-/// Check if key is a selection/enter
-pub fn is_select(key: KeyCode) -> bool
-{
-    matches!(key, KeyCode::Enter)
-}
-
-/// Check if key is go back
-pub fn is_back(key: KeyCode) -> bool
-{
-    matches!(key, KeyCode::Backspace | KeyCode::Left | KeyCode::Char('h'))
-}
-
-/// Check if key is download
-pub fn is_download(key: KeyCode) -> bool
-{
-    matches!(key, KeyCode::Char('d'))
-}
-
-/// Check if key is quit
-pub fn is_quit(key: KeyCode) -> bool
-{
-    matches!(key, KeyCode::Char('q') | KeyCode::Esc)
-}
-
-/// Check if key is help
-pub fn is_help(key: KeyCode) -> bool
-{
-    matches!(key, KeyCode::Char('?'))
-}
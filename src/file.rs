@@ -1,6 +1,9 @@
-use serde::Deserialize;
+use std::cmp::Ordering;
 
-#[derive(Debug, Clone, Deserialize)]
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct FileNode
 {
     pub name: String,
@@ -8,6 +11,8 @@ pub struct FileNode
     pub node_type: String,
     pub path: String,
     pub size: Option<u64>,
+    #[serde(default)]
+    pub mtime: Option<DateTime<Utc>>,
 }
 
 impl FileNode
@@ -18,6 +23,31 @@ impl FileNode
         self.node_type == "dir"
     }
 
+    /// Formats the modification time for display (strftime `format`)
+    pub fn formatted_mtime(&self,
+                           format: &str)
+                           -> String
+    {
+        match self.mtime
+        {
+            Some(t) => t.format(format).to_string(),
+            None => String::new(),
+        }
+    }
+
+    /// Lowercase file extension (empty for directories and files without one)
+    pub fn extension(&self) -> String
+    {
+        if self.is_dir()
+        {
+            return String::new();
+        }
+        std::path::Path::new(&self.name)
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default()
+    }
+
     /// Format size for display
     pub fn formatted_size(&self) -> String
     {
@@ -37,7 +67,7 @@ impl FileNode
 }
 
 /// Format bytes into human-readable size
-fn format_bytes(bytes: u64) -> String
+pub fn format_bytes(bytes: u64) -> String
 {
     const KB: u64 = 1024;
     const MB: u64 = KB * 1024;
@@ -74,6 +104,7 @@ pub fn parent_entry(current_path: &str) -> FileNode
         node_type: "dir".to_string(),
         path: parent_path,
         size: None,
+        mtime: None,
     }
 }
 
@@ -85,5 +116,188 @@ pub fn path_entry(path: &str) -> FileNode
         node_type: "dir".to_string(),
         path: path.to_string(),
         size: None,
+        mtime: None,
+    }
+}
+
+/// Sort key for the Files panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey
+{
+    Name,       // Case-insensitive name
+    Natural,    // Name with embedded numbers compared by value (file2 < file10)
+    Size,       // Largest first (directories by recursive size)
+    Mtime,      // Newest first
+    Extension,  // Extension, then name
+}
+
+impl SortKey
+{
+    /// Next key in the cycle
+    pub fn next(&self) -> Self
+    {
+        match self
+        {
+            SortKey::Name => SortKey::Natural,
+            SortKey::Natural => SortKey::Size,
+            SortKey::Size => SortKey::Mtime,
+            SortKey::Mtime => SortKey::Extension,
+            SortKey::Extension => SortKey::Name,
+        }
+    }
+
+    /// Parse a label (as shown in the panel title)
+    pub fn parse(label: &str) -> Option<Self>
+    {
+        match label
+        {
+            "name" => Some(SortKey::Name),
+            "natural" => Some(SortKey::Natural),
+            "size" => Some(SortKey::Size),
+            "mtime" => Some(SortKey::Mtime),
+            "type" | "extension" => Some(SortKey::Extension),
+            _ => None,
+        }
+    }
+
+    /// Short label for the panel title
+    pub fn label(&self) -> &'static str
+    {
+        match self
+        {
+            SortKey::Name => "name",
+            SortKey::Natural => "natural",
+            SortKey::Size => "size",
+            SortKey::Mtime => "mtime",
+            SortKey::Extension => "type",
+        }
+    }
+}
+
+/// Sort order for the Files panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileSort
+{
+    pub key: SortKey,
+    pub reverse: bool,
+    pub dirs_first: bool,
+}
+
+impl Default for FileSort
+{
+    fn default() -> Self
+    {
+        Self {
+            key: SortKey::Name,
+            reverse: false,
+            dirs_first: true,
+        }
+    }
+}
+
+impl FileSort
+{
+    /// Label for the panel title, e.g. "size, rev, dirs first"
+    pub fn label(&self) -> String
+    {
+        let mut label = self.key.label().to_string();
+        if self.reverse
+        {
+            label.push_str(", rev");
+        }
+        if self.dirs_first
+        {
+            label.push_str(", dirs first");
+        }
+        label
+    }
+
+    /// Compare two nodes; `size_of` supplies sizes (recursive for directories)
+    pub fn compare(&self,
+                   a: &FileNode,
+                   b: &FileNode,
+                   size_of: impl Fn(&FileNode) -> u64)
+                   -> Ordering
+    {
+        if self.dirs_first
+        {
+            let dirs = b.is_dir().cmp(&a.is_dir());
+            if dirs != Ordering::Equal
+            {
+                return dirs;
+            }
+        }
+
+        let by_name = || a.name.to_lowercase().cmp(&b.name.to_lowercase());
+        let ordering = match self.key
+        {
+            SortKey::Name => by_name(),
+            SortKey::Natural => natural_cmp(&a.name, &b.name),
+            SortKey::Size => size_of(b).cmp(&size_of(a)).then_with(by_name),
+            SortKey::Mtime => b.mtime.cmp(&a.mtime).then_with(by_name),
+            SortKey::Extension => a.extension().cmp(&b.extension()).then_with(by_name),
+        };
+
+        if self.reverse { ordering.reverse() } else { ordering }
+    }
+}
+
+/// Compare names with runs of digits compared by numeric value
+pub fn natural_cmp(a: &str,
+                   b: &str)
+                   -> Ordering
+{
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+
+    loop
+    {
+        match (a_chars.peek().copied(), b_chars.peek().copied())
+        {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() =>
+            {
+                let x_num = take_digits(&mut a_chars);
+                let y_num = take_digits(&mut b_chars);
+                let x_trimmed = x_num.trim_start_matches('0');
+                let y_trimmed = y_num.trim_start_matches('0');
+
+                // Longer number (without leading zeros) is larger
+                let ordering = x_trimmed.len().cmp(&y_trimmed.len())
+                                                .then_with(|| x_trimmed.cmp(y_trimmed));
+                if ordering != Ordering::Equal
+                {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) =>
+            {
+                let ordering = x.to_lowercase().cmp(y.to_lowercase());
+                if ordering != Ordering::Equal
+                {
+                    return ordering;
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+/// Consume a run of ASCII digits
+fn take_digits(chars: &mut std::iter::Peekable<std::str::Chars>) -> String
+{
+    let mut digits = String::new();
+    while let Some(c) = chars.peek().copied()
+    {
+        if !c.is_ascii_digit()
+        {
+            break;
+        }
+        digits.push(c);
+        chars.next();
     }
+    digits
 }
@@ -0,0 +1,311 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Deserialize;
+
+/// Retention rules passed to `restic forget`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForgetPolicy
+{
+    pub keep_last: Option<u32>,
+    pub keep_hourly: Option<u32>,
+    pub keep_daily: Option<u32>,
+    pub keep_weekly: Option<u32>,
+    pub keep_monthly: Option<u32>,
+    pub keep_yearly: Option<u32>,
+    /// Duration such as "1y6m" (restic's --keep-within syntax)
+    pub keep_within: Option<String>,
+    /// Comma-separated grouping (host, paths, tags)
+    pub group_by: String,
+}
+
+impl Default for ForgetPolicy
+{
+    fn default() -> Self
+    {
+        Self {
+            keep_last: None,
+            keep_hourly: None,
+            keep_daily: None,
+            keep_weekly: None,
+            keep_monthly: None,
+            keep_yearly: None,
+            keep_within: None,
+            group_by: "host,paths".to_string(),
+        }
+    }
+}
+
+impl ForgetPolicy
+{
+    /// True if no keep rule is set (restic would keep everything)
+    pub fn is_empty(&self) -> bool
+    {
+        self.keep_last.is_none()
+            && self.keep_hourly.is_none()
+            && self.keep_daily.is_none()
+            && self.keep_weekly.is_none()
+            && self.keep_monthly.is_none()
+            && self.keep_yearly.is_none()
+            && self.keep_within.is_none()
+    }
+
+    /// Short description, e.g. "last 3, daily 7, within 1y"
+    pub fn summary(&self) -> String
+    {
+        let counts = [
+            ("last", self.keep_last),
+            ("hourly", self.keep_hourly),
+            ("daily", self.keep_daily),
+            ("weekly", self.keep_weekly),
+            ("monthly", self.keep_monthly),
+            ("yearly", self.keep_yearly),
+        ];
+        let mut parts: Vec<String> = counts.iter()
+                                           .filter_map(|(name, n)| n.map(|n| format!("{} {}", name, n)))
+                                           .collect();
+        if let Some(ref within) = self.keep_within
+        {
+            parts.push(format!("within {}", within));
+        }
+        parts.join(", ")
+    }
+
+    /// Command-line flags for the policy
+    pub fn to_args(&self) -> Vec<String>
+    {
+        let mut args = Vec::new();
+        let counts = [
+            ("--keep-last", self.keep_last),
+            ("--keep-hourly", self.keep_hourly),
+            ("--keep-daily", self.keep_daily),
+            ("--keep-weekly", self.keep_weekly),
+            ("--keep-monthly", self.keep_monthly),
+            ("--keep-yearly", self.keep_yearly),
+        ];
+        for (flag, value) in counts
+        {
+            if let Some(n) = value
+            {
+                args.push(flag.to_string());
+                args.push(n.to_string());
+            }
+        }
+        if let Some(ref within) = self.keep_within
+        {
+            args.push("--keep-within".to_string());
+            args.push(within.clone());
+        }
+        args.push("--group-by".to_string());
+        args.push(self.group_by.clone());
+        args
+    }
+}
+
+/// Snapshot reference inside `restic forget --json` output
+#[derive(Debug, Clone, Deserialize)]
+pub struct ForgetSnapshot
+{
+    pub id: String,
+}
+
+/// Why a snapshot is kept
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeepReason
+{
+    pub snapshot: ForgetSnapshot,
+    #[serde(default)]
+    pub matches: Vec<String>,
+}
+
+/// One snapshot group from `restic forget --dry-run --json`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ForgetGroup
+{
+    #[serde(default)]
+    pub keep: Option<Vec<ForgetSnapshot>>,
+    #[serde(default)]
+    pub remove: Option<Vec<ForgetSnapshot>>,
+    #[serde(default)]
+    pub reasons: Option<Vec<KeepReason>>,
+}
+
+/// Outcome of a simulated policy, keyed by full snapshot ID
+#[derive(Debug, Clone, Default)]
+pub struct ForgetPlan
+{
+    pub policy: ForgetPolicy,
+    /// Kept snapshots with the rules that matched them
+    pub kept: HashMap<String, Vec<String>>,
+    pub removed: HashSet<String>,
+}
+
+impl ForgetPlan
+{
+    /// Build a plan from the groups restic reported
+    pub fn from_groups(policy: ForgetPolicy,
+                       groups: Vec<ForgetGroup>)
+                       -> Self
+    {
+        let mut plan = Self {
+            policy,
+            ..Default::default()
+        };
+
+        for group in groups
+        {
+            for snapshot in group.keep.unwrap_or_default()
+            {
+                plan.kept.entry(snapshot.id).or_default();
+            }
+            for reason in group.reasons.unwrap_or_default()
+            {
+                plan.kept.insert(reason.snapshot.id, reason.matches);
+            }
+            for snapshot in group.remove.unwrap_or_default()
+            {
+                plan.removed.insert(snapshot.id);
+            }
+        }
+
+        plan
+    }
+}
+
+/// Labels for the policy dialog fields, in display order
+pub const POLICY_FIELDS: &[&str] = &[
+    "Keep last",
+    "Keep hourly",
+    "Keep daily",
+    "Keep weekly",
+    "Keep monthly",
+    "Keep yearly",
+    "Keep within",
+    "Group by",
+];
+
+/// Retention policy dialog state (one text value per POLICY_FIELDS entry)
+pub struct ForgetDialog
+{
+    pub values: Vec<String>,
+    pub focus: usize,
+    pub error: Option<String>,
+}
+
+impl ForgetDialog
+{
+    /// Create a dialog prefilled from a policy
+    pub fn new(policy: &ForgetPolicy) -> Self
+    {
+        let count = |n: Option<u32>| n.map(|n| n.to_string()).unwrap_or_default();
+        Self {
+            values: vec![
+                count(policy.keep_last),
+                count(policy.keep_hourly),
+                count(policy.keep_daily),
+                count(policy.keep_weekly),
+                count(policy.keep_monthly),
+                count(policy.keep_yearly),
+                policy.keep_within.clone().unwrap_or_default(),
+                policy.group_by.clone(),
+            ],
+            focus: 0,
+            error: None,
+        }
+    }
+
+    /// Move focus to next field
+    pub fn focus_next(&mut self)
+    {
+        self.focus = (self.focus + 1) % self.values.len();
+    }
+
+    /// Move focus to previous field
+    pub fn focus_prev(&mut self)
+    {
+        self.focus = (self.focus + self.values.len() - 1) % self.values.len();
+    }
+
+    /// Append a character to the focused field
+    pub fn insert_char(&mut self,
+                       c: char)
+    {
+        self.values[self.focus].push(c);
+        self.error = None;
+    }
+
+    /// Delete the last character of the focused field
+    pub fn backspace(&mut self)
+    {
+        self.values[self.focus].pop();
+        self.error = None;
+    }
+
+    /// Parse the fields into a policy
+    pub fn policy(&self) -> Result<ForgetPolicy, String>
+    {
+        let count = |i: usize| -> Result<Option<u32>, String> {
+            let value = self.values[i].trim();
+            if value.is_empty()
+            {
+                return Ok(None);
+            }
+            value.parse::<u32>()
+                 .map(Some)
+                 .map_err(|_| format!("{}: '{}' is not a number", POLICY_FIELDS[i], value))
+        };
+
+        let within = self.values[6].trim();
+        if !within.is_empty() && !is_valid_duration(within)
+        {
+            return Err(format!("Keep within: '{}' is not a duration like 1y6m2d", within));
+        }
+
+        let group_by = self.values[7].trim();
+        if !group_by.split(',').filter(|g| !g.is_empty()).all(|g| matches!(g, "host" | "paths" | "tags"))
+        {
+            return Err("Group by: use host, paths and/or tags separated by commas".to_string());
+        }
+
+        let policy = ForgetPolicy {
+            keep_last: count(0)?,
+            keep_hourly: count(1)?,
+            keep_daily: count(2)?,
+            keep_weekly: count(3)?,
+            keep_monthly: count(4)?,
+            keep_yearly: count(5)?,
+            keep_within: if within.is_empty() { None } else { Some(within.to_string()) },
+            group_by: group_by.to_string(),
+        };
+
+        if policy.is_empty()
+        {
+            return Err("Set at least one keep rule".to_string());
+        }
+
+        Ok(policy)
+    }
+}
+
+/// Check restic duration syntax: number+unit pairs with units y, m, d, h
+fn is_valid_duration(s: &str) -> bool
+{
+    let mut has_digits = false;
+    let mut has_unit = false;
+    for c in s.chars()
+    {
+        if c.is_ascii_digit()
+        {
+            has_digits = true;
+        }
+        else if matches!(c, 'y' | 'm' | 'd' | 'h') && has_digits
+        {
+            has_digits = false;
+            has_unit = true;
+        }
+        else
+        {
+            return false;
+        }
+    }
+    has_unit && !has_digits
+}
@@ -0,0 +1,185 @@
+use crate::file::FileNode;
+
+/// What the prompt does once a directory listing arrives
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GoToWaiting
+{
+    Complete(String),   // Complete the input against this directory
+    Open(String),       // Look the input up in this directory and go there
+}
+
+/// Prompt for an absolute path within the current snapshot
+pub struct GoToPrompt
+{
+    pub input: String,
+    pub cursor: usize,
+    /// Names matching the last ambiguous completion
+    pub candidates: Vec<String>,
+    pub error: Option<String>,
+    /// Set while the listing needed for Tab or Enter is fetched
+    pub waiting: Option<GoToWaiting>,
+}
+
+impl GoToPrompt
+{
+    /// Start with the directory shown in the Files panel
+    pub fn new(current_path: &str) -> Self
+    {
+        let input = format!("{}/", current_path.trim_end_matches('/'));
+        Self {
+            cursor: input.len(),
+            input,
+            candidates: Vec::new(),
+            error: None,
+            waiting: None,
+        }
+    }
+
+    /// Directory the last path component is completed in
+    pub fn completion_dir(&self) -> String
+    {
+        match self.input.rfind('/')
+        {
+            Some(0) | None => "/".to_string(),
+            Some(i) => self.input[..i].to_string(),
+        }
+    }
+
+    /// Complete the last path component against the entries of `completion_dir`.
+    ///
+    /// A single match is completed (directories get a trailing slash);
+    /// several matches are extended to their common prefix and listed.
+    pub fn complete(&mut self,
+                    entries: &[FileNode],
+                    show_hidden: bool)
+    {
+        let start = self.input.rfind('/').map_or(0, |i| i + 1);
+        let prefix = self.input[start..].to_string();
+
+        // Dotfiles only when hidden files are shown or asked for
+        let matches: Vec<&FileNode> = entries
+            .iter()
+            .filter(|e| e.name != ".." && e.name.starts_with(&prefix))
+            .filter(|e| show_hidden || prefix.starts_with('.') || !e.name.starts_with('.'))
+            .collect();
+
+        self.candidates.clear();
+        match matches.as_slice()
+        {
+            [] =>
+            {
+                self.error = Some(format!("No match for \"{}\"", prefix));
+            }
+            [single] =>
+            {
+                self.input.truncate(start);
+                self.input.push_str(&single.name);
+                if single.is_dir()
+                {
+                    self.input.push('/');
+                }
+                self.error = None;
+            }
+            several =>
+            {
+                let common = several.iter()
+                                    .map(|e| e.name.as_str())
+                                    .reduce(common_prefix)
+                                    .unwrap_or("");
+                self.input.truncate(start);
+                self.input.push_str(common);
+                self.candidates = several.iter()
+                                         .map(|e| if e.is_dir() { format!("{}/", e.name) } else { e.name.clone() })
+                                         .collect();
+                self.error = None;
+            }
+        }
+        self.cursor = self.input.len();
+    }
+
+    /// Input changed by hand: forget completion results
+    fn edited(&mut self)
+    {
+        self.candidates.clear();
+        self.error = None;
+        self.waiting = None;
+    }
+
+    pub fn insert_char(&mut self,
+                       c: char)
+    {
+        // Typing a full path after the prefilled one shouldn't leave "//"
+        if c == '/' && self.input[..self.cursor].ends_with('/')
+        {
+            return;
+        }
+        self.input.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+        self.edited();
+    }
+
+    pub fn backspace(&mut self)
+    {
+        if let Some(c) = self.input[..self.cursor].chars().next_back()
+        {
+            self.cursor -= c.len_utf8();
+            self.input.remove(self.cursor);
+            self.edited();
+        }
+    }
+
+    pub fn delete(&mut self)
+    {
+        if self.cursor < self.input.len()
+        {
+            self.input.remove(self.cursor);
+            self.edited();
+        }
+    }
+
+    /// Delete the path component before the cursor (Ctrl-W)
+    pub fn delete_component(&mut self)
+    {
+        let before = self.input[..self.cursor].trim_end_matches('/');
+        let start = before.rfind('/').map_or(0, |i| i + 1);
+        self.input.replace_range(start..self.cursor, "");
+        self.cursor = start;
+        self.edited();
+    }
+
+    /// Delete everything before the cursor (Ctrl-U)
+    pub fn delete_to_start(&mut self)
+    {
+        self.input.replace_range(..self.cursor, "");
+        self.cursor = 0;
+        self.edited();
+    }
+
+    pub fn cursor_left(&mut self)
+    {
+        if let Some(c) = self.input[..self.cursor].chars().next_back()
+        {
+            self.cursor -= c.len_utf8();
+        }
+    }
+
+    pub fn cursor_right(&mut self)
+    {
+        if let Some(c) = self.input[self.cursor..].chars().next()
+        {
+            self.cursor += c.len_utf8();
+        }
+    }
+}
+
+/// Longest common prefix of two names (on character boundaries)
+fn common_prefix<'a>(a: &'a str,
+                     b: &str)
+                     -> &'a str
+{
+    let len = a.char_indices()
+               .zip(b.chars())
+               .find(|((_, x), y)| x != y)
+               .map_or_else(|| a.len().min(b.len()), |((i, _), _)| i);
+    &a[..len]
+}
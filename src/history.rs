@@ -0,0 +1,96 @@
+use crate::search::SearchMode;
+
+/// Locations kept in each direction
+const MAX_HISTORY: usize = 100;
+
+/// A directory of a snapshot as it was shown in the Files panel
+#[derive(Debug, Clone)]
+pub struct Location
+{
+    pub snapshot_id: String,
+    /// "" = the snapshot's paths root
+    pub path: String,
+    /// Entry under the cursor (by path, since the listing may be sorted differently)
+    pub selected: Option<String>,
+    pub scroll: usize,
+    pub search_query: String,
+    pub search_mode: SearchMode,
+}
+
+impl Location
+{
+    fn same_place(&self,
+                  other: &Location)
+                  -> bool
+    {
+        self.snapshot_id == other.snapshot_id && self.path == other.path
+    }
+}
+
+/// Browser-style back/forward history of Files panel locations
+#[derive(Debug, Default)]
+pub struct History
+{
+    back: Vec<Location>,
+    forward: Vec<Location>,
+}
+
+impl History
+{
+    /// Record the location being left for somewhere new (drops the forward list)
+    pub fn push(&mut self,
+                location: Location)
+    {
+        self.forward.clear();
+        // Leaving the same place twice in a row keeps the later state only
+        if self.back.last().is_some_and(|last| last.same_place(&location))
+        {
+            self.back.pop();
+        }
+        push_bounded(&mut self.back, location);
+    }
+
+    /// Step back: returns the location to show and remembers `current` for forward
+    pub fn back(&mut self,
+                current: Option<Location>)
+                -> Option<Location>
+    {
+        let location = self.back.pop()?;
+        if let Some(current) = current
+        {
+            push_bounded(&mut self.forward, current);
+        }
+        Some(location)
+    }
+
+    /// Step forward: returns the location to show and remembers `current` for back
+    pub fn forward(&mut self,
+                   current: Option<Location>)
+                   -> Option<Location>
+    {
+        let location = self.forward.pop()?;
+        if let Some(current) = current
+        {
+            push_bounded(&mut self.back, current);
+        }
+        Some(location)
+    }
+
+    /// Drop every location in a snapshot (e.g. one that no longer exists)
+    pub fn remove_snapshot(&mut self,
+                           snapshot_id: &str)
+    {
+        self.back.retain(|l| l.snapshot_id != snapshot_id);
+        self.forward.retain(|l| l.snapshot_id != snapshot_id);
+    }
+}
+
+fn push_bounded(list: &mut Vec<Location>,
+                location: Location)
+{
+    if list.len() >= MAX_HISTORY
+    {
+        list.remove(0);
+    }
+    list.push(location);
+}
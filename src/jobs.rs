@@ -0,0 +1,159 @@
+use std::time::{Duration, Instant};
+
+use crate::check::format_duration;
+
+/// Default number of jobs allowed to run at the same time
+pub const DEFAULT_MAX_JOBS: usize = 2;
+
+/// What a job does
+#[derive(Debug, Clone)]
+pub enum JobKind
+{
+    /// `restic restore --include <path>... --target <target>`
+    Restore
+    {
+        snapshot_id: String,
+        paths: Vec<String>,
+        target: String,
+    },
+    /// `restic dump <path>` written to a local file
+    Dump
+    {
+        snapshot_id: String,
+        path: String,
+        target_file: String,
+        /// Expected size, used for progress (None for directories, dumped as tar)
+        size: Option<u64>,
+    },
+}
+
+impl JobKind
+{
+    /// Short verb for the jobs list
+    pub fn verb(&self) -> &'static str
+    {
+        match self
+        {
+            JobKind::Restore { .. } => "restore",
+            JobKind::Dump { .. } => "dump",
+        }
+    }
+
+    /// "source -> destination" description
+    pub fn description(&self) -> String
+    {
+        match self
+        {
+            JobKind::Restore { paths, target, .. } => match paths.as_slice()
+            {
+                [path] => format!("{} -> {}", path, target),
+                _ => format!("{} entries -> {}", paths.len(), target),
+            },
+            JobKind::Dump { path, target_file, .. } => format!("{} -> {}", path, target_file),
+        }
+    }
+}
+
+/// Lifecycle of a job
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobStatus
+{
+    Queued,
+    Running,
+    Done,
+    Failed(String),
+}
+
+/// A queued, running or finished long operation
+pub struct Job
+{
+    pub id: usize,
+    pub kind: JobKind,
+    pub status: JobStatus,
+    /// Fraction done (0.0 - 1.0) when the command reports it
+    pub progress: Option<f64>,
+    pub started: Option<Instant>,
+    pub duration: Option<Duration>,
+}
+
+impl Job
+{
+    pub fn new(id: usize,
+               kind: JobKind)
+               -> Self
+    {
+        Self {
+            id,
+            kind,
+            status: JobStatus::Queued,
+            progress: None,
+            started: None,
+            duration: None,
+        }
+    }
+
+    /// Mark the job as running
+    pub fn start(&mut self)
+    {
+        self.status = JobStatus::Running;
+        self.progress = None;
+        self.started = Some(Instant::now());
+        self.duration = None;
+    }
+
+    /// Record the final result
+    pub fn finish(&mut self,
+                  result: Result<(), String>,
+                  duration: Duration)
+    {
+        self.duration = Some(duration);
+        self.status = match result
+        {
+            Ok(()) =>
+            {
+                self.progress = Some(1.0);
+                JobStatus::Done
+            }
+            Err(e) => JobStatus::Failed(e),
+        };
+    }
+
+    /// Put a failed job back in the queue
+    pub fn retry(&mut self) -> bool
+    {
+        if !matches!(self.status, JobStatus::Failed(_))
+        {
+            return false;
+        }
+        self.status = JobStatus::Queued;
+        self.progress = None;
+        self.started = None;
+        self.duration = None;
+        true
+    }
+
+    pub fn is_finished(&self) -> bool
+    {
+        matches!(self.status, JobStatus::Done | JobStatus::Failed(_))
+    }
+
+    /// Status text for the jobs list, e.g. "running 45% 12.0s"
+    pub fn status_text(&self) -> String
+    {
+        let elapsed = self.duration
+                          .or_else(|| self.started.map(|s| s.elapsed()))
+                          .map(format_duration)
+                          .unwrap_or_default();
+        match &self.status
+        {
+            JobStatus::Queued => "queued".to_string(),
+            JobStatus::Running => match self.progress
+            {
+                Some(p) => format!("running {:>3.0}% {}", p * 100.0, elapsed),
+                None => format!("running {}", elapsed),
+            },
+            JobStatus::Done => format!("done {}", elapsed),
+            JobStatus::Failed(_) => format!("failed {}", elapsed),
+        }
+    }
+}
@@ -0,0 +1,597 @@
+use std::collections::BTreeMap;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+use crate::app::Panel;
+use crate::event::Movement;
+
+/// Named actions that keys can be bound to (outside of dialogs and text input)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action
+{
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    HalfPageUp,
+    HalfPageDown,
+    Top,
+    Bottom,
+    SwitchPanel,
+    Select,
+    Open,
+    Back,
+    HistoryBack,
+    HistoryForward,
+    OlderSnapshot,
+    NewerSnapshot,
+    Compare,
+    CompareLocal,
+    Search,
+    DeepSearch,
+    GoToPath,
+    SortNext,
+    SortReverse,
+    DirsFirst,
+    ToggleHidden,
+    Layout,
+    Expand,
+    Collapse,
+    Download,
+    Dump,
+    Bookmark,
+    BookmarkPinned,
+    Bookmarks,
+    Forget,
+    ClearForget,
+    RetryJob,
+    ClearJobs,
+    MoreJobs,
+    FewerJobs,
+    Check,
+    DismissCheck,
+    Stats,
+    Help,
+    Quit,
+}
+
+/// Actions grouped for the help overlay, in display order
+pub const SECTIONS: &[&[Action]] = &[
+    &[
+        Action::Up,
+        Action::Down,
+        Action::PageDown,
+        Action::PageUp,
+        Action::HalfPageDown,
+        Action::HalfPageUp,
+        Action::Top,
+        Action::Bottom,
+    ],
+    &[
+        Action::SwitchPanel,
+        Action::Select,
+        Action::Open,
+        Action::Back,
+        Action::HistoryBack,
+        Action::HistoryForward,
+    ],
+    &[Action::OlderSnapshot, Action::NewerSnapshot, Action::Compare, Action::CompareLocal],
+    &[
+        Action::Search,
+        Action::DeepSearch,
+        Action::GoToPath,
+        Action::SortNext,
+        Action::SortReverse,
+        Action::DirsFirst,
+        Action::ToggleHidden,
+        Action::Layout,
+        Action::Expand,
+        Action::Collapse,
+        Action::Download,
+        Action::Dump,
+        Action::Bookmark,
+        Action::BookmarkPinned,
+        Action::Bookmarks,
+        Action::Forget,
+        Action::ClearForget,
+        Action::RetryJob,
+        Action::ClearJobs,
+        Action::MoreJobs,
+        Action::FewerJobs,
+        Action::Check,
+        Action::DismissCheck,
+        Action::Stats,
+        Action::Help,
+        Action::Quit,
+    ],
+];
+
+impl Action
+{
+    /// Name used in the `[keys]` table of the config file
+    pub fn name(&self) -> &'static str
+    {
+        match self
+        {
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::PageUp => "page_up",
+            Action::PageDown => "page_down",
+            Action::HalfPageUp => "half_page_up",
+            Action::HalfPageDown => "half_page_down",
+            Action::Top => "top",
+            Action::Bottom => "bottom",
+            Action::SwitchPanel => "switch_panel",
+            Action::Select => "select",
+            Action::Open => "open",
+            Action::Back => "back",
+            Action::HistoryBack => "history_back",
+            Action::HistoryForward => "history_forward",
+            Action::OlderSnapshot => "older_snapshot",
+            Action::NewerSnapshot => "newer_snapshot",
+            Action::Compare => "compare",
+            Action::CompareLocal => "compare_local",
+            Action::Search => "search",
+            Action::DeepSearch => "deep_search",
+            Action::GoToPath => "go_to_path",
+            Action::SortNext => "sort_next",
+            Action::SortReverse => "sort_reverse",
+            Action::DirsFirst => "dirs_first",
+            Action::ToggleHidden => "toggle_hidden",
+            Action::Layout => "layout",
+            Action::Expand => "expand",
+            Action::Collapse => "collapse",
+            Action::Download => "download",
+            Action::Dump => "dump",
+            Action::Bookmark => "bookmark",
+            Action::BookmarkPinned => "bookmark_pinned",
+            Action::Bookmarks => "bookmarks",
+            Action::Forget => "forget",
+            Action::ClearForget => "clear_forget",
+            Action::RetryJob => "retry_job",
+            Action::ClearJobs => "clear_jobs",
+            Action::MoreJobs => "more_jobs",
+            Action::FewerJobs => "fewer_jobs",
+            Action::Check => "check",
+            Action::DismissCheck => "dismiss_check",
+            Action::Stats => "stats",
+            Action::Help => "help",
+            Action::Quit => "quit",
+        }
+    }
+
+    /// Description for the help overlay
+    pub fn description(&self) -> &'static str
+    {
+        match self
+        {
+            Action::Up => "Move cursor up",
+            Action::Down => "Move cursor down",
+            Action::PageUp => "Page up (full screen)",
+            Action::PageDown => "Page down (full screen)",
+            Action::HalfPageUp => "Scroll up (half screen)",
+            Action::HalfPageDown => "Scroll down (half screen)",
+            Action::Top => "Go to first item",
+            Action::Bottom => "Go to last item",
+            Action::SwitchPanel => "Switch panel (Snapshots→Files→Log→Jobs→Check)",
+            Action::Select => "Open directory / Select snapshot",
+            Action::Open => "Open directory (columns layout)",
+            Action::Back => "Go to parent directory",
+            Action::HistoryBack => "Go back to the previous location",
+            Action::HistoryForward => "Go forward again",
+            Action::OlderSnapshot => "Same directory in the previous snapshot (same host/paths)",
+            Action::NewerSnapshot => "Same directory in the next snapshot (same host/paths)",
+            Action::Compare => "Compare the directory with another snapshot side by side",
+            Action::CompareLocal => "Compare the directory with a local one (in Files panel)",
+            Action::Search => "Search/filter files (in Files panel)",
+            Action::DeepSearch => "Search all files below the current directory",
+            Action::GoToPath => "Type a path to go to (in Files panel)",
+            Action::SortNext => "Cycle sort order (in Files panel)",
+            Action::SortReverse => "Reverse sort order (in Files panel)",
+            Action::DirsFirst => "Toggle directories first (in Files panel)",
+            Action::ToggleHidden => "Show/hide dotfiles (in Files panel)",
+            Action::Layout => "Cycle list/columns/tree layout (in Files panel)",
+            Action::Expand => "Expand directory (tree layout)",
+            Action::Collapse => "Collapse directory (tree layout)",
+            Action::Download => "Download selected file/folder",
+            Action::Dump => "Dump selected file (dirs as .tar) to a file",
+            Action::Bookmark => "Bookmark the current directory (in Files panel)",
+            Action::BookmarkPinned => "Bookmark the current directory in this snapshot (in Files panel)",
+            Action::Bookmarks => "Open a bookmark",
+            Action::Forget => "Simulate retention policy (Snapshots panel)",
+            Action::ClearForget => "Clear policy simulation (Snapshots panel)",
+            Action::RetryJob => "Retry failed job (Jobs panel)",
+            Action::ClearJobs => "Clear finished jobs (Jobs panel)",
+            Action::MoreJobs => "Raise concurrent jobs (Jobs panel)",
+            Action::FewerJobs => "Lower concurrent jobs (Jobs panel)",
+            Action::Check => "Check repository in the background",
+            Action::DismissCheck => "Dismiss finished check (Check panel)",
+            Action::Stats => "Snapshot/repository stats",
+            Action::Help => "Toggle this help",
+            Action::Quit => "Quit",
+        }
+    }
+
+    /// Panel the action is limited to (None = everywhere)
+    pub fn scope(&self) -> Option<Panel>
+    {
+        match self
+        {
+            Action::Open
+            | Action::Back
+            | Action::Search
+            | Action::DeepSearch
+            | Action::GoToPath
+            | Action::CompareLocal
+            | Action::SortNext
+            | Action::SortReverse
+            | Action::DirsFirst
+            | Action::ToggleHidden
+            | Action::Layout
+            | Action::Expand
+            | Action::Collapse
+            | Action::Download
+            | Action::Dump
+            | Action::Bookmark
+            | Action::BookmarkPinned => Some(Panel::Files),
+            Action::Forget | Action::ClearForget => Some(Panel::Snapshots),
+            Action::RetryJob | Action::ClearJobs | Action::MoreJobs | Action::FewerJobs => Some(Panel::Jobs),
+            Action::DismissCheck => Some(Panel::Check),
+            _ => None,
+        }
+    }
+
+    /// Built-in bindings
+    fn default_keys(&self) -> &'static [&'static str]
+    {
+        match self
+        {
+            Action::Up => &["Up", "k"],
+            Action::Down => &["Down", "j"],
+            Action::PageUp => &["Ctrl-b", "PageUp"],
+            Action::PageDown => &["Ctrl-f", "PageDown"],
+            Action::HalfPageUp => &["Ctrl-u"],
+            Action::HalfPageDown => &["Ctrl-d"],
+            Action::Top => &["g", "Home"],
+            Action::Bottom => &["G", "End"],
+            Action::SwitchPanel => &["Tab", "Shift-Tab"],
+            Action::Select => &["Enter"],
+            Action::Open => &["Right", "l"],
+            Action::Back => &["Backspace", "Left", "h"],
+            // Terminals send Ctrl-I as Tab, so forward has no Ctrl binding
+            Action::HistoryBack => &["Ctrl-o", "Alt-Left"],
+            Action::HistoryForward => &["Alt-Right"],
+            Action::OlderSnapshot => &["["],
+            Action::NewerSnapshot => &["]"],
+            Action::Compare => &["="],
+            Action::CompareLocal => &["L"],
+            Action::Search => &["/"],
+            Action::DeepSearch => &["*"],
+            Action::GoToPath => &[":", "Ctrl-l"],
+            Action::SortNext => &["o"],
+            Action::SortReverse => &["O"],
+            Action::DirsFirst => &["D"],
+            Action::ToggleHidden => &["."],
+            Action::Layout => &["v"],
+            Action::Expand => &["+"],
+            Action::Collapse => &["-"],
+            Action::Download => &["d"],
+            Action::Dump => &["e"],
+            Action::Bookmark => &["m"],
+            Action::BookmarkPinned => &["M"],
+            Action::Bookmarks => &["'"],
+            Action::Forget => &["f"],
+            Action::ClearForget => &["F"],
+            Action::RetryJob => &["r"],
+            Action::ClearJobs => &["x"],
+            Action::MoreJobs => &["+"],
+            Action::FewerJobs => &["-"],
+            Action::Check => &["c"],
+            Action::DismissCheck => &["x"],
+            Action::Stats => &["s"],
+            Action::Help => &["?"],
+            Action::Quit => &["q", "Esc"],
+        }
+    }
+
+    /// Cursor movement performed by the action, if any
+    pub fn movement(&self) -> Option<Movement>
+    {
+        match self
+        {
+            Action::Up => Some(Movement::Up(1)),
+            Action::Down => Some(Movement::Down(1)),
+            Action::PageUp => Some(Movement::PageUp),
+            Action::PageDown => Some(Movement::PageDown),
+            Action::HalfPageUp => Some(Movement::HalfPageUp),
+            Action::HalfPageDown => Some(Movement::HalfPageDown),
+            Action::Top => Some(Movement::Top),
+            Action::Bottom => Some(Movement::Bottom),
+            _ => None,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self>
+    {
+        SECTIONS.iter().flat_map(|s| s.iter()).copied().find(|a| a.name() == name)
+    }
+}
+
+/// A key with modifiers. Shift is part of the character ('G' vs 'g'), so only
+/// Ctrl and Alt are tracked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyChord
+{
+    pub code: KeyCode,
+    pub ctrl: bool,
+    pub alt: bool,
+}
+
+impl KeyChord
+{
+    /// Chord for a key event
+    pub fn from_event(key: &KeyEvent) -> Self
+    {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let code = match key.code
+        {
+            // Some terminals report Ctrl+letter as uppercase
+            KeyCode::Char(c) if ctrl => KeyCode::Char(c.to_ascii_lowercase()),
+            code => code,
+        };
+        Self {
+            code,
+            ctrl,
+            alt: key.modifiers.contains(KeyModifiers::ALT),
+        }
+    }
+
+    /// Parse a chord such as "q", "G", "Ctrl-f", "Alt-x", "Shift-Tab", "PageDown" or "F5"
+    pub fn parse(s: &str) -> Result<Self, String>
+    {
+        let mut rest = s;
+        let mut ctrl = false;
+        let mut alt = false;
+        let mut shift = false;
+
+        // Modifier prefixes; a lone "-" or "+" is a key, not a separator
+        loop
+        {
+            let lower = rest.to_lowercase();
+            let prefix = ["ctrl-", "ctrl+", "c-", "alt-", "alt+", "m-", "shift-", "shift+", "s-"]
+                .iter()
+                .find(|p| lower.starts_with(*p) && rest.len() > p.len());
+            match prefix
+            {
+                Some(p) =>
+                {
+                    match p.chars().next()
+                    {
+                        Some('c') => ctrl = true,
+                        Some('a') | Some('m') => alt = true,
+                        _ => shift = true,
+                    }
+                    rest = &rest[p.len()..];
+                }
+                None => break,
+            }
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next())
+        {
+            (Some(c), None) if ctrl => KeyCode::Char(c.to_ascii_lowercase()),
+            (Some(c), None) if shift => KeyCode::Char(c.to_ascii_uppercase()),
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str()
+            {
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" if shift => KeyCode::BackTab,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" | "bksp" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" | "pgup" => KeyCode::PageUp,
+                "pagedown" | "pgdn" => KeyCode::PageDown,
+                "space" => KeyCode::Char(' '),
+                name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok())
+                {
+                    Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key '{}'", s)),
+                },
+            },
+        };
+
+        Ok(Self { code, ctrl, alt })
+    }
+
+    /// Label for the help overlay and status bar, e.g. "Ctrl-F", "↑", "Bksp"
+    pub fn label(&self) -> String
+    {
+        let key = match self.code
+        {
+            KeyCode::Up => "↑".to_string(),
+            KeyCode::Down => "↓".to_string(),
+            KeyCode::Left => "←".to_string(),
+            KeyCode::Right => "→".to_string(),
+            KeyCode::Enter => "Enter".to_string(),
+            KeyCode::Esc => "Esc".to_string(),
+            KeyCode::Tab => "Tab".to_string(),
+            KeyCode::BackTab => "S-Tab".to_string(),
+            KeyCode::Backspace => "Bksp".to_string(),
+            KeyCode::Delete => "Del".to_string(),
+            KeyCode::Insert => "Ins".to_string(),
+            KeyCode::Home => "Home".to_string(),
+            KeyCode::End => "End".to_string(),
+            KeyCode::PageUp => "PgUp".to_string(),
+            KeyCode::PageDown => "PgDn".to_string(),
+            KeyCode::F(n) => format!("F{}", n),
+            KeyCode::Char(' ') => "Space".to_string(),
+            KeyCode::Char(c) if self.ctrl => c.to_ascii_uppercase().to_string(),
+            KeyCode::Char(c) => c.to_string(),
+            _ => "?".to_string(),
+        };
+
+        let mut label = String::new();
+        if self.ctrl
+        {
+            label.push_str("Ctrl-");
+        }
+        if self.alt
+        {
+            label.push_str("Alt-");
+        }
+        label.push_str(&key);
+        label
+    }
+}
+
+/// One key or a list of keys in the `[keys]` config table
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum KeyList
+{
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Active key bindings
+#[derive(Debug, Clone)]
+pub struct Keymap
+{
+    bindings: Vec<(KeyChord, Action)>,
+}
+
+impl Default for Keymap
+{
+    fn default() -> Self
+    {
+        Self::with_overrides(&BTreeMap::new()).expect("built-in keymap is valid")
+    }
+}
+
+impl Keymap
+{
+    /// Built-in bindings, with the keys of each action named in `overrides` replaced
+    pub fn with_overrides(overrides: &BTreeMap<String, KeyList>) -> Result<Self, String>
+    {
+        let mut custom = BTreeMap::new();
+        for (name, keys) in overrides
+        {
+            if Action::from_name(name).is_none()
+            {
+                return Err(format!("keys: unknown action '{}'", name));
+            }
+            let keys = match keys
+            {
+                KeyList::One(key) => vec![key.clone()],
+                KeyList::Many(keys) => keys.clone(),
+            };
+            let chords = keys.iter()
+                             .map(|k| KeyChord::parse(k).map_err(|e| format!("keys.{}: {}", name, e)))
+                             .collect::<Result<Vec<_>, _>>()?;
+            custom.insert(name.as_str(), chords);
+        }
+
+        let mut bindings = Vec::new();
+        for action in SECTIONS.iter().flat_map(|s| s.iter()).copied()
+        {
+            match custom.get(action.name())
+            {
+                Some(chords) => bindings.extend(chords.iter().map(|c| (*c, action))),
+                None =>
+                {
+                    for key in action.default_keys()
+                    {
+                        bindings.push((KeyChord::parse(key)?, action));
+                    }
+                }
+            }
+        }
+
+        let keymap = Self { bindings };
+        keymap.check_conflicts()?;
+        Ok(keymap)
+    }
+
+    /// Reject a chord bound to two actions that can be active at the same time
+    fn check_conflicts(&self) -> Result<(), String>
+    {
+        for (i, (chord, action)) in self.bindings.iter().enumerate()
+        {
+            for (other_chord, other) in &self.bindings[i + 1..]
+            {
+                let overlap = match (action.scope(), other.scope())
+                {
+                    (Some(a), Some(b)) => a == b,
+                    _ => true,
+                };
+                if chord == other_chord && action != other && overlap
+                {
+                    return Err(format!("keys: {} is bound to both {} and {}",
+                                       chord.label(), action.name(), other.name()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Action bound to a key in the focused panel.
+    ///
+    /// A modified non-character key (e.g. Ctrl-Down) without a binding of its
+    /// own falls back to the plain key, as before keys were configurable.
+    pub fn action(&self,
+                  key: &KeyEvent,
+                  panel: Panel)
+                  -> Option<Action>
+    {
+        let chord = KeyChord::from_event(key);
+        self.bound(chord, panel).or_else(|| {
+            if matches!(chord.code, KeyCode::Char(_)) || !(chord.ctrl || chord.alt)
+            {
+                return None;
+            }
+            self.bound(KeyChord { code: chord.code, ctrl: false, alt: false }, panel)
+        })
+    }
+
+    fn bound(&self,
+             chord: KeyChord,
+             panel: Panel)
+             -> Option<Action>
+    {
+        self.bindings
+            .iter()
+            .find(|(c, a)| *c == chord && a.scope().map_or(true, |p| p == panel))
+            .map(|(_, a)| *a)
+    }
+
+    /// Labels of all keys bound to an action
+    pub fn keys(&self,
+                action: Action)
+                -> Vec<String>
+    {
+        self.bindings
+            .iter()
+            .filter(|(_, a)| *a == action)
+            .map(|(c, _)| c.label())
+            .collect()
+    }
+
+    /// Label of the first key bound to an action ("-" if unbound)
+    pub fn first_key(&self,
+                     action: Action)
+                     -> String
+    {
+        self.keys(action).into_iter().next().unwrap_or_else(|| "-".to_string())
+    }
+}
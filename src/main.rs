@@ -1,31 +1,80 @@
 mod app;
+mod bookmark;
+mod check;
+mod compare;
+mod cli;
+mod config;
+mod deepsearch;
+mod diff;
+mod dirsize;
 mod event;
 mod file;
+mod forget;
+mod goto;
+mod history;
+mod jobs;
+mod keymap;
+mod mouse;
+mod preview;
 mod restic;
+mod search;
 mod snapshot;
+mod stats;
+mod theme;
+mod tree;
 mod ui;
 
-use std::time::Duration;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
-use crossterm::event::{self as ct_event, Event};
+use crossterm::event::{self as ct_event, DisableMouseCapture, EnableMouseCapture, Event};
+use tokio::io::AsyncReadExt;
 use tokio::sync::mpsc;
 
-use app::{App, AppState};
+use app::{App, AppState, DeepLink};
+use cli::{OutputFormat, Subcommand};
+use config::Config;
+use crate::compare::Listing;
+use crate::diff::{DIFF_MAX_BYTES, DiffBody};
+use crate::dirsize::DirSize;
 use crate::event::Command;
 use crate::file::FileNode;
-use restic::ResticClient;
+use crate::forget::{ForgetGroup, ForgetPolicy};
+use crate::jobs::JobKind;
+use crate::preview::{PREVIEW_BYTES, Preview};
+use restic::{GlobalOptions, ResticClient};
+use stats::{ResticStats, StatsMode, StatsTarget};
 
 /// CLI configuration
 struct CliConfig
 {
     log_file: Option<String>,
+    max_jobs: Option<usize>,
+    subcommand: Option<Subcommand>,
+    format: OutputFormat,
+    deep_link: DeepLink,
+    restic_options: GlobalOptions,
+    config_path: Option<String>,
+    profile: Option<String>,
 }
 
 fn parse_args() -> CliConfig
 {
     let args: Vec<String> = std::env::args().collect();
-    let mut config = CliConfig { log_file: None };
+    let mut config = CliConfig {
+        log_file: None,
+        max_jobs: None,
+        subcommand: None,
+        format: OutputFormat::Table,
+        deep_link: DeepLink::default(),
+        restic_options: GlobalOptions::default(),
+        config_path: None,
+        profile: None,
+    };
+    let mut positional = Vec::new();
+    let mut format_given = false;
 
     let mut i = 1;
     while i < args.len()
@@ -42,7 +91,143 @@ fn parse_args() -> CliConfig
                 else
                 {
                     eprintln!("Error: --log-file requires a path argument");
-                    std::process::exit(1);
+                    std::process::exit(cli::EXIT_USAGE);
+                }
+            }
+            "--jobs" | "-j" =>
+            {
+                match args.get(i + 1).and_then(|n| n.parse::<usize>().ok()).filter(|n| *n > 0)
+                {
+                    Some(n) =>
+                    {
+                        config.max_jobs = Some(n);
+                        i += 2;
+                    }
+                    None =>
+                    {
+                        eprintln!("Error: --jobs requires a positive number");
+                        std::process::exit(cli::EXIT_USAGE);
+                    }
+                }
+            }
+            "--snapshot" | "-s" | "--host" | "--path" | "-p" =>
+            {
+                let value = match args.get(i + 1)
+                {
+                    Some(v) => v.clone(),
+                    None =>
+                    {
+                        eprintln!("Error: {} requires a value", args[i]);
+                        std::process::exit(cli::EXIT_USAGE);
+                    }
+                };
+                match args[i].as_str()
+                {
+                    "--snapshot" | "-s" => config.deep_link.snapshot = Some(value),
+                    "--host" => config.deep_link.host = Some(value),
+                    _ => config.deep_link.path = Some(value),
+                }
+                i += 2;
+            }
+            "--config" | "-c" | "--profile" | "-P" =>
+            {
+                let value = match args.get(i + 1)
+                {
+                    Some(v) => v.clone(),
+                    None =>
+                    {
+                        eprintln!("Error: {} requires a value", args[i]);
+                        std::process::exit(cli::EXIT_USAGE);
+                    }
+                };
+                if matches!(args[i].as_str(), "--config" | "-c")
+                {
+                    config.config_path = Some(value);
+                }
+                else
+                {
+                    config.profile = Some(value);
+                }
+                i += 2;
+            }
+            "--option" | "-o" | "--cacert" | "--cache-dir" =>
+            {
+                let value = match args.get(i + 1)
+                {
+                    Some(v) => v.clone(),
+                    None =>
+                    {
+                        eprintln!("Error: {} requires a value", args[i]);
+                        std::process::exit(cli::EXIT_USAGE);
+                    }
+                };
+                match args[i].as_str()
+                {
+                    "--option" | "-o" =>
+                    {
+                        if !value.contains('=')
+                        {
+                            eprintln!("Error: {} expects key=value, got '{}'", args[i], value);
+                            std::process::exit(cli::EXIT_USAGE);
+                        }
+                        config.restic_options.options.push(value);
+                    }
+                    "--cacert" => config.restic_options.cacert.push(value),
+                    _ => config.restic_options.cache_dir = Some(value),
+                }
+                i += 2;
+            }
+            "--limit-download" | "--pack-size" =>
+            {
+                let value = match args.get(i + 1).and_then(|n| n.parse::<u32>().ok()).filter(|n| *n > 0)
+                {
+                    Some(n) => n,
+                    None =>
+                    {
+                        eprintln!("Error: {} requires a positive number", args[i]);
+                        std::process::exit(cli::EXIT_USAGE);
+                    }
+                };
+                if args[i] == "--limit-download"
+                {
+                    config.restic_options.limit_download = Some(value);
+                }
+                else
+                {
+                    config.restic_options.pack_size = Some(value);
+                }
+                i += 2;
+            }
+            "--insecure-tls" =>
+            {
+                config.restic_options.insecure_tls = true;
+                i += 1;
+            }
+            "--no-cache" =>
+            {
+                config.restic_options.no_cache = true;
+                i += 1;
+            }
+            "--no-lock" =>
+            {
+                config.restic_options.no_lock = true;
+                i += 1;
+            }
+            "--format" | "-f" =>
+            {
+                match args.get(i + 1).and_then(|f| OutputFormat::parse(f))
+                {
+                    Some(format) =>
+                    {
+                        config.format = format;
+                        format_given = true;
+                        i += 2;
+                    }
+                    None =>
+                    {
+                        eprintln!("Error: --format requires 'table' or 'json'");
+                        std::process::exit(cli::EXIT_USAGE);
+                    }
                 }
             }
             "--help" | "-h" =>
@@ -50,11 +235,40 @@ fn parse_args() -> CliConfig
                 println!("rest-snapview - Terminal UI for browsing restic snapshots");
                 println!();
                 println!("Usage: rest-snapview [OPTIONS]");
+                println!("       rest-snapview [OPTIONS] <SUBCOMMAND>");
+                println!();
+                println!("Subcommands (non-interactive):");
+                println!("  snapshots                        List snapshots");
+                println!("  ls <snapshot> [path]             List a directory in a snapshot");
+                println!("  get <snapshot> <path> <target>   Restore a file/directory into target");
                 println!();
                 println!("Options:");
+                println!("  -c, --config <PATH>    Config file (default ~/.config/rest-snapview/config.toml)");
+                println!("  -P, --profile <NAME>   Use a repository profile from the config file");
                 println!("  -l, --log-file <PATH>  Save command logs to file");
+                println!("  -j, --jobs <N>         Run up to N restore/dump jobs at once (default 2)");
+                println!("  -f, --format <FORMAT>  Subcommand output: table (default) or json");
+                println!("  -s, --snapshot <ID>    Start in this snapshot (ID prefix or 'latest')");
+                println!("      --host <HOST>      Only consider snapshots from this host");
+                println!("  -p, --path <PATH>      Start in this directory of the snapshot");
                 println!("  -h, --help             Show this help message");
                 println!();
+                println!("restic options (passed to every restic command):");
+                println!("  -o, --option <KEY=VALUE>      Extended option, may be repeated");
+                println!("      --cacert <FILE>           Extra TLS root certificate, may be repeated");
+                println!("      --insecure-tls            Skip TLS certificate verification");
+                println!("      --limit-download <KiB/s>  Limit download bandwidth");
+                println!("      --cache-dir <DIR>         Use this cache directory");
+                println!("      --no-cache                Don't use a local cache");
+                println!("      --no-lock                 Don't lock the repository (read-only commands)");
+                println!("      --pack-size <MiB>         Target pack size");
+                println!();
+                println!("Exit codes:");
+                println!("  0  success");
+                println!("  1  restic failed");
+                println!("  2  invalid arguments or configuration");
+                println!("  3  snapshot or path not found");
+                println!();
                 println!("Environment variables:");
                 println!("  RESTIC_REPOSITORY      Repository location (required)");
                 println!("  RESTIC_PASSWORD        Repository password");
@@ -62,15 +276,45 @@ fn parse_args() -> CliConfig
                 println!("  RESTIC_PASSWORD_COMMAND Command to get password");
                 std::process::exit(0);
             }
-            arg =>
+            arg if arg.starts_with('-') && arg != "-" =>
             {
                 eprintln!("Error: Unknown argument: {}", arg);
                 eprintln!("Use --help for usage information");
-                std::process::exit(1);
+                std::process::exit(cli::EXIT_USAGE);
+            }
+            arg =>
+            {
+                positional.push(arg.to_string());
+                i += 1;
             }
         }
     }
 
+    if !positional.is_empty()
+    {
+        match Subcommand::parse(&positional)
+        {
+            Ok(subcommand) => config.subcommand = Some(subcommand),
+            Err(e) =>
+            {
+                eprintln!("Error: {}", e);
+                eprintln!("Use --help for usage information");
+                std::process::exit(cli::EXIT_USAGE);
+            }
+        }
+    }
+    else if format_given
+    {
+        eprintln!("Error: --format only applies to subcommands");
+        std::process::exit(cli::EXIT_USAGE);
+    }
+
+    if config.subcommand.is_some() && !config.deep_link.is_empty()
+    {
+        eprintln!("Error: --snapshot/--host/--path only apply to the interactive UI");
+        std::process::exit(cli::EXIT_USAGE);
+    }
+
     config
 }
 
@@ -83,10 +327,113 @@ enum TaskResult
         result: Result<Vec<FileNode>, String>,
         error_output: Option<String>,
     },
-    Download
+    /// Contents of the columns layout's preview column
+    Preview
+    {
+        command: String,
+        snapshot_id: String,
+        path: String,
+        result: Result<Preview, String>,
+        error_output: Option<String>,
+    },
+    /// A file version fetched for the diff view
+    FileVersion
+    {
+        command: String,
+        snapshot_id: String,
+        path: String,
+        result: Result<Vec<u8>, String>,
+        error_output: Option<String>,
+    },
+    /// A local file read for the diff view
+    LocalFileVersion
+    {
+        snapshot_id: String,
+        path: String,
+        result: Result<Vec<u8>, String>,
+    },
+    /// The local side of a comparison
+    LocalListing
+    {
+        path: String,
+        listing: Listing,
+    },
+    /// The diff computed from both versions of a file
+    Diff
+    {
+        path: String,
+        left_id: String,
+        right_id: String,
+        body: DiffBody,
+    },
+    JobProgress
+    {
+        id: usize,
+        progress: f64,
+    },
+    JobDone
+    {
+        id: usize,
+        command: String,
+        result: Result<(), String>,
+        error_output: Option<String>,
+        duration: Duration,
+    },
+    /// A batch of directories whose recursive size is now known
+    DirSizes
+    {
+        run: usize,
+        snapshot_id: String,
+        sizes: Vec<(String, DirSize)>,
+    },
+    /// The recursive listing behind DirSizes has finished
+    DirSizesDone
     {
         command: String,
-        result: Result<String, String>,  // Ok(target path) or Err(error message)
+        run: usize,
+        snapshot_id: String,
+        result: Result<(), String>,
+        error_output: Option<String>,
+    },
+    /// A batch of entries listed for a deep search
+    DeepSearchEntries
+    {
+        id: usize,
+        nodes: Vec<FileNode>,
+    },
+    /// The recursive listing behind a deep search has finished
+    DeepSearchDone
+    {
+        command: String,
+        id: usize,
+        result: Result<(), String>,
+        error_output: Option<String>,
+    },
+    /// A line of `restic check` output
+    CheckOutput
+    {
+        line: String,
+    },
+    CheckDone
+    {
+        command: String,
+        result: Result<(), String>,
+        error_output: Option<String>,
+        duration: Duration,
+    },
+    Forget
+    {
+        command: String,
+        policy: ForgetPolicy,
+        result: Result<Vec<ForgetGroup>, String>,
+        error_output: Option<String>,
+    },
+    Stats
+    {
+        command: String,
+        target: StatsTarget,
+        mode: StatsMode,
+        result: Result<ResticStats, String>,
         error_output: Option<String>,
     },
 }
@@ -97,10 +444,32 @@ async fn main() -> Result<()>
     // Parse CLI arguments
     let config = parse_args();
 
-    // Create restic client from environment
-    let client = match ResticClient::from_env()
+    // Load the config file (command-line options take precedence)
+    let settings = match Config::load(config.config_path.as_deref())
     {
-        Ok(c) => c,
+        Ok(s) => s,
+        Err(e) =>
+        {
+            eprintln!("Error: invalid config: {}", e);
+            std::process::exit(cli::EXIT_USAGE);
+        }
+    };
+    let profile = match settings.profile(config.profile.as_deref())
+    {
+        Ok(p) => p,
+        Err(e) =>
+        {
+            eprintln!("Error: {}", e);
+            std::process::exit(cli::EXIT_USAGE);
+        }
+    };
+    let mut restic_options = settings.restic.clone();
+    restic_options.merge(config.restic_options);
+
+    // Create restic client from the profile or environment
+    let client = match profile.map_or_else(ResticClient::from_env, ResticClient::from_profile)
+    {
+        Ok(c) => c.with_global_options(&restic_options),
         Err(e) =>
         {
             eprintln!("Error: {}", e);
@@ -113,17 +482,39 @@ async fn main() -> Result<()>
             eprintln!("Example:");
             eprintln!("  export RESTIC_REPOSITORY=\"rest:https://your-server/repo\"");
             eprintln!("  export RESTIC_PASSWORD_FILE=\"$HOME/.restic-password\"");
+            eprintln!();
+            eprintln!("Or define a repository profile in the config file and pass --profile <NAME>.");
             std::process::exit(1);
         }
     };
 
+    // Run a subcommand without the TUI
+    if let Some(subcommand) = config.subcommand
+    {
+        let code = cli::run(&client, subcommand, config.format, &settings).await;
+        std::process::exit(code);
+    }
+
     // Initialize terminal
     let mut terminal = ratatui::init();
     terminal.clear()?;
+    if settings.mouse
+    {
+        enable_mouse_capture()?;
+    }
 
     // Create app
     let mut app = App::new();
-    app.log_file_path = config.log_file;
+    app.apply_config(&settings);
+    if config.log_file.is_some()
+    {
+        app.log_file_path = config.log_file;
+    }
+    if let Some(n) = config.max_jobs
+    {
+        app.max_jobs = n;
+    }
+    app.load_bookmarks(settings.bookmarks_file.as_deref().map(PathBuf::from).or_else(bookmark::default_path));
 
     // Load initial snapshots
     let cmd_result = client.list_snapshots().await;
@@ -133,12 +524,19 @@ async fn main() -> Result<()>
         cmd_result.error_output.clone(),
     );
 
+    let mut initial_commands = Vec::new();
     match cmd_result.result
     {
         Ok(snapshots) =>
         {
             app.snapshots = snapshots;
             app.state = AppState::Ready;
+
+            // Jump straight to --snapshot/--host/--path if given
+            if !config.deep_link.is_empty()
+            {
+                initial_commands = app.open_deep_link(&config.deep_link);
+            }
         }
         Err(e) =>
         {
@@ -147,22 +545,44 @@ async fn main() -> Result<()>
     }
 
     // Run event loop
-    let result = run_event_loop(&mut terminal, &mut app, client).await;
+    let result = run_event_loop(&mut terminal, &mut app, client, initial_commands).await;
 
     // Restore terminal
+    if settings.mouse
+    {
+        let _ = crossterm::execute!(std::io::stdout(), DisableMouseCapture);
+    }
     ratatui::restore();
 
     result
 }
 
+/// Capture mouse events, releasing the mouse again if the TUI panics
+fn enable_mouse_capture() -> Result<()>
+{
+    crossterm::execute!(std::io::stdout(), EnableMouseCapture)?;
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = crossterm::execute!(std::io::stdout(), DisableMouseCapture);
+        hook(info);
+    }));
+    Ok(())
+}
+
 async fn run_event_loop(terminal: &mut ratatui::DefaultTerminal,
                         app: &mut App,
-                        client: ResticClient)
+                        client: ResticClient,
+                        initial_commands: Vec<Command>)
                         -> Result<()>
 {
     // Channel for receiving results from background tasks
     let (tx, mut rx) = mpsc::channel::<TaskResult>(10);
 
+    for cmd in initial_commands
+    {
+        spawn_command(&client, cmd, tx.clone(), app);
+    }
+
     loop
     {
         // Tick spinner for animation
@@ -171,7 +591,10 @@ async fn run_event_loop(terminal: &mut ratatui::DefaultTerminal,
         // Check for completed background tasks (non-blocking)
         while let Ok(result) = rx.try_recv()
         {
-            handle_task_result(app, result);
+            if let Some(cmd) = handle_task_result(app, result)
+            {
+                spawn_command(&client, cmd, tx.clone(), app);
+            }
         }
 
         // Draw UI
@@ -180,16 +603,31 @@ async fn run_event_loop(terminal: &mut ratatui::DefaultTerminal,
         // Poll for events with short timeout to keep spinner animated
         if ct_event::poll(Duration::from_millis(80))?
         {
-            if let Event::Key(key) = ct_event::read()?
+            // Handle key or mouse event and get optional command
+            let cmd = match ct_event::read()?
             {
-                // Handle key and get optional command
-                if let Some(cmd) = app.handle_key(key)
-                {
-                    spawn_command(&client, cmd, tx.clone(), app);
-                }
+                Event::Key(key) => app.handle_key(key),
+                Event::Mouse(mouse) => app.handle_mouse(mouse),
+                _ => None,
+            };
+            if let Some(cmd) = cmd
+            {
+                spawn_command(&client, cmd, tx.clone(), app);
             }
         }
 
+        // Fetch the preview once the cursor rests on an entry
+        if let Some(cmd) = app.preview_command()
+        {
+            spawn_command(&client, cmd, tx.clone(), app);
+        }
+
+        // Start queued jobs while there are free slots
+        for (id, kind) in app.start_ready_jobs()
+        {
+            spawn_job(&client, id, kind, tx.clone());
+        }
+
         if app.should_quit
         {
             break;
@@ -225,29 +663,221 @@ fn spawn_command(client: &ResticClient,
                 });
             }
         }
-        Command::Download { path, target } =>
+        Command::LoadPreview { snapshot_id, path, is_dir } =>
         {
-            if let Some(ref snapshot_id) = app.current_snapshot_id
-            {
-                // Set downloading state before spawning
-                app.state = AppState::Downloading(path.clone());
+            let client = client.clone();
+            tokio::spawn(async move {
+                let (command, result, error_output) = if is_dir
+                {
+                    let cmd_result = client.list_files(&snapshot_id, &path).await;
+                    (cmd_result.command, cmd_result.result.map(Preview::Listing), cmd_result.error_output)
+                }
+                else
+                {
+                    let cmd_result = client.dump_head(&snapshot_id, &path, PREVIEW_BYTES).await;
+                    (cmd_result.command, cmd_result.result.map(Preview::Content), cmd_result.error_output)
+                };
+                let task_result = TaskResult::Preview {
+                    command,
+                    snapshot_id,
+                    path,
+                    result: result.map_err(|e| e.to_string()),
+                    error_output,
+                };
+                let _ = tx.send(task_result).await;
+            });
+        }
+        Command::LoadFileVersion { snapshot_id, path } =>
+        {
+            let client = client.clone();
+            tokio::spawn(async move {
+                let cmd_result = client.dump_head(&snapshot_id, &path, DIFF_MAX_BYTES + 1).await;
+                let task_result = TaskResult::FileVersion {
+                    command: cmd_result.command,
+                    snapshot_id,
+                    path,
+                    result: cmd_result.result.map_err(|e| e.to_string()),
+                    error_output: cmd_result.error_output,
+                };
+                let _ = tx.send(task_result).await;
+            });
+        }
+        Command::ComputeDirSizes { run, snapshot_id, cancel } =>
+        {
+            let client = client.clone();
+            tokio::spawn(async move {
+                let (batch_tx, batch_rx) = mpsc::unbounded_channel();
+
+                // Forward batches until another snapshot is opened; dropping
+                // the receiver then stops the listing
+                let forward = async {
+                    let mut batch_rx = batch_rx;
+                    while let Some(sizes) = batch_rx.recv().await
+                    {
+                        if cancel.load(Ordering::Relaxed)
+                        {
+                            break;
+                        }
+                        let _ = tx.send(TaskResult::DirSizes {
+                                      run,
+                                      snapshot_id: snapshot_id.clone(),
+                                      sizes,
+                                  })
+                                  .await;
+                    }
+                };
+                let (cmd_result, _) = tokio::join!(client.dir_sizes(&snapshot_id, batch_tx, &cancel), forward);
+
+                let task_result = TaskResult::DirSizesDone {
+                    command: cmd_result.command,
+                    run,
+                    snapshot_id,
+                    result: cmd_result.result
+                        .map_err(|e| format!("Failed to compute directory sizes: {}", e)),
+                    error_output: cmd_result.error_output,
+                };
+                let _ = tx.send(task_result).await;
+            });
+        }
+        Command::DeepSearch { id, snapshot_id, paths, cancel } =>
+        {
+            let client = client.clone();
+            tokio::spawn(async move {
+                let (batch_tx, batch_rx) = mpsc::unbounded_channel();
+
+                // Forward entries until the search is replaced; dropping the
+                // receiver then stops the listing
+                let forward = async {
+                    let mut batch_rx = batch_rx;
+                    while let Some(nodes) = batch_rx.recv().await
+                    {
+                        if cancel.load(Ordering::Relaxed)
+                        {
+                            break;
+                        }
+                        let _ = tx.send(TaskResult::DeepSearchEntries { id, nodes }).await;
+                    }
+                };
+                let (cmd_result, _) = tokio::join!(client.list_recursive(&snapshot_id, &paths, batch_tx, &cancel), forward);
 
+                let task_result = TaskResult::DeepSearchDone {
+                    command: cmd_result.command,
+                    id,
+                    result: cmd_result.result
+                        .map_err(|e| format!("Deep search failed: {}", e)),
+                    error_output: cmd_result.error_output,
+                };
+                let _ = tx.send(task_result).await;
+            });
+        }
+        Command::Check { read_data_subset } =>
+        {
+            let client = client.clone();
+            tokio::spawn(async move {
+                let started = Instant::now();
+                let (line_tx, mut line_rx) = mpsc::unbounded_channel();
+
+                // Forward output while the check is still running
+                let forward = async {
+                    while let Some(line) = line_rx.recv().await
+                    {
+                        let _ = tx.send(TaskResult::CheckOutput { line }).await;
+                    }
+                };
+                let (cmd_result, _) = tokio::join!(client.check(read_data_subset.as_deref(), line_tx),
+                                                   forward);
+
+                let task_result = TaskResult::CheckDone {
+                    command: cmd_result.command,
+                    result: cmd_result.result.map_err(|e| e.to_string()),
+                    error_output: cmd_result.error_output,
+                    duration: started.elapsed(),
+                };
+                let _ = tx.send(task_result).await;
+            });
+        }
+        Command::SimulateForget { policy } =>
+        {
+            let client = client.clone();
+            tokio::spawn(async move {
+                let cmd_result = client.forget_dry_run(&policy).await;
+                let task_result = TaskResult::Forget {
+                    command: cmd_result.command,
+                    policy,
+                    result: cmd_result.result
+                        .map_err(|e| format!("Policy simulation failed: {}", e)),
+                    error_output: cmd_result.error_output,
+                };
+                let _ = tx.send(task_result).await;
+            });
+        }
+        Command::LoadStats { requests } =>
+        {
+            for (target, mode) in requests
+            {
                 let client = client.clone();
-                let snapshot_id = snapshot_id.clone();
-                let target_clone = target.clone();
+                let tx = tx.clone();
                 tokio::spawn(async move {
-                    let cmd_result = client.restore(&snapshot_id, &path, &target_clone).await;
-                    let task_result = TaskResult::Download {
+                    let snapshot_id = match &target
+                    {
+                        StatsTarget::Snapshot(id) => Some(id.as_str()),
+                        StatsTarget::Repository => None,
+                    };
+                    let cmd_result = client.stats(snapshot_id, mode).await;
+                    let task_result = TaskResult::Stats {
                         command: cmd_result.command,
-                        result: cmd_result.result
-                            .map(|_| target_clone)
-                            .map_err(|e| format!("Download failed: {}", e)),
+                        target,
+                        mode,
+                        result: cmd_result.result.map_err(|e| e.to_string()),
                         error_output: cmd_result.error_output,
                     };
                     let _ = tx.send(task_result).await;
                 });
             }
         }
+        Command::LoadLocalFileVersion { snapshot_id, path, file } =>
+        {
+            tokio::spawn(async move {
+                let read = async {
+                    let mut content = Vec::new();
+                    tokio::fs::File::open(&file).await?
+                                                 .take(DIFF_MAX_BYTES as u64 + 1)
+                                                 .read_to_end(&mut content)
+                                                 .await?;
+                    Ok::<_, std::io::Error>(content)
+                };
+                let result = read.await.map_err(|e| format!("{}: {}", file.display(), e));
+                let _ = tx.send(TaskResult::LocalFileVersion { snapshot_id, path, result }).await;
+            });
+        }
+        Command::LoadLocalListing { path, dir } =>
+        {
+            tokio::spawn(async move {
+                let listing = {
+                    let path = path.clone();
+                    tokio::task::spawn_blocking(move || compare::local_listing(&path, &dir))
+                        .await
+                        .unwrap_or_else(|e| Listing::Absent(e.to_string()))
+                };
+                let _ = tx.send(TaskResult::LocalListing { path, listing }).await;
+            });
+        }
+        Command::ComputeDiff { path, left_id, right_id, input } =>
+        {
+            tokio::spawn(async move {
+                let body = tokio::task::spawn_blocking(move || diff::compute(input))
+                    .await
+                    .unwrap_or_else(|e| DiffBody::Failed(format!("Diff failed: {}", e)));
+                let _ = tx.send(TaskResult::Diff { path, left_id, right_id, body }).await;
+            });
+        }
+        Command::Batch(commands) =>
+        {
+            for cmd in commands
+            {
+                spawn_command(client, cmd, tx.clone(), app);
+            }
+        }
         Command::Quit =>
         {
             // Already handled by should_quit flag
@@ -255,9 +885,54 @@ fn spawn_command(client: &ResticClient,
     }
 }
 
-/// Handle results from background tasks
+/// Spawn a restore/dump job as a background task
+fn spawn_job(client: &ResticClient,
+             id: usize,
+             kind: JobKind,
+             tx: mpsc::Sender<TaskResult>)
+{
+    let client = client.clone();
+    tokio::spawn(async move {
+        let started = Instant::now();
+        let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
+
+        // Forward progress while the job is still running
+        let forward = async {
+            while let Some(progress) = progress_rx.recv().await
+            {
+                let _ = tx.send(TaskResult::JobProgress { id, progress }).await;
+            }
+        };
+        let run = async {
+            match &kind
+            {
+                JobKind::Restore { snapshot_id, paths, target } =>
+                {
+                    client.restore(snapshot_id, paths, target, progress_tx).await
+                }
+                JobKind::Dump { snapshot_id, path, target_file, size } =>
+                {
+                    client.dump(snapshot_id, path, target_file, *size, progress_tx).await
+                }
+            }
+        };
+        let (cmd_result, _) = tokio::join!(run, forward);
+
+        let task_result = TaskResult::JobDone {
+            id,
+            command: cmd_result.command,
+            result: cmd_result.result.map_err(|e| e.to_string()),
+            error_output: cmd_result.error_output,
+            duration: started.elapsed(),
+        };
+        let _ = tx.send(task_result).await;
+    });
+}
+
+/// Handle results from background tasks (some lead to another command)
 fn handle_task_result(app: &mut App,
                       result: TaskResult)
+                      -> Option<Command>
 {
     match result
     {
@@ -267,21 +942,85 @@ fn handle_task_result(app: &mut App,
             match result
             {
                 Ok(files) => app.set_files(files),
-                Err(e) => app.set_error(e),
+                Err(e) => return app.navigation_failed(e),
             }
         }
-        TaskResult::Download { command, result, error_output } =>
+        TaskResult::Preview { command, snapshot_id, path, result, error_output } =>
+        {
+            app.add_command_log(command, result.is_ok(), error_output);
+            return app.set_preview(snapshot_id, path, result);
+        }
+        TaskResult::FileVersion { command, snapshot_id, path, result, error_output } =>
+        {
+            app.add_command_log(command, result.is_ok(), error_output);
+            return app.set_file_version(&snapshot_id, &path, result);
+        }
+        TaskResult::LocalFileVersion { snapshot_id, path, result } =>
+        {
+            return app.set_file_version(&snapshot_id, &path, result);
+        }
+        TaskResult::LocalListing { path, listing } =>
+        {
+            app.set_local_listing(&path, listing);
+        }
+        TaskResult::Diff { path, left_id, right_id, body } =>
+        {
+            app.set_diff_body(&path, &left_id, &right_id, body);
+        }
+        TaskResult::JobProgress { id, progress } =>
+        {
+            app.set_job_progress(id, progress);
+        }
+        TaskResult::JobDone { id, command, result, error_output, duration } =>
+        {
+            app.add_timed_command_log(command, result.is_ok(), error_output, duration);
+            app.finish_job(id, result, duration);
+            return app.reload_local_compare(id);
+        }
+        TaskResult::DirSizes { run, snapshot_id, sizes } =>
+        {
+            app.add_dir_sizes(run, &snapshot_id, sizes);
+        }
+        TaskResult::DirSizesDone { command, run, snapshot_id, result, error_output } =>
+        {
+            app.add_command_log(command, result.is_ok(), error_output);
+            app.finish_dir_sizes(run, &snapshot_id, result);
+        }
+        TaskResult::DeepSearchEntries { id, nodes } =>
+        {
+            app.add_deep_search_entries(id, nodes);
+        }
+        TaskResult::DeepSearchDone { command, id, result, error_output } =>
+        {
+            app.add_command_log(command, result.is_ok(), error_output);
+            app.finish_deep_search(id, result);
+        }
+        TaskResult::CheckOutput { line } =>
+        {
+            if let Some(ref mut check) = app.check
+            {
+                check.push_line(line);
+            }
+        }
+        TaskResult::CheckDone { command, result, error_output, duration } =>
+        {
+            app.add_timed_command_log(command, result.is_ok(), error_output, duration);
+            app.finish_check(result);
+        }
+        TaskResult::Forget { command, policy, result, error_output } =>
         {
             app.add_command_log(command, result.is_ok(), error_output);
             match result
             {
-                Ok(target) =>
-                {
-                    app.state = AppState::Ready;
-                    app.set_status(format!("Downloaded to: {}", target));
-                }
+                Ok(groups) => app.set_forget_plan(policy, groups),
                 Err(e) => app.set_error(e),
             }
         }
+        TaskResult::Stats { command, target, mode, result, error_output } =>
+        {
+            app.add_command_log(command, result.is_ok(), error_output);
+            app.set_stats(target, mode, result);
+        }
     }
+    None
 }
@@ -0,0 +1,131 @@
+use std::time::{Duration, Instant};
+
+use ratatui::layout::{Position, Rect};
+
+use crate::app::{DialogFocus, Panel};
+
+/// Two clicks on the same cell within this time make a double-click
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(400);
+
+/// Rows moved per scroll wheel notch
+pub const WHEEL_LINES: i32 = 3;
+
+/// A bordered list on screen and the item shown on each row inside the border
+#[derive(Debug, Clone, Default)]
+pub struct ListArea
+{
+    pub area: Rect,
+    /// Item index for each row, top to bottom
+    pub rows: Vec<usize>,
+}
+
+impl ListArea
+{
+    pub fn new(area: Rect,
+               rows: Vec<usize>)
+               -> Self
+    {
+        Self { area, rows }
+    }
+
+    /// List rows `first..first + visible_height`, limited to `count` items
+    pub fn scrolled(area: Rect,
+                    first: usize,
+                    visible_height: usize,
+                    count: usize)
+                    -> Self
+    {
+        Self::new(area, (first..count.min(first + visible_height)).collect())
+    }
+
+    pub fn contains(&self,
+                    column: u16,
+                    row: u16)
+                    -> bool
+    {
+        self.area.contains(Position::new(column, row))
+    }
+
+    /// Item on the given screen row, if any
+    pub fn item_at(&self,
+                   column: u16,
+                   row: u16)
+                   -> Option<usize>
+    {
+        if !self.contains(column, row) || row <= self.area.y
+        {
+            return None;
+        }
+        self.rows.get((row - self.area.y - 1) as usize).copied()
+    }
+}
+
+/// Where clickable things were drawn in the last frame (updated by UI)
+#[derive(Debug, Clone, Default)]
+pub struct MouseAreas
+{
+    pub panels: Vec<(Panel, ListArea)>,
+    // Download dialog
+    pub dialog_input: Option<Rect>,
+    pub dialog_listing: Option<ListArea>,
+    pub dialog_buttons: Vec<(DialogFocus, Rect)>,
+    // Retention policy dialog: one row per field
+    pub forget_fields: Option<Rect>,
+    // Deep search results
+    pub deep_search: Option<ListArea>,
+    // Bookmark picker
+    pub bookmarks: Option<ListArea>,
+    // Comparison rows (the header line above them stands in for the border)
+    pub compare: Option<ListArea>,
+}
+
+impl MouseAreas
+{
+    /// Panel under the pointer
+    pub fn panel_at(&self,
+                    column: u16,
+                    row: u16)
+                    -> Option<(Panel, &ListArea)>
+    {
+        self.panels
+            .iter()
+            .find(|(_, list)| list.contains(column, row))
+            .map(|(panel, list)| (*panel, list))
+    }
+
+    /// Download dialog button under the pointer
+    pub fn button_at(&self,
+                     column: u16,
+                     row: u16)
+                     -> Option<DialogFocus>
+    {
+        self.dialog_buttons
+            .iter()
+            .find(|(_, area)| area.contains(Position::new(column, row)))
+            .map(|(focus, _)| *focus)
+    }
+}
+
+/// Detects double-clicks from a sequence of left clicks
+#[derive(Debug, Default)]
+pub struct ClickTracker
+{
+    last: Option<(Instant, u16, u16)>,
+}
+
+impl ClickTracker
+{
+    /// Record a click; true if it completes a double-click
+    pub fn click(&mut self,
+                 column: u16,
+                 row: u16)
+                 -> bool
+    {
+        let now = Instant::now();
+        let double = matches!(self.last,
+                              Some((at, c, r)) if c == column && r == row && now - at <= DOUBLE_CLICK_TIME);
+        // A third click starts over instead of counting as another double-click
+        self.last = if double { None } else { Some((now, column, row)) };
+        double
+    }
+}
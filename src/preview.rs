@@ -0,0 +1,105 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::file::FileNode;
+
+/// Bytes read from the start of a file for its preview
+pub const PREVIEW_BYTES: usize = 16 * 1024;
+
+/// How long the cursor has to rest on an entry before its preview is fetched
+pub const PREVIEW_DELAY: Duration = Duration::from_millis(200);
+
+/// Preview of a Files panel entry (right column of the Miller-column layout)
+#[derive(Debug, Clone)]
+pub enum Preview
+{
+    Loading,
+    /// Children of a directory, sorted like the Files panel
+    Listing(Vec<FileNode>),
+    /// The first PREVIEW_BYTES of a file
+    Content(Vec<u8>),
+    Failed(String),
+}
+
+/// Most previews kept; the least recently used are dropped beyond this
+pub const PREVIEW_CACHE_ENTRIES: usize = 256;
+
+/// Previews per (snapshot ID, path), limited to the most recently used ones
+#[derive(Debug, Default)]
+pub struct PreviewCache
+{
+    entries: HashMap<(String, String), (Preview, Cell<u64>)>,
+    /// Bumped on every access; entries remember when they were last used
+    clock: Cell<u64>,
+}
+
+impl PreviewCache
+{
+    pub fn get(&self,
+               key: &(String, String))
+               -> Option<&Preview>
+    {
+        let (preview, used) = self.entries.get(key)?;
+        used.set(self.tick());
+        Some(preview)
+    }
+
+    pub fn contains_key(&self,
+                        key: &(String, String))
+                        -> bool
+    {
+        self.entries.contains_key(key)
+    }
+
+    pub fn insert(&mut self,
+                  key: (String, String),
+                  preview: Preview)
+    {
+        let used = Cell::new(self.tick());
+        self.entries.insert(key, (preview, used));
+
+        if self.entries.len() > PREVIEW_CACHE_ENTRIES
+        {
+            let oldest = self.entries
+                             .iter()
+                             .min_by_key(|(_, (_, used))| used.get())
+                             .map(|(key, _)| key.clone());
+            if let Some(key) = oldest
+            {
+                self.entries.remove(&key);
+            }
+        }
+    }
+
+    pub fn clear(&mut self)
+    {
+        self.entries.clear();
+    }
+
+    fn tick(&self) -> u64
+    {
+        let now = self.clock.get() + 1;
+        self.clock.set(now);
+        now
+    }
+}
+
+/// Text lines of a file preview, or None if the content looks binary
+pub fn text_lines(content: &[u8]) -> Option<Vec<String>>
+{
+    if content.contains(&0)
+    {
+        return None;
+    }
+
+    let text = match std::str::from_utf8(content)
+    {
+        Ok(text) => text,
+        // The preview may cut a multi-byte character in half
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&content[..e.valid_up_to()]).ok()?,
+        Err(_) => return None,
+    };
+
+    Some(text.lines().map(|line| line.replace('\t', "    ")).collect())
+}
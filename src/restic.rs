@@ -1,9 +1,18 @@
 use anyhow::{Context, Result, bail};
 use std::process::Stdio;
-use tokio::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use serde::Deserialize;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, Command};
+use tokio::sync::mpsc;
 
+use crate::config::Profile;
+use crate::dirsize::{DirSize, DirSizeAccumulator};
 use crate::file::FileNode;
+use crate::forget::{ForgetGroup, ForgetPolicy};
 use crate::snapshot::Snapshot;
+use crate::stats::{ResticStats, StatsMode};
 
 /// Result of a restic command, including the command string for logging
 pub struct CommandResult<T>
@@ -39,10 +48,95 @@ impl<T> CommandResult<T>
     }
 }
 
+/// restic global options applied to every invocation
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GlobalOptions
+{
+    /// Extended options (`-o key=value`)
+    pub options: Vec<String>,
+    /// Extra TLS root certificates (`--cacert`)
+    pub cacert: Vec<String>,
+    pub insecure_tls: bool,
+    /// Download limit in KiB/s
+    pub limit_download: Option<u32>,
+    pub cache_dir: Option<String>,
+    pub no_cache: bool,
+    pub no_lock: bool,
+    /// Pack size in MiB
+    pub pack_size: Option<u32>,
+}
+
+impl GlobalOptions
+{
+    /// Add options from `other` (e.g. the command line) on top of these
+    pub fn merge(&mut self,
+                 other: GlobalOptions)
+    {
+        self.options.extend(other.options);
+        self.cacert.extend(other.cacert);
+        self.insecure_tls |= other.insecure_tls;
+        self.limit_download = other.limit_download.or(self.limit_download);
+        self.cache_dir = other.cache_dir.or(self.cache_dir.take());
+        self.no_cache |= other.no_cache;
+        self.no_lock |= other.no_lock;
+        self.pack_size = other.pack_size.or(self.pack_size);
+    }
+
+    /// Command-line flags for the options
+    pub fn to_args(&self) -> Vec<String>
+    {
+        let mut args = Vec::new();
+        for option in &self.options
+        {
+            args.push("-o".to_string());
+            args.push(option.clone());
+        }
+        for cacert in &self.cacert
+        {
+            args.push("--cacert".to_string());
+            args.push(cacert.clone());
+        }
+        if self.insecure_tls
+        {
+            args.push("--insecure-tls".to_string());
+        }
+        if let Some(limit) = self.limit_download
+        {
+            args.push("--limit-download".to_string());
+            args.push(limit.to_string());
+        }
+        if let Some(ref dir) = self.cache_dir
+        {
+            args.push("--cache-dir".to_string());
+            args.push(dir.clone());
+        }
+        if self.no_cache
+        {
+            args.push("--no-cache".to_string());
+        }
+        if self.no_lock
+        {
+            args.push("--no-lock".to_string());
+        }
+        if let Some(size) = self.pack_size
+        {
+            args.push("--pack-size".to_string());
+            args.push(size.to_string());
+        }
+        args
+    }
+}
+
 #[derive(Clone)]
 pub struct ResticClient
 {
     repository: String,
+    global_args: Vec<String>,
+    /// Extra environment for restic (e.g. a profile's password file)
+    env: Vec<(String, String)>,
+    /// Inherited variables that would conflict with `env`
+    env_remove: Vec<&'static str>,
 }
 
 impl ResticClient
@@ -53,7 +147,49 @@ impl ResticClient
         let repository = std::env::var("RESTIC_REPOSITORY")
             .context("RESTIC_REPOSITORY environment variable not set")?;
 
-        // Verify password is available (either RESTIC_PASSWORD or RESTIC_PASSWORD_FILE)
+        Self::check_password_env()?;
+
+        Ok(Self {
+            repository,
+            global_args: Vec::new(),
+            env: Vec::new(),
+            env_remove: Vec::new(),
+        })
+    }
+
+    /// Create a client for a repository profile from the config file
+    pub fn from_profile(profile: &Profile) -> Result<Self>
+    {
+        // restic refuses to run with more than one password source, so the
+        // profile's replaces any from the environment
+        let mut env = Vec::new();
+        let mut env_remove = Vec::new();
+        if let Some(ref file) = profile.password_file
+        {
+            env.push(("RESTIC_PASSWORD_FILE".to_string(), file.clone()));
+            env_remove.extend(["RESTIC_PASSWORD", "RESTIC_PASSWORD_COMMAND"]);
+        }
+        else if let Some(ref command) = profile.password_command
+        {
+            env.push(("RESTIC_PASSWORD_COMMAND".to_string(), command.clone()));
+            env_remove.extend(["RESTIC_PASSWORD", "RESTIC_PASSWORD_FILE"]);
+        }
+        else
+        {
+            Self::check_password_env()?;
+        }
+
+        Ok(Self {
+            repository: profile.repository.clone(),
+            global_args: Vec::new(),
+            env,
+            env_remove,
+        })
+    }
+
+    /// Verify a password is available (RESTIC_PASSWORD, RESTIC_PASSWORD_FILE or RESTIC_PASSWORD_COMMAND)
+    fn check_password_env() -> Result<()>
+    {
         if std::env::var("RESTIC_PASSWORD").is_err()
             && std::env::var("RESTIC_PASSWORD_FILE").is_err()
             && std::env::var("RESTIC_PASSWORD_COMMAND").is_err()
@@ -62,26 +198,57 @@ impl ResticClient
                 "No password configured. Set RESTIC_PASSWORD, RESTIC_PASSWORD_FILE, or RESTIC_PASSWORD_COMMAND"
             );
         }
+        Ok(())
+    }
 
-        Ok(Self { repository })
+    /// Pass the given global options to every restic invocation
+    pub fn with_global_options(mut self,
+                               options: &GlobalOptions)
+                               -> Self
+    {
+        self.global_args = options.to_args();
+        self
     }
 
-    /// Build a base command with repository configured
-    fn base_command(&self) -> Command
+    /// Build a command with repository and global options configured
+    /// (human-readable output), along with its command line for logging
+    fn repo_command(&self,
+                    args: &[&str])
+                    -> (Command, String)
     {
+        let mut all_args = vec!["--repo", self.repository.as_str()];
+        all_args.extend(self.global_args.iter().map(String::as_str));
+        all_args.extend_from_slice(args);
+
         let mut cmd = Command::new("restic");
-        cmd.arg("--repo").arg(&self.repository);
-        cmd.arg("--json");
-        cmd
+        cmd.args(&all_args);
+        for key in &self.env_remove
+        {
+            cmd.env_remove(key);
+        }
+        cmd.envs(self.env.iter().map(|(k, v)| (k, v)));
+
+        let command_str = std::iter::once("restic".to_string())
+            .chain(all_args.iter().map(|a| quote_arg(a)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        (cmd, command_str)
+    }
+
+    /// Build a base command with repository and global options configured
+    fn base_command(&self,
+                    args: &[&str])
+                    -> (Command, String)
+    {
+        let mut json_args = vec!["--json"];
+        json_args.extend_from_slice(args);
+        self.repo_command(&json_args)
     }
 
     /// List all snapshots in the repository
     pub async fn list_snapshots(&self) -> CommandResult<Vec<Snapshot>>
     {
-        let command_str = format!("restic --repo {} --json snapshots", self.repository);
-
-        let mut cmd = self.base_command();
-        cmd.arg("snapshots");
+        let (mut cmd, command_str) = self.base_command(&["snapshots"]);
         cmd.stdout(Stdio::piped());
         cmd.stderr(Stdio::piped());
 
@@ -111,7 +278,7 @@ impl ResticClient
             Ok(mut snapshots) =>
             {
                 // Sort by date descending (most recent first)
-                snapshots.sort_by(|a, b| b.time.cmp(&a.time));
+                snapshots.sort_by_key(|s| std::cmp::Reverse(s.time));
                 CommandResult::success(command_str, snapshots)
             }
             Err(e) => CommandResult::failure(
@@ -128,13 +295,7 @@ impl ResticClient
                             path: &str)
                             -> CommandResult<Vec<FileNode>>
     {
-        let command_str = format!("restic --repo {} --json ls {} {}",
-                                  self.repository, snapshot_id, path);
-
-        let mut cmd = self.base_command();
-        cmd.arg("ls");
-        cmd.arg(snapshot_id);
-        cmd.arg(path);
+        let (mut cmd, command_str) = self.base_command(&["ls", snapshot_id, path]);
         cmd.stdout(Stdio::piped());
         cmd.stderr(Stdio::piped());
 
@@ -161,6 +322,7 @@ impl ResticClient
         // restic ls --json outputs one JSON object per line (NDJSON)
         let stdout = String::from_utf8_lossy(&output.stdout);
         let mut files = Vec::new();
+        let mut found_path = false;
 
         for line in stdout.lines()
         {
@@ -176,7 +338,11 @@ impl ResticClient
                 {
                     // Skip the root entry (path == requested path)
                     // and only include direct children
-                    if node.path != path && is_direct_child(&node.path, path)
+                    if node.path.trim_end_matches('/') == path.trim_end_matches('/')
+                    {
+                        found_path = true;
+                    }
+                    else if is_direct_child(&node.path, path)
                     {
                         files.push(node);
                     }
@@ -189,35 +355,196 @@ impl ResticClient
             }
         }
 
-        // Sort: directories first, then by name
-        files.sort_by(|a, b| {
-                 match (a.is_dir(), b.is_dir())
-                 {
-                     (true, false) => std::cmp::Ordering::Less,
-                     (false, true) => std::cmp::Ordering::Greater,
-                     _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
-                 }
-             });
+        // restic ls succeeds with no output for paths missing from the snapshot
+        let is_root = path.trim_end_matches('/').is_empty();
+        if !is_root && !found_path && files.is_empty()
+        {
+            return CommandResult::failure(
+                command_str,
+                anyhow::anyhow!("path not found in snapshot: {}", path),
+                None,
+            );
+        }
 
+        // Sorting is up to the caller (see FileSort)
         CommandResult::success(command_str, files)
     }
 
-    /// Restore a file or directory from a snapshot
-    pub async fn restore(&self,
-                         snapshot_id: &str,
-                         include_path: &str,
-                         target: &str)
-                         -> CommandResult<()>
+    /// Compute recursive directory sizes for a whole snapshot.
+    ///
+    /// Streams `restic ls` output and sends batches of completed directories
+    /// on `batches` as they become known. restic is stopped as soon as
+    /// `cancel` is set, or once `batches` is closed.
+    pub async fn dir_sizes(&self,
+                           snapshot_id: &str,
+                           batches: mpsc::UnboundedSender<Vec<(String, DirSize)>>,
+                           cancel: &AtomicBool)
+                           -> CommandResult<()>
     {
-        let command_str = format!("restic --repo {} restore {} --include {} --target {}",
-                                  self.repository, snapshot_id, include_path, target);
+        const BATCH_SIZE: usize = 256;
 
-        let mut cmd = Command::new("restic");
-        cmd.arg("--repo").arg(&self.repository);
-        cmd.arg("restore");
-        cmd.arg(snapshot_id);
-        cmd.arg("--include").arg(include_path);
-        cmd.arg("--target").arg(target);
+        let (mut cmd, command_str) = self.base_command(&["ls", snapshot_id]);
+        cmd.stdout(Stdio::piped());
+        cmd.stderr(Stdio::piped());
+
+        let mut child = match cmd.spawn()
+        {
+            Ok(c) => c,
+            Err(e) => return CommandResult::failure(
+                command_str,
+                anyhow::anyhow!("Failed to run restic ls: {}", e),
+                None,
+            ),
+        };
+
+        let stderr = drain_stderr(&mut child);
+        let mut accumulator = DirSizeAccumulator::default();
+        let mut pending = Vec::new();
+
+        if let Some(stdout) = child.stdout.take()
+        {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Some(line) = next_line(&mut lines, cancel).await
+            {
+                // Skip the snapshot header and anything else that isn't a node
+                if let Ok(node) = serde_json::from_str::<FileNode>(&line)
+                {
+                    pending.extend(accumulator.push(&node));
+                    if pending.len() >= BATCH_SIZE && batches.send(std::mem::take(&mut pending)).is_err()
+                    {
+                        // Nobody is interested in the rest
+                        let _ = child.kill().await;
+                        return CommandResult::success(command_str, ());
+                    }
+                }
+            }
+        }
+
+        if cancel.load(Ordering::Relaxed)
+        {
+            let _ = child.kill().await;
+            return CommandResult::success(command_str, ());
+        }
+
+        let status = match child.wait().await
+        {
+            Ok(s) => s,
+            Err(e) => return CommandResult::failure(
+                command_str,
+                anyhow::anyhow!("Failed to run restic ls: {}", e),
+                None,
+            ),
+        };
+
+        if !status.success()
+        {
+            let stderr = stderr.await.unwrap_or_default();
+            return CommandResult::failure(
+                command_str,
+                anyhow::anyhow!("restic ls failed: {}", stderr),
+                Some(stderr),
+            );
+        }
+
+        pending.extend(accumulator.finish());
+        let _ = batches.send(pending);
+
+        CommandResult::success(command_str, ())
+    }
+
+    /// List the given directories of a snapshot and everything below them,
+    /// sending batches of entries on `batches` as they are listed.
+    ///
+    /// restic is stopped as soon as `cancel` is set, or once `batches` is closed.
+    pub async fn list_recursive(&self,
+                                snapshot_id: &str,
+                                paths: &[String],
+                                batches: mpsc::UnboundedSender<Vec<FileNode>>,
+                                cancel: &AtomicBool)
+                                -> CommandResult<()>
+    {
+        const BATCH_SIZE: usize = 256;
+
+        let mut args = vec!["ls", "--recursive", snapshot_id];
+        args.extend(paths.iter().map(String::as_str));
+        let (mut cmd, command_str) = self.base_command(&args);
+        cmd.stdout(Stdio::piped());
+        cmd.stderr(Stdio::piped());
+
+        let mut child = match cmd.spawn()
+        {
+            Ok(c) => c,
+            Err(e) => return CommandResult::failure(
+                command_str,
+                anyhow::anyhow!("Failed to run restic ls: {}", e),
+                None,
+            ),
+        };
+
+        let stderr = drain_stderr(&mut child);
+        let mut pending = Vec::new();
+
+        if let Some(stdout) = child.stdout.take()
+        {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Some(line) = next_line(&mut lines, cancel).await
+            {
+                // Skip the snapshot header and anything else that isn't a node
+                match serde_json::from_str::<FileNode>(&line)
+                {
+                    Ok(node) => pending.push(node),
+                    Err(_) => continue,
+                }
+                if pending.len() >= BATCH_SIZE && batches.send(std::mem::take(&mut pending)).is_err()
+                {
+                    // Nobody is interested in the rest
+                    let _ = child.kill().await;
+                    return CommandResult::success(command_str, ());
+                }
+            }
+        }
+
+        if cancel.load(Ordering::Relaxed)
+        {
+            let _ = child.kill().await;
+            return CommandResult::success(command_str, ());
+        }
+
+        let status = match child.wait().await
+        {
+            Ok(s) => s,
+            Err(e) => return CommandResult::failure(
+                command_str,
+                anyhow::anyhow!("Failed to run restic ls: {}", e),
+                None,
+            ),
+        };
+
+        if !status.success()
+        {
+            let stderr = stderr.await.unwrap_or_default();
+            return CommandResult::failure(
+                command_str,
+                anyhow::anyhow!("restic ls failed: {}", stderr),
+                Some(stderr),
+            );
+        }
+
+        let _ = batches.send(pending);
+
+        CommandResult::success(command_str, ())
+    }
+
+    /// Compute stats for a snapshot, or for the whole repository when `snapshot_id` is None
+    pub async fn stats(&self,
+                       snapshot_id: Option<&str>,
+                       mode: StatsMode)
+                       -> CommandResult<ResticStats>
+    {
+        let mut args = vec!["stats", "--mode", mode.as_arg()];
+        args.extend(snapshot_id);
+
+        let (mut cmd, command_str) = self.base_command(&args);
         cmd.stdout(Stdio::piped());
         cmd.stderr(Stdio::piped());
 
@@ -226,7 +553,58 @@ impl ResticClient
             Ok(o) => o,
             Err(e) => return CommandResult::failure(
                 command_str,
-                anyhow::anyhow!("Failed to run restic restore: {}", e),
+                anyhow::anyhow!("Failed to run restic stats: {}", e),
+                None,
+            ),
+        };
+
+        if !output.status.success()
+        {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            return CommandResult::failure(
+                command_str,
+                anyhow::anyhow!("restic stats failed: {}", stderr),
+                Some(stderr),
+            );
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        match serde_json::from_str::<ResticStats>(stdout.trim())
+        {
+            Ok(stats) => CommandResult::success(command_str, stats),
+            Err(e) => CommandResult::failure(
+                command_str,
+                anyhow::anyhow!("Failed to parse stats JSON: {}", e),
+                None,
+            ),
+        }
+    }
+
+    /// Simulate a retention policy. Always passes --dry-run; nothing is removed.
+    ///
+    /// Runs without a lock so read-only or locked repositories work too.
+    pub async fn forget_dry_run(&self,
+                                policy: &ForgetPolicy)
+                                -> CommandResult<Vec<ForgetGroup>>
+    {
+        let policy_args = policy.to_args();
+        let mut args = vec!["forget", "--dry-run"];
+        if !self.global_args.iter().any(|a| a == "--no-lock")
+        {
+            args.push("--no-lock");
+        }
+        args.extend(policy_args.iter().map(String::as_str));
+
+        let (mut cmd, command_str) = self.base_command(&args);
+        cmd.stdout(Stdio::piped());
+        cmd.stderr(Stdio::piped());
+
+        let output = match cmd.output().await
+        {
+            Ok(o) => o,
+            Err(e) => return CommandResult::failure(
+                command_str,
+                anyhow::anyhow!("Failed to run restic forget: {}", e),
                 None,
             ),
         };
@@ -234,6 +612,162 @@ impl ResticClient
         if !output.status.success()
         {
             let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            return CommandResult::failure(
+                command_str,
+                anyhow::anyhow!("restic forget failed: {}", stderr),
+                Some(stderr),
+            );
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        match serde_json::from_str::<Option<Vec<ForgetGroup>>>(stdout.trim())
+        {
+            Ok(groups) => CommandResult::success(command_str, groups.unwrap_or_default()),
+            Err(e) => CommandResult::failure(
+                command_str,
+                anyhow::anyhow!("Failed to parse forget JSON: {}", e),
+                None,
+            ),
+        }
+    }
+
+    /// Check repository integrity, sending each line of output on `lines` as it arrives
+    pub async fn check(&self,
+                       read_data_subset: Option<&str>,
+                       lines: mpsc::UnboundedSender<String>)
+                       -> CommandResult<()>
+    {
+        let subset_arg = read_data_subset.map(|subset| format!("--read-data-subset={}", subset));
+        let mut args = vec!["check"];
+        args.extend(subset_arg.as_deref());
+
+        let (mut cmd, command_str) = self.repo_command(&args);
+        cmd.stdout(Stdio::piped());
+        cmd.stderr(Stdio::piped());
+
+        let mut child = match cmd.spawn()
+        {
+            Ok(c) => c,
+            Err(e) => return CommandResult::failure(
+                command_str,
+                anyhow::anyhow!("Failed to run restic check: {}", e),
+                None,
+            ),
+        };
+
+        // Progress goes to stdout, problems to stderr; stream both
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let read_stdout = async {
+            if let Some(out) = stdout
+            {
+                let mut reader = BufReader::new(out).lines();
+                while let Ok(Some(line)) = reader.next_line().await
+                {
+                    let _ = lines.send(line);
+                }
+            }
+        };
+        let read_stderr = async {
+            let mut collected = Vec::new();
+            if let Some(err) = stderr
+            {
+                let mut reader = BufReader::new(err).lines();
+                while let Ok(Some(line)) = reader.next_line().await
+                {
+                    let _ = lines.send(line.clone());
+                    collected.push(line);
+                }
+            }
+            collected.join("\n")
+        };
+        let (_, stderr_text) = tokio::join!(read_stdout, read_stderr);
+
+        let status = match child.wait().await
+        {
+            Ok(s) => s,
+            Err(e) => return CommandResult::failure(
+                command_str,
+                anyhow::anyhow!("Failed to run restic check: {}", e),
+                None,
+            ),
+        };
+
+        if !status.success()
+        {
+            return CommandResult::failure(
+                command_str,
+                anyhow::anyhow!("restic check failed: {}", stderr_text),
+                Some(stderr_text),
+            );
+        }
+
+        CommandResult::success(command_str, ())
+    }
+
+    /// Restore files or directories from a snapshot in one run, sending
+    /// progress (0.0 - 1.0) on `progress`
+    pub async fn restore(&self,
+                         snapshot_id: &str,
+                         include_paths: &[String],
+                         target: &str,
+                         progress: mpsc::UnboundedSender<f64>)
+                         -> CommandResult<()>
+    {
+        // --include takes glob patterns; paths must match literally
+        let patterns: Vec<String> = include_paths.iter().map(|p| escape_pattern(p)).collect();
+        let mut args = vec!["restore", snapshot_id];
+        for pattern in &patterns
+        {
+            args.push("--include");
+            args.push(pattern);
+        }
+        args.push("--target");
+        args.push(target);
+        let (mut cmd, command_str) = self.base_command(&args);
+        cmd.stdout(Stdio::piped());
+        cmd.stderr(Stdio::piped());
+
+        let mut child = match cmd.spawn()
+        {
+            Ok(c) => c,
+            Err(e) => return CommandResult::failure(
+                command_str,
+                anyhow::anyhow!("Failed to run restic restore: {}", e),
+                None,
+            ),
+        };
+
+        let stderr = drain_stderr(&mut child);
+        // restore --json emits one status object per line while it runs
+        if let Some(stdout) = child.stdout.take()
+        {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await
+            {
+                if let Ok(status) = serde_json::from_str::<RestoreStatus>(&line)
+                {
+                    if let (true, Some(done)) = (status.message_type == "status", status.percent_done)
+                    {
+                        let _ = progress.send(done);
+                    }
+                }
+            }
+        }
+
+        let status = match child.wait().await
+        {
+            Ok(s) => s,
+            Err(e) => return CommandResult::failure(
+                command_str,
+                anyhow::anyhow!("Failed to run restic restore: {}", e),
+                None,
+            ),
+        };
+
+        if !status.success()
+        {
+            let stderr = stderr.await.unwrap_or_default();
             return CommandResult::failure(
                 command_str,
                 anyhow::anyhow!("restic restore failed: {}", stderr),
@@ -243,6 +777,273 @@ impl ResticClient
 
         CommandResult::success(command_str, ())
     }
+
+    /// Dump a file (or a directory as tar) from a snapshot into `target_file`.
+    ///
+    /// Progress is reported when the expected size is known. Refuses to
+    /// overwrite an existing file; a partial file is removed on failure.
+    pub async fn dump(&self,
+                      snapshot_id: &str,
+                      path: &str,
+                      target_file: &str,
+                      size: Option<u64>,
+                      progress: mpsc::UnboundedSender<f64>)
+                      -> CommandResult<()>
+    {
+        let (mut cmd, command_str) = self.repo_command(&["dump", snapshot_id, path]);
+        let command_str = format!("{} > {}", command_str, quote_arg(target_file));
+
+        let mut file = match tokio::fs::OpenOptions::new().write(true)
+                                                          .create_new(true)
+                                                          .open(target_file)
+                                                          .await
+        {
+            Ok(f) => f,
+            Err(e) => return CommandResult::failure(
+                command_str,
+                anyhow::anyhow!("Cannot create {}: {}", target_file, e),
+                None,
+            ),
+        };
+
+        cmd.stdout(Stdio::piped());
+        cmd.stderr(Stdio::piped());
+
+        let mut child = match cmd.spawn()
+        {
+            Ok(c) => c,
+            Err(e) =>
+            {
+                let _ = tokio::fs::remove_file(target_file).await;
+                return CommandResult::failure(
+                    command_str,
+                    anyhow::anyhow!("Failed to run restic dump: {}", e),
+                    None,
+                );
+            }
+        };
+
+        let stderr = drain_stderr(&mut child);
+        let mut write_error = None;
+        if let Some(mut stdout) = child.stdout.take()
+        {
+            let mut buf = vec![0u8; 64 * 1024];
+            let mut written: u64 = 0;
+            loop
+            {
+                let n = match stdout.read(&mut buf).await
+                {
+                    Ok(0) => break,
+                    Ok(n) => n,
+                    Err(e) =>
+                    {
+                        write_error = Some(e);
+                        break;
+                    }
+                };
+                if let Err(e) = file.write_all(&buf[..n]).await
+                {
+                    write_error = Some(e);
+                    break;
+                }
+                written += n as u64;
+                if let Some(total) = size.filter(|t| *t > 0)
+                {
+                    let _ = progress.send((written as f64 / total as f64).min(1.0));
+                }
+            }
+        }
+        let _ = file.flush().await;
+
+        if let Some(e) = write_error
+        {
+            let _ = child.kill().await;
+            let _ = tokio::fs::remove_file(target_file).await;
+            return CommandResult::failure(
+                command_str,
+                anyhow::anyhow!("Failed to write {}: {}", target_file, e),
+                None,
+            );
+        }
+
+        let status = match child.wait().await
+        {
+            Ok(s) => s,
+            Err(e) => return CommandResult::failure(
+                command_str,
+                anyhow::anyhow!("Failed to run restic dump: {}", e),
+                None,
+            ),
+        };
+
+        if !status.success()
+        {
+            let _ = tokio::fs::remove_file(target_file).await;
+            let stderr = stderr.await.unwrap_or_default();
+            return CommandResult::failure(
+                command_str,
+                anyhow::anyhow!("restic dump failed: {}", stderr),
+                Some(stderr),
+            );
+        }
+
+        CommandResult::success(command_str, ())
+    }
+
+    /// Read up to `limit` bytes from the start of a file in a snapshot.
+    ///
+    /// restic is stopped once enough has been read.
+    pub async fn dump_head(&self,
+                           snapshot_id: &str,
+                           path: &str,
+                           limit: usize)
+                           -> CommandResult<Vec<u8>>
+    {
+        let (mut cmd, command_str) = self.repo_command(&["dump", snapshot_id, path]);
+        let command_str = format!("{} | head -c {}", command_str, limit);
+        cmd.stdout(Stdio::piped());
+        cmd.stderr(Stdio::piped());
+
+        let mut child = match cmd.spawn()
+        {
+            Ok(c) => c,
+            Err(e) => return CommandResult::failure(
+                command_str,
+                anyhow::anyhow!("Failed to run restic dump: {}", e),
+                None,
+            ),
+        };
+
+        let stderr = drain_stderr(&mut child);
+        let mut head = Vec::new();
+        if let Some(stdout) = child.stdout.take()
+        {
+            if let Err(e) = stdout.take(limit as u64).read_to_end(&mut head).await
+            {
+                let _ = child.kill().await;
+                return CommandResult::failure(
+                    command_str,
+                    anyhow::anyhow!("Failed to read restic dump output: {}", e),
+                    None,
+                );
+            }
+        }
+
+        // The rest of the file isn't needed
+        if head.len() >= limit
+        {
+            let _ = child.kill().await;
+            return CommandResult::success(command_str, head);
+        }
+
+        let status = match child.wait().await
+        {
+            Ok(s) => s,
+            Err(e) => return CommandResult::failure(
+                command_str,
+                anyhow::anyhow!("Failed to run restic dump: {}", e),
+                None,
+            ),
+        };
+
+        if !status.success()
+        {
+            let stderr = stderr.await.unwrap_or_default();
+            return CommandResult::failure(
+                command_str,
+                anyhow::anyhow!("restic dump failed: {}", stderr),
+                Some(stderr),
+            );
+        }
+
+        CommandResult::success(command_str, head)
+    }
+}
+
+/// Progress line from `restic restore --json`
+#[derive(Deserialize)]
+struct RestoreStatus
+{
+    message_type: String,
+    #[serde(default)]
+    percent_done: Option<f64>,
+}
+
+/// True if restic's error message says the snapshot or path doesn't exist
+pub fn is_not_found(message: &str) -> bool
+{
+    let lower = message.to_lowercase();
+    lower.contains("no matching id")
+        || lower.contains("no snapshot")
+        || lower.contains("not found")
+        || lower.contains("no such file")
+}
+
+/// Quote an argument for the logged command line if the shell would split it
+fn quote_arg(arg: &str) -> String
+{
+    let plain = !arg.is_empty()
+        && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:=,@+%".contains(c));
+    if plain
+    {
+        arg.to_string()
+    }
+    else
+    {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// How often a streaming command checks for cancellation while waiting for output
+const CANCEL_POLL: Duration = Duration::from_millis(100);
+
+/// Next line of a command's output; None at the end or once `cancel` is set
+async fn next_line<R: AsyncRead + Unpin>(lines: &mut Lines<BufReader<R>>,
+                                         cancel: &AtomicBool)
+                                         -> Option<String>
+{
+    loop
+    {
+        if cancel.load(Ordering::Relaxed)
+        {
+            return None;
+        }
+        // next_line is cancel safe, so a partial line isn't lost on timeout
+        tokio::select! {
+            line = lines.next_line() => return line.ok().flatten(),
+            _ = tokio::time::sleep(CANCEL_POLL) => {}
+        }
+    }
+}
+
+/// Read a child's stderr in the background while its stdout is streamed, so
+/// restic can't block on a full stderr pipe
+fn drain_stderr(child: &mut Child) -> tokio::task::JoinHandle<String>
+{
+    let stderr = child.stderr.take();
+    tokio::spawn(async move {
+        let mut buf = Vec::new();
+        if let Some(mut stderr) = stderr
+        {
+            let _ = stderr.read_to_end(&mut buf).await;
+        }
+        String::from_utf8_lossy(&buf).to_string()
+    })
+}
+
+/// Escape glob metacharacters so restic matches a path literally
+fn escape_pattern(path: &str) -> String
+{
+    let mut escaped = String::with_capacity(path.len());
+    for c in path.chars()
+    {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\')
+        {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
 }
 
 /// Check if child_path is a direct child of parent_path
@@ -264,9 +1065,8 @@ fn is_direct_child(child_path: &str,
     let remaining = &child[parent.len()..];
 
     // Must start with / and have no other / in the remaining path
-    if remaining.starts_with('/')
+    if let Some(after_slash) = remaining.strip_prefix('/')
     {
-        let after_slash = &remaining[1..];
         !after_slash.contains('/')
     }
     else if parent.is_empty() || parent == "/"
@@ -0,0 +1,346 @@
+use regex::{Regex, RegexBuilder};
+
+/// How the file filter interprets the query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchMode
+{
+    Fuzzy,      // Characters in order, ranked by score (fzf-style)
+    Substring,  // Name contains the query
+    Glob,       // Whole name matches a shell pattern: *.sql.gz
+    Regex,      // Name contains a match of a regular expression
+}
+
+impl SearchMode
+{
+    /// Next mode in the cycle
+    pub fn next(&self) -> Self
+    {
+        match self
+        {
+            SearchMode::Fuzzy => SearchMode::Substring,
+            SearchMode::Substring => SearchMode::Glob,
+            SearchMode::Glob => SearchMode::Regex,
+            SearchMode::Regex => SearchMode::Fuzzy,
+        }
+    }
+
+    /// Parse a label (as used in the config file)
+    pub fn parse(label: &str) -> Option<Self>
+    {
+        match label
+        {
+            "fuzzy" => Some(SearchMode::Fuzzy),
+            "substring" => Some(SearchMode::Substring),
+            "glob" => Some(SearchMode::Glob),
+            "regex" => Some(SearchMode::Regex),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str
+    {
+        match self
+        {
+            SearchMode::Fuzzy => "fuzzy",
+            SearchMode::Substring => "substring",
+            SearchMode::Glob => "glob",
+            SearchMode::Regex => "regex",
+        }
+    }
+}
+
+/// A compiled search query
+#[derive(Debug, Clone)]
+pub enum Matcher
+{
+    Fuzzy(String),
+    /// Substring (as an escaped regex), glob (translated to an anchored
+    /// regex) or regex
+    Pattern(Regex),
+}
+
+impl Default for Matcher
+{
+    fn default() -> Self
+    {
+        Matcher::Fuzzy(String::new())
+    }
+}
+
+impl Matcher
+{
+    /// Compile a query; all modes use smart case
+    pub fn new(mode: SearchMode,
+               query: &str)
+               -> Result<Self, String>
+    {
+        let case_sensitive = has_uppercase(query, mode == SearchMode::Regex);
+        match mode
+        {
+            SearchMode::Fuzzy => Ok(Matcher::Fuzzy(query.to_string())),
+            SearchMode::Substring | SearchMode::Glob | SearchMode::Regex =>
+            {
+                // Case folding goes through the regex so spans stay on the
+                // original name
+                let pattern = match mode
+                {
+                    SearchMode::Substring => regex::escape(query),
+                    SearchMode::Glob => glob_to_regex(query),
+                    _ => query.to_string(),
+                };
+                RegexBuilder::new(&pattern).case_insensitive(!case_sensitive)
+                                           .build()
+                                           .map(Matcher::Pattern)
+                                           .map_err(|e| regex_error(&e))
+            }
+        }
+    }
+
+    /// Score and matched characters if `name` matches. Only fuzzy matches
+    /// are scored; other modes score 0 so directory order is kept.
+    pub fn find(&self,
+                name: &str)
+                -> Option<SearchMatch>
+    {
+        match self
+        {
+            Matcher::Fuzzy(query) => fuzzy_match(query, name),
+            Matcher::Pattern(regex) =>
+            {
+                let m = regex.find(name)?;
+                Some(SearchMatch::span(name, m.start(), m.end()))
+            }
+        }
+    }
+}
+
+/// A match of a query against a name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchMatch
+{
+    /// Higher is better
+    pub score: i32,
+    /// Character indices (not byte offsets) of the matched characters in the name
+    pub positions: Vec<usize>,
+}
+
+impl SearchMatch
+{
+    /// Unscored match of the bytes `start..end` of `text`
+    fn span(text: &str,
+            start: usize,
+            end: usize)
+            -> Self
+    {
+        let first = text[..start].chars().count();
+        let count = text[start..end].chars().count();
+        Self {
+            score: 0,
+            positions: (first..first + count).collect(),
+        }
+    }
+}
+
+/// Smart case: uppercase in the query makes it case-sensitive. In a regex,
+/// escapes such as `\W` or `\S` don't count.
+fn has_uppercase(query: &str,
+                 regex: bool)
+                 -> bool
+{
+    let mut chars = query.chars();
+    while let Some(c) = chars.next()
+    {
+        if regex && c == '\\'
+        {
+            chars.next();
+        }
+        else if c.is_uppercase()
+        {
+            return true;
+        }
+    }
+    false
+}
+
+/// Translate a glob (`*`, `?`, `[abc]`, `[!abc]`) into an anchored regex
+fn glob_to_regex(glob: &str) -> String
+{
+    let mut pattern = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next()
+    {
+        match c
+        {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            '[' =>
+            {
+                pattern.push('[');
+                if chars.next_if(|&c| c == '!' || c == '^').is_some()
+                {
+                    pattern.push('^');
+                }
+                // Copy the class; an unterminated class is reported by the regex parser
+                for c in chars.by_ref()
+                {
+                    // Characters with a special meaning inside regex classes
+                    if matches!(c, '\\' | '[' | '&' | '~')
+                    {
+                        pattern.push('\\');
+                    }
+                    pattern.push(c);
+                    if c == ']'
+                    {
+                        break;
+                    }
+                }
+            }
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    pattern
+}
+
+/// Short, single-line form of a regex compile error
+fn regex_error(error: &regex::Error) -> String
+{
+    match error
+    {
+        // The syntax error message spans several lines with a caret diagram; keep the last line
+        regex::Error::Syntax(text) => text.lines()
+                                          .rev()
+                                          .find(|l| !l.trim().is_empty())
+                                          .unwrap_or("invalid pattern")
+                                          .trim_start_matches("error: ")
+                                          .to_string(),
+        other => other.to_string(),
+    }
+}
+
+// Scoring, roughly as in fzf: every matched character scores, matches at word
+// starts and runs of consecutive characters score extra, and gaps cost.
+const SCORE_MATCH: i32 = 16;
+const GAP_START: i32 = 3;
+const GAP_EXTEND: i32 = 1;
+const BONUS_CONSECUTIVE: i32 = 4;
+const BONUS_START: i32 = 10;
+const BONUS_BOUNDARY: i32 = 8;
+const BONUS_CAMEL: i32 = 7;
+
+/// Match `query` as a subsequence of `name`, fzf-style.
+///
+/// Smart case: case-sensitive only when the query contains an uppercase
+/// letter. An empty query matches everything with score 0.
+pub fn fuzzy_match(query: &str,
+                   name: &str)
+                   -> Option<SearchMatch>
+{
+    let case_sensitive = query.chars().any(char::is_uppercase);
+    let fold = |c: char| if case_sensitive { c } else { c.to_lowercase().next().unwrap_or(c) };
+
+    let query: Vec<char> = query.chars().map(fold).collect();
+    let original: Vec<char> = name.chars().collect();
+    let text: Vec<char> = original.iter().copied().map(fold).collect();
+    let (m, n) = (query.len(), text.len());
+
+    if m == 0
+    {
+        return Some(SearchMatch { score: 0, positions: Vec::new() });
+    }
+    if m > n
+    {
+        return None;
+    }
+
+    let bonus: Vec<i32> = (0..n).map(|j| position_bonus(&original, j)).collect();
+
+    // score[i][j]: best score with query[i] matched at text[j]; from[i][j]: where query[i - 1] was matched
+    let mut score = vec![vec![None::<i32>; n]; m];
+    let mut from = vec![vec![0usize; n]; m];
+
+    for i in 0..m
+    {
+        // Best earlier match of query[i - 1] with the gap cost up to j, and its position
+        let mut carry: Option<(i32, usize)> = None;
+
+        for j in i..n
+        {
+            if i > 0 && j >= 2
+            {
+                // Extend the running gap by one, or start a gap after a match at j - 2
+                carry = carry.map(|(s, k)| (s - GAP_EXTEND, k));
+                if let Some(s) = score[i - 1][j - 2]
+                {
+                    let started = s - GAP_START;
+                    if carry.map_or(true, |(c, _)| started > c)
+                    {
+                        carry = Some((started, j - 2));
+                    }
+                }
+            }
+
+            if text[j] != query[i]
+            {
+                continue;
+            }
+
+            let here = SCORE_MATCH + if i == 0 { bonus[j] * 2 } else { bonus[j] };
+            if i == 0
+            {
+                score[i][j] = Some(here);
+                continue;
+            }
+
+            let consecutive = score[i - 1][j - 1].map(|s| (s + here + BONUS_CONSECUTIVE, j - 1));
+            let gapped = carry.map(|(s, k)| (s + here, k));
+            let best = match (consecutive, gapped)
+            {
+                (Some(a), Some(b)) => Some(if b.0 > a.0 { b } else { a }),
+                (a, b) => a.or(b),
+            };
+            if let Some((s, k)) = best
+            {
+                score[i][j] = Some(s);
+                from[i][j] = k;
+            }
+        }
+    }
+
+    let (end, best) = (0..n).filter_map(|j| score[m - 1][j].map(|s| (j, s)))
+                            .max_by_key(|&(j, s)| (s, std::cmp::Reverse(j)))?;
+
+    let mut positions = vec![0; m];
+    let mut j = end;
+    for i in (0..m).rev()
+    {
+        positions[i] = j;
+        j = from[i][j];
+    }
+
+    Some(SearchMatch { score: best, positions })
+}
+
+/// Extra score for a match at `j`: start of the name, start of a word, or a camelCase hump
+fn position_bonus(text: &[char],
+                  j: usize)
+                  -> i32
+{
+    if j == 0
+    {
+        return BONUS_START;
+    }
+    let (prev, cur) = (text[j - 1], text[j]);
+    if !prev.is_alphanumeric() && cur.is_alphanumeric()
+    {
+        BONUS_BOUNDARY
+    }
+    else if prev.is_lowercase() && cur.is_uppercase() || !prev.is_ascii_digit() && cur.is_ascii_digit()
+    {
+        BONUS_CAMEL
+    }
+    else
+    {
+        0
+    }
+}
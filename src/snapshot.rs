@@ -1,7 +1,7 @@
 use chrono::{DateTime, Utc};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Snapshot
 {
     #[serde(rename = "id")]
@@ -25,9 +25,11 @@ impl Snapshot
         &self.short_id
     }
 
-    /// Formats the time for display
-    pub fn formatted_time(&self) -> String
+    /// Formats the time for display (strftime `format`)
+    pub fn formatted_time(&self,
+                          format: &str)
+                          -> String
     {
-        self.time.format("%Y-%m-%d %H:%M").to_string()
+        self.time.format(format).to_string()
     }
 }
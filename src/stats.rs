@@ -0,0 +1,114 @@
+use serde::Deserialize;
+
+use crate::file::format_bytes;
+
+/// What a stats request is computed over
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum StatsTarget
+{
+    /// A single snapshot (full ID)
+    Snapshot(String),
+    /// All snapshots in the repository
+    Repository,
+}
+
+/// Counting mode passed to `restic stats --mode`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsMode
+{
+    /// Size of the files as they would be restored
+    RestoreSize,
+    /// Size of the deduplicated blobs stored in the repository
+    RawData,
+}
+
+impl StatsMode
+{
+    /// Value for the `--mode` flag
+    pub fn as_arg(&self) -> &'static str
+    {
+        match self
+        {
+            StatsMode::RestoreSize => "restore-size",
+            StatsMode::RawData => "raw-data",
+        }
+    }
+}
+
+/// Output of `restic stats --json` (fields depend on the mode)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ResticStats
+{
+    #[serde(default)]
+    pub total_size: u64,
+    #[serde(default)]
+    pub total_file_count: u64,
+    #[serde(default)]
+    pub total_blob_count: u64,
+    #[serde(default)]
+    pub snapshots_count: u64,
+    #[serde(default)]
+    pub total_uncompressed_size: u64,
+    #[serde(default)]
+    pub compression_ratio: f64,
+}
+
+impl ResticStats
+{
+    /// Formatted total size
+    pub fn formatted_size(&self) -> String
+    {
+        format_bytes(self.total_size)
+    }
+
+    /// Formatted uncompressed size (raw-data mode)
+    pub fn formatted_uncompressed_size(&self) -> String
+    {
+        format_bytes(self.total_uncompressed_size)
+    }
+
+    /// Formatted compression ratio, or "-" for uncompressed repositories
+    pub fn formatted_compression_ratio(&self) -> String
+    {
+        if self.compression_ratio > 0.0
+        {
+            format!("{:.2}x", self.compression_ratio)
+        }
+        else
+        {
+            "-".to_string()
+        }
+    }
+}
+
+/// Progress of one stats command
+#[derive(Debug, Clone)]
+pub enum StatsStatus
+{
+    Loading,
+    Loaded(ResticStats),
+    Failed(String),
+}
+
+/// Stats for one target, filled in as each mode finishes (None = not requested)
+#[derive(Debug, Clone, Default)]
+pub struct StatsEntry
+{
+    pub restore_size: Option<StatsStatus>,
+    pub raw_data: Option<StatsStatus>,
+}
+
+impl StatsEntry
+{
+    /// Get the slot for a mode
+    pub fn slot_mut(&mut self,
+                    mode: StatsMode)
+                    -> &mut Option<StatsStatus>
+    {
+        match mode
+        {
+            StatsMode::RestoreSize => &mut self.restore_size,
+            StatsMode::RawData => &mut self.raw_data,
+        }
+    }
+}
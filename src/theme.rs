@@ -0,0 +1,272 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use ratatui::style::{Color, Modifier, Style};
+
+/// Names of the built-in themes
+pub const THEME_NAMES: &[&str] = &["default", "light", "high-contrast", "mono"];
+
+/// Styles for every element the UI draws
+#[derive(Debug, Clone)]
+pub struct Theme
+{
+    /// Border of an unfocused panel
+    pub border: Style,
+    /// Border of the focused panel
+    pub border_focused: Style,
+    /// Border of dialogs and overlays
+    pub dialog_border: Style,
+    /// Cursor row in the focused panel
+    pub selected: Style,
+    /// Cursor row in an unfocused panel
+    pub selected_inactive: Style,
+    /// Regular list rows
+    pub text: Style,
+    /// Directories in the Files panel and download dialog
+    pub directory: Style,
+    /// Characters matched by the file search
+    pub search_match: Style,
+    /// Hints, placeholders, the status bar and other secondary text
+    pub muted: Style,
+    /// Key names and field labels
+    pub label: Style,
+    /// Section headings in overlays
+    pub heading: Style,
+    /// Titles inside overlays
+    pub emphasis: Style,
+    /// Successful or kept items (jobs done, KEEP, clean check)
+    pub success: Style,
+    /// Errors and removed items
+    pub error: Style,
+    /// Running work, loading and confirmations
+    pub warning: Style,
+    /// Command log: OK marker
+    pub log_success: Style,
+    /// Command log: FAIL marker and error output
+    pub log_failure: Style,
+    /// Text being typed into inputs
+    pub input: Style,
+    /// Border of the focused input box
+    pub input_focused: Style,
+    pub button: Style,
+    pub button_focused: Style,
+}
+
+impl Default for Theme
+{
+    fn default() -> Self
+    {
+        Self::builtin("default").expect("default theme exists")
+    }
+}
+
+impl Theme
+{
+    /// A built-in theme by name
+    pub fn builtin(name: &str) -> Option<Self>
+    {
+        let fg = |c: Color| Style::default().fg(c);
+        let bold = |s: Style| s.add_modifier(Modifier::BOLD);
+
+        let theme = match name
+        {
+            "default" => Self {
+                border: fg(Color::DarkGray),
+                border_focused: fg(Color::Cyan),
+                dialog_border: fg(Color::Cyan),
+                selected: bold(fg(Color::Yellow)),
+                selected_inactive: fg(Color::White),
+                text: fg(Color::Gray),
+                directory: fg(Color::Blue),
+                search_match: bold(fg(Color::LightGreen)),
+                muted: fg(Color::DarkGray),
+                label: fg(Color::Cyan),
+                heading: fg(Color::Yellow),
+                emphasis: bold(Style::default()),
+                success: fg(Color::Green),
+                error: fg(Color::Red),
+                warning: fg(Color::Yellow),
+                log_success: fg(Color::Green),
+                log_failure: fg(Color::Red),
+                input: fg(Color::White),
+                input_focused: fg(Color::Yellow),
+                button: fg(Color::White),
+                button_focused: bold(fg(Color::Black).bg(Color::Yellow)),
+            },
+            // For terminals with a light background
+            "light" => Self {
+                border: fg(Color::Gray),
+                border_focused: fg(Color::Blue),
+                dialog_border: fg(Color::Blue),
+                selected: bold(fg(Color::Magenta)),
+                selected_inactive: bold(fg(Color::Black)),
+                text: fg(Color::Black),
+                directory: fg(Color::Blue),
+                search_match: bold(fg(Color::Red)),
+                muted: fg(Color::DarkGray),
+                label: fg(Color::Blue),
+                heading: bold(fg(Color::Magenta)),
+                emphasis: bold(Style::default()),
+                success: fg(Color::Green),
+                error: fg(Color::Red),
+                warning: fg(Color::Magenta),
+                log_success: fg(Color::Green),
+                log_failure: fg(Color::Red),
+                input: fg(Color::Black),
+                input_focused: fg(Color::Magenta),
+                button: fg(Color::Black),
+                button_focused: bold(fg(Color::White).bg(Color::Blue)),
+            },
+            "high-contrast" => Self {
+                border: fg(Color::White),
+                border_focused: bold(fg(Color::LightYellow)),
+                dialog_border: bold(fg(Color::LightYellow)),
+                selected: bold(fg(Color::Black).bg(Color::LightYellow)),
+                selected_inactive: fg(Color::Black).bg(Color::White),
+                text: fg(Color::White),
+                directory: bold(fg(Color::LightCyan)),
+                search_match: bold(fg(Color::LightMagenta)).add_modifier(Modifier::UNDERLINED),
+                muted: fg(Color::Gray),
+                label: bold(fg(Color::LightCyan)),
+                heading: bold(fg(Color::LightYellow)),
+                emphasis: bold(fg(Color::White)),
+                success: bold(fg(Color::LightGreen)),
+                error: bold(fg(Color::LightRed)),
+                warning: bold(fg(Color::LightYellow)),
+                log_success: bold(fg(Color::LightGreen)),
+                log_failure: bold(fg(Color::LightRed)),
+                input: bold(fg(Color::White)),
+                input_focused: bold(fg(Color::LightYellow)),
+                button: fg(Color::White),
+                button_focused: bold(fg(Color::Black).bg(Color::LightYellow)),
+            },
+            "mono" => Self::mono(),
+            _ => return None,
+        };
+        Some(theme)
+    }
+
+    /// No colors, only text attributes (used for NO_COLOR)
+    pub fn mono() -> Self
+    {
+        let plain = Style::default();
+        let with = |m: Modifier| Style::default().add_modifier(m);
+        Self {
+            border: with(Modifier::DIM),
+            border_focused: with(Modifier::BOLD),
+            dialog_border: with(Modifier::BOLD),
+            selected: with(Modifier::REVERSED | Modifier::BOLD),
+            selected_inactive: with(Modifier::REVERSED),
+            text: plain,
+            directory: with(Modifier::BOLD),
+            search_match: with(Modifier::UNDERLINED),
+            muted: with(Modifier::DIM),
+            label: with(Modifier::BOLD),
+            heading: with(Modifier::BOLD | Modifier::UNDERLINED),
+            emphasis: with(Modifier::BOLD),
+            success: plain,
+            error: with(Modifier::BOLD),
+            warning: with(Modifier::BOLD),
+            log_success: plain,
+            log_failure: with(Modifier::BOLD),
+            input: plain,
+            input_focused: with(Modifier::BOLD),
+            button: plain,
+            button_focused: with(Modifier::REVERSED | Modifier::BOLD),
+        }
+    }
+
+    /// Build the theme from config: a base theme plus per-element overrides
+    pub fn from_config(name: &str,
+                       overrides: &BTreeMap<String, String>)
+                       -> Result<Self, String>
+    {
+        let mut theme = Self::builtin(name).ok_or_else(|| {
+            format!("theme: unknown theme '{}' (built-in: {})", name, THEME_NAMES.join(", "))
+        })?;
+        for (element, spec) in overrides
+        {
+            let style = parse_style(spec).map_err(|e| format!("colors.{}: {}", element, e))?;
+            let slot = theme.slot_mut(element).ok_or_else(|| format!("colors: unknown element '{}'", element))?;
+            *slot = style;
+        }
+        Ok(theme)
+    }
+
+    fn slot_mut(&mut self,
+                element: &str)
+                -> Option<&mut Style>
+    {
+        let slot = match element
+        {
+            "border" => &mut self.border,
+            "border_focused" => &mut self.border_focused,
+            "dialog_border" => &mut self.dialog_border,
+            "selected" => &mut self.selected,
+            "selected_inactive" => &mut self.selected_inactive,
+            "text" => &mut self.text,
+            "directory" => &mut self.directory,
+            "search_match" => &mut self.search_match,
+            "muted" => &mut self.muted,
+            "label" => &mut self.label,
+            "heading" => &mut self.heading,
+            "emphasis" => &mut self.emphasis,
+            "success" => &mut self.success,
+            "error" => &mut self.error,
+            "warning" => &mut self.warning,
+            "log_success" => &mut self.log_success,
+            "log_failure" => &mut self.log_failure,
+            "input" => &mut self.input,
+            "input_focused" => &mut self.input_focused,
+            "button" => &mut self.button,
+            "button_focused" => &mut self.button_focused,
+            _ => return None,
+        };
+        Some(slot)
+    }
+}
+
+/// True if the NO_COLOR environment variable asks for monochrome output
+pub fn no_color() -> bool
+{
+    std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+}
+
+/// Parse a style such as "yellow bold", "white on blue" or "#ff8800 underlined"
+fn parse_style(spec: &str) -> Result<Style, String>
+{
+    let mut style = Style::default();
+    let mut words = spec.split_whitespace();
+    while let Some(word) = words.next()
+    {
+        let modifier = match word.to_lowercase().as_str()
+        {
+            "bold" => Some(Modifier::BOLD),
+            "dim" => Some(Modifier::DIM),
+            "italic" => Some(Modifier::ITALIC),
+            "underlined" | "underline" => Some(Modifier::UNDERLINED),
+            "reversed" | "reverse" => Some(Modifier::REVERSED),
+            _ => None,
+        };
+
+        if let Some(m) = modifier
+        {
+            style = style.add_modifier(m);
+        }
+        else if word.eq_ignore_ascii_case("on")
+        {
+            let color = words.next().ok_or_else(|| "'on' needs a background color".to_string())?;
+            style = style.bg(parse_color(color)?);
+        }
+        else
+        {
+            style = style.fg(parse_color(word)?);
+        }
+    }
+    Ok(style)
+}
+
+fn parse_color(word: &str) -> Result<Color, String>
+{
+    Color::from_str(word).map_err(|_| format!("unknown color '{}'", word))
+}
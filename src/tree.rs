@@ -0,0 +1,103 @@
+use crate::file::FileNode;
+
+/// A row of the Files panel's tree layout
+#[derive(Debug, Clone)]
+pub struct TreeRow<'a>
+{
+    pub node: &'a FileNode,
+    pub depth: usize,
+    /// Indentation guides drawn before the name, e.g. "│ ├ "
+    pub guides: String,
+}
+
+/// Flatten the expanded part of a directory tree into display rows.
+///
+/// `children` returns the loaded children of an expanded directory, `keep`
+/// hides entries (dotfiles), and `matches` is the search filter: when set, an
+/// entry is shown if it matches or an expanded descendant does.
+pub fn flatten<'a>(roots: &'a [FileNode],
+                   children: &dyn Fn(&str) -> Option<&'a [FileNode]>,
+                   keep: &dyn Fn(&FileNode) -> bool,
+                   matches: Option<&dyn Fn(&FileNode) -> bool>)
+                   -> Vec<TreeRow<'a>>
+{
+    let walker = Walker { children, keep, matches };
+    let mut rows = Vec::new();
+    walker.walk(roots, 0, "", &mut rows);
+    rows
+}
+
+struct Walker<'a, 'f>
+{
+    children: &'f dyn Fn(&str) -> Option<&'a [FileNode]>,
+    keep: &'f dyn Fn(&FileNode) -> bool,
+    matches: Option<&'f dyn Fn(&FileNode) -> bool>,
+}
+
+impl<'a> Walker<'a, '_>
+{
+    fn walk(&self,
+            nodes: &'a [FileNode],
+            depth: usize,
+            prefix: &str,
+            rows: &mut Vec<TreeRow<'a>>)
+    {
+        let shown: Vec<&FileNode> = nodes.iter().filter(|n| self.shown(n)).collect();
+
+        for (i, node) in shown.iter().enumerate()
+        {
+            let last = i + 1 == shown.len();
+            let (branch, continuation) = match (depth, last)
+            {
+                (0, _) => ("", ""),
+                (_, false) => ("├ ", "│ "),
+                (_, true) => ("└ ", "  "),
+            };
+            rows.push(TreeRow {
+                node,
+                depth,
+                guides: format!("{}{}", prefix, branch),
+            });
+
+            // ".." points back up; never expand it
+            if node.name == ".."
+            {
+                continue;
+            }
+            if let Some(children) = (self.children)(&node.path)
+            {
+                self.walk(children, depth + 1, &format!("{}{}", prefix, continuation), rows);
+            }
+        }
+    }
+
+    /// True if the entry gets a row
+    fn shown(&self,
+             node: &FileNode)
+             -> bool
+    {
+        if node.name == ".."
+        {
+            return true;
+        }
+        if !(self.keep)(node)
+        {
+            return false;
+        }
+        match self.matches
+        {
+            Some(matches) => matches(node) || self.has_match_below(node),
+            None => true,
+        }
+    }
+
+    fn has_match_below(&self,
+                       node: &FileNode)
+                       -> bool
+    {
+        (self.children)(&node.path).is_some_and(|children| {
+            children.iter()
+                    .any(|c| (self.keep)(c) && (self.matches.is_some_and(|m| m(c)) || self.has_match_below(c)))
+        })
+    }
+}
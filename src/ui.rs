@@ -1,12 +1,25 @@
 use ratatui::{
     Frame,
     layout::{Constraint, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
 };
 
-use crate::app::{App, AppState, DialogFocus, DownloadDialog, Panel};
+use crate::app::{App, AppState, DialogFocus, DownloadDialog, DownloadMode, FilesLayout, Panel};
+use crate::check::{CheckStatus, format_duration, is_error_line};
+use crate::compare::{Change, CompareRow, Listing};
+use crate::diff::{DiffBody, LineKind, Summary};
+use crate::deepsearch::DeepSearchStatus;
+use crate::file::{FileNode, parent_entry};
+use crate::forget::POLICY_FIELDS;
+use crate::jobs::JobStatus;
+use crate::keymap::{Action, SECTIONS};
+use crate::mouse::{ListArea, MouseAreas};
+use crate::preview::{Preview, text_lines};
+use crate::search::SearchMode;
+use crate::stats::{StatsEntry, StatsStatus, StatsTarget};
+use crate::theme::Theme;
 
 /// Main render function
 pub fn render(frame: &mut Frame,
@@ -20,27 +33,100 @@ pub fn render(frame: &mut Frame,
     ])
     .split(frame.area());
 
+    // Clickable areas are recorded again as everything is drawn
+    app.mouse_areas = MouseAreas::default();
+
     render_snapshots(frame, app, chunks[0]);
     render_files(frame, app, chunks[1]);
-    render_command_log(frame, app, chunks[2]);
+
+    // Jobs and check results share the bottom row with the command log once they exist
+    let show_jobs = !app.jobs.is_empty();
+    let show_check = app.check.is_some();
+    let mut constraints = vec![Constraint::Fill(1)]; // Command log
+    if show_jobs
+    {
+        constraints.push(Constraint::Fill(1));
+    }
+    if show_check
+    {
+        constraints.push(Constraint::Fill(1));
+    }
+    let bottom = Layout::horizontal(constraints).split(chunks[2]);
+    render_command_log(frame, app, bottom[0]);
+    if show_jobs
+    {
+        render_jobs_panel(frame, app, bottom[1]);
+    }
+    if show_check
+    {
+        render_check_panel(frame, app, bottom[bottom.len() - 1]);
+    }
     render_status_bar(frame, app, chunks[3]);
 
     // Render loading overlay if loading
-    if matches!(app.state, AppState::Loading | AppState::Downloading(_))
+    if app.state == AppState::Loading
     {
         render_loading_overlay(frame, app);
     }
 
+    // Render snapshot comparison (also behind the diff or a download dialog opened from it)
+    if app.compare.is_some()
+    {
+        render_compare(frame, app);
+    }
+
+    // Render diff of a compared file
+    if app.state == AppState::Diff
+    {
+        render_diff(frame, app);
+    }
+
     // Render download dialog
     if app.state == AppState::DownloadDialog
     {
         render_download_dialog(frame, app);
     }
 
+    // Render retention policy dialog
+    if app.state == AppState::ForgetDialog
+    {
+        render_forget_dialog(frame, app);
+    }
+
+    // Render check prompt
+    if app.state == AppState::CheckPrompt
+    {
+        render_check_prompt(frame, app);
+    }
+
+    // Render go-to-path prompt
+    if app.state == AppState::GoToPath
+    {
+        render_goto_prompt(frame, app);
+    }
+
+    // Render stats view
+    if app.state == AppState::Stats
+    {
+        render_stats_overlay(frame, app);
+    }
+
+    // Render deep search
+    if app.state == AppState::DeepSearch
+    {
+        render_deep_search(frame, app);
+    }
+
+    // Render bookmark picker
+    if app.state == AppState::Bookmarks
+    {
+        render_bookmarks(frame, app);
+    }
+
     // Render help overlay if in help state
     if app.state == AppState::Help
     {
-        render_help_overlay(frame);
+        render_help_overlay(frame, app);
     }
 }
 
@@ -49,14 +135,15 @@ fn render_snapshots(frame: &mut Frame,
                     app: &mut App,
                     area: Rect)
 {
+    let theme = app.theme.clone();
     let focused = app.focused_panel == Panel::Snapshots;
     let border_style = if focused
     {
-        Style::default().fg(Color::Cyan)
+        theme.border_focused
     }
     else
     {
-        Style::default().fg(Color::DarkGray)
+        theme.border
     };
 
     // Calculate visible height (area height minus borders)
@@ -67,8 +154,18 @@ fn render_snapshots(frame: &mut Frame,
 
     // Adjust scroll to keep cursor visible
     app.adjust_scroll(Panel::Snapshots, visible_height);
+    app.mouse_areas.panels.push((Panel::Snapshots,
+                                 ListArea::scrolled(area, app.snapshot_scroll, visible_height, app.snapshots.len())));
 
-    let title = format!(" Snapshots ({}) ", app.snapshots.len());
+    let title = match &app.forget_plan
+    {
+        Some(plan) => format!(" Snapshots ({}) [dry run: {} -> keep {}, remove {}] ",
+                              app.snapshots.len(),
+                              plan.policy.summary(),
+                              plan.kept.len(),
+                              plan.removed.len()),
+        None => format!(" Snapshots ({}) ", app.snapshots.len()),
+    };
     let block = Block::default().title(title)
                                 .borders(Borders::ALL)
                                 .border_style(border_style);
@@ -109,25 +206,45 @@ fn render_snapshots(frame: &mut Frame,
                let line = format!("{} {:8}  {}  {:16}  {:8}  {}",
                                   prefix,
                                   snapshot.display_id(),
-                                  snapshot.formatted_time(),
+                                  snapshot.formatted_time(&app.time_format),
                                   snapshot.hostname,
                                   snapshot.username,
                                   tags_str);
 
                let style = if is_selected && focused
                {
-                   Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+                   theme.selected
                }
                else if is_selected
                {
-                   Style::default().fg(Color::White)
+                   theme.selected_inactive
                }
                else
                {
-                   Style::default().fg(Color::Gray)
+                   theme.text
                };
 
-               ListItem::new(line).style(style)
+               // Policy simulation: mark kept/removed snapshots and why they're kept
+               let plan = match &app.forget_plan
+               {
+                   Some(plan) => plan,
+                   None => return ListItem::new(line).style(style),
+               };
+               let mut spans = vec![Span::styled(line, style)];
+               if let Some(reasons) = plan.kept.get(&snapshot.full_id)
+               {
+                   spans.push(Span::styled("  KEEP", theme.success));
+                   if !reasons.is_empty()
+                   {
+                       spans.push(Span::styled(format!(" ({})", reasons.join(", ")),
+                                               theme.muted));
+                   }
+               }
+               else if plan.removed.contains(&snapshot.full_id)
+               {
+                   spans.push(Span::styled("  REMOVE", theme.error));
+               }
+               ListItem::new(Line::from(spans))
            })
            .collect();
 
@@ -140,17 +257,18 @@ fn render_files(frame: &mut Frame,
                 app: &mut App,
                 area: Rect)
 {
+    let theme = app.theme.clone();
     let focused = app.focused_panel == Panel::Files;
     let is_searching = app.state == AppState::FileSearch;
     let has_filter = !app.search_query.is_empty();
 
     let border_style = if focused || is_searching
     {
-        Style::default().fg(Color::Cyan)
+        theme.border_focused
     }
     else
     {
-        Style::default().fg(Color::DarkGray)
+        theme.border
     };
 
     // Split area for search bar if searching
@@ -174,6 +292,25 @@ fn render_files(frame: &mut Frame,
         render_search_bar(frame, app, search_area, is_searching);
     }
 
+    // Columns layout: parent | current directory | preview
+    let columns = app.files_layout == FilesLayout::Columns && app.current_snapshot_id.is_some();
+    let list_area = if columns
+    {
+        let chunks = Layout::horizontal([
+            Constraint::Percentage(20), // Parent directory
+            Constraint::Percentage(40), // Current directory
+            Constraint::Percentage(40), // Preview
+        ])
+        .split(list_area);
+        render_parent_column(frame, app, chunks[0]);
+        render_preview_column(frame, app, chunks[2]);
+        chunks[1]
+    }
+    else
+    {
+        list_area
+    };
+
     // Calculate visible height
     let visible_height = list_area.height.saturating_sub(2) as usize;
 
@@ -182,13 +319,15 @@ fn render_files(frame: &mut Frame,
 
     // Adjust scroll to keep cursor visible
     app.adjust_scroll(Panel::Files, visible_height);
+    app.mouse_areas.panels.push((Panel::Files,
+                                 ListArea::scrolled(list_area, app.file_scroll, visible_height, app.visible_file_count())));
 
     // Get visible files
     let visible_files = app.visible_files();
     let file_count = visible_files.len();
     let total_count = app.files.len();
 
-    let title = if app.current_path.is_empty()
+    let mut title = if app.current_path.is_empty()
     {
         if app.current_snapshot_id.is_some()
         {
@@ -208,6 +347,16 @@ fn render_files(frame: &mut Frame,
         format!(" {} [{} items] ", app.current_path, total_count)
     };
 
+    // Sort order and background size computation indicators
+    if app.current_snapshot_id.is_some()
+    {
+        title.push_str(&format!("[sort: {}] ", app.file_sort.label()));
+        if app.dir_sizes_pending()
+        {
+            title.push_str(&format!("{} sizing ", app.spinner_char()));
+        }
+    }
+
     let block = Block::default().title(title)
                                 .borders(Borders::ALL)
                                 .border_style(border_style);
@@ -236,11 +385,39 @@ fn render_files(frame: &mut Frame,
     if visible_files.is_empty()
     {
         let paragraph = Paragraph::new("  No matches found").block(block)
-            .style(Style::default().fg(Color::DarkGray));
+            .style(theme.muted);
         frame.render_widget(paragraph, list_area);
         return;
     }
 
+    // Tree layout: indentation guides and an expanded/collapsed marker per row
+    let tree_rows = if app.files_layout == FilesLayout::Tree { app.tree_rows() } else { Vec::new() };
+    let spinner = app.spinner_char();
+    let tree_indent = |i: usize, file: &FileNode| -> String {
+        let row = match tree_rows.get(i)
+        {
+            Some(r) => r,
+            None => return String::new(),
+        };
+        let marker = if !file.is_dir() || file.name == ".."
+        {
+            "  ".to_string()
+        }
+        else if !app.expanded.contains_key(&file.path)
+        {
+            "▸ ".to_string()
+        }
+        else if matches!(app.expanded.get(&file.path), Some(None))
+        {
+            format!("{} ", spinner)
+        }
+        else
+        {
+            "▾ ".to_string()
+        };
+        format!("{}{}", row.guides, marker)
+    };
+
     let items: Vec<ListItem> =
         visible_files
            .iter()
@@ -252,35 +429,75 @@ fn render_files(frame: &mut Frame,
                let prefix = if is_selected { ">" } else { " " };
 
                // Format: "> name                                 [DIR] or size"
+               let indent = tree_indent(i, file);
                let name_display = if file.is_dir() && file.name != ".."
                {
-                   format!("{}/", file.name)
+                   format!("{}{}/", indent, file.name)
                }
                else
                {
-                   file.name.clone()
+                   format!("{}{}", indent, file.name)
                };
 
-               let size_display = file.formatted_size();
+               // Directories show their recursive size once it is known
+               let dir_size = if file.is_dir() && file.name != ".."
+               {
+                   app.dir_size(&file.path)
+               }
+               else
+               {
+                   None
+               };
+               let (size_display, files_display) = match dir_size
+               {
+                   Some(d) => (d.formatted_size(), d.formatted_files()),
+                   None => (file.formatted_size(), String::new()),
+               };
 
-               let line = format!("{} {:<50} {:>10}", prefix, name_display, size_display);
+               // The middle column only has room for name and size
+               let line = if columns
+               {
+                   let name_width = (list_area.width as usize).saturating_sub(16);
+                   format!("{} {:<name_width$} {:>10}", prefix, name_display, size_display)
+               }
+               else
+               {
+                   format!("{} {:<50} {:>10} {:>12}  {:16}",
+                           prefix,
+                           name_display,
+                           size_display,
+                           files_display,
+                           file.formatted_mtime(&app.time_format))
+               };
 
                let style = if is_selected && (focused || is_searching)
                {
-                   Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+                   theme.selected
                }
                else if is_selected
                {
-                   Style::default().fg(Color::White)
+                   theme.selected_inactive
                }
                else if file.is_dir()
                {
-                   Style::default().fg(Color::Blue)
+                   theme.directory
+               }
+               else
+               {
+                   theme.text
+               };
+
+               // Highlight the characters the search matched
+               let matched = if has_filter && file.name != ".."
+               {
+                   app.search_match(&file.name).map(|m| m.positions).unwrap_or_default()
                }
                else
                {
-                   Style::default().fg(Color::Gray)
+                   Vec::new()
                };
+               let name_start = 2 + indent.chars().count();
+               let line = highlight_chars(line, matched.iter().map(|p| name_start + p), theme.search_match);
 
                ListItem::new(line).style(style)
            })
@@ -290,19 +507,203 @@ fn render_files(frame: &mut Frame,
     frame.render_widget(list, list_area);
 }
 
+/// Render the parent directory column of the columns layout
+fn render_parent_column(frame: &mut Frame,
+                        app: &App,
+                        area: Rect)
+{
+    let theme = &app.theme;
+    let title = match app.current_path.as_str()
+    {
+        "" => " Snapshot ".to_string(),
+        _ if app.nav_stack.last().is_some_and(|c| c.path.is_empty()) => " Paths ".to_string(),
+        path => format!(" {} ", parent_entry(path).path),
+    };
+    let block = Block::default().title(title)
+                                .borders(Borders::ALL)
+                                .border_style(theme.border);
+
+    let entries: Vec<&FileNode> = app.parent_listing()
+                                     .unwrap_or_default()
+                                     .iter()
+                                     .filter(|f| f.name != ".." && (app.show_hidden || !f.name.starts_with('.')))
+                                     .collect();
+
+    if entries.is_empty()
+    {
+        let text = match app.current_path.as_str()
+        {
+            "" => app.snapshots
+                     .get(app.snapshot_cursor)
+                     .map(|s| format!(" {} {}", s.short_id, s.hostname))
+                     .unwrap_or_default(),
+            _ => String::new(),
+        };
+        let paragraph = Paragraph::new(text).block(block).style(theme.muted);
+        frame.render_widget(paragraph, area);
+        return;
+    }
+
+    // Keep the current directory in view
+    let visible_height = area.height.saturating_sub(2) as usize;
+    let current = entries.iter().position(|f| f.path == app.current_path);
+    let scroll = current.map_or(0, |i| (i + 1).saturating_sub(visible_height));
+
+    let items: Vec<ListItem> =
+        entries.iter()
+               .enumerate()
+               .skip(scroll)
+               .take(visible_height)
+               .map(|(i, file)| {
+                   let style = if Some(i) == current
+                   {
+                       theme.selected_inactive
+                   }
+                   else if file.is_dir()
+                   {
+                       theme.directory
+                   }
+                   else
+                   {
+                       theme.text
+                   };
+                   ListItem::new(format!(" {}", entry_name(file))).style(style)
+               })
+               .collect();
+
+    frame.render_widget(List::new(items).block(block), area);
+}
+
+/// Render the preview column of the columns layout: the children of the
+/// directory under the cursor, or the start of the file
+fn render_preview_column(frame: &mut Frame,
+                         app: &App,
+                         area: Rect)
+{
+    let theme = &app.theme;
+    let block = Block::default().title(" Preview ")
+                                .borders(Borders::ALL)
+                                .border_style(theme.border);
+    let visible_height = area.height.saturating_sub(2) as usize;
+
+    let file = match app.file_at_cursor()
+    {
+        Some(f) if f.name != ".." => f,
+        _ =>
+        {
+            frame.render_widget(block, area);
+            return;
+        }
+    };
+
+    let preview = app.current_snapshot_id
+                     .as_ref()
+                     .and_then(|id| app.previews.get(&(id.clone(), file.path.clone())));
+
+    let message = |text: String, style| Paragraph::new(text).block(block.clone()).style(style);
+    let widget = match preview
+    {
+        _ if !file.is_dir() && file.node_type != "file" =>
+        {
+            message(format!("  {}", file.node_type), theme.muted)
+        }
+        None | Some(Preview::Loading) => message(format!("  {} Loading...", app.spinner_char()), theme.warning),
+        Some(Preview::Failed(e)) => message(format!("  {}", e), theme.error),
+        Some(Preview::Listing(children)) =>
+        {
+            let lines: Vec<Line> =
+                children.iter()
+                        .filter(|f| app.show_hidden || !f.name.starts_with('.'))
+                        .take(visible_height)
+                        .map(|f| {
+                            let style = if f.is_dir() { theme.directory } else { theme.text };
+                            Line::styled(format!(" {}", entry_name(f)), style)
+                        })
+                        .collect();
+            if lines.is_empty()
+            {
+                message("  Empty directory".to_string(), theme.muted)
+            }
+            else
+            {
+                Paragraph::new(lines).block(block.clone())
+            }
+        }
+        Some(Preview::Content(content)) => match text_lines(content)
+        {
+            Some(lines) if lines.is_empty() => message("  Empty file".to_string(), theme.muted),
+            Some(lines) =>
+            {
+                let lines: Vec<Line> = lines.into_iter()
+                                            .take(visible_height)
+                                            .map(|l| Line::styled(l, theme.text))
+                                            .collect();
+                Paragraph::new(lines).block(block.clone())
+            }
+            None => message(format!("  Binary file, {}", file.formatted_size()), theme.muted),
+        },
+    };
+    frame.render_widget(widget, area);
+}
+
+/// Split a line into spans, styling the characters at the given (ascending) indices
+fn highlight_chars(line: String,
+                   positions: impl Iterator<Item = usize>,
+                   style: Style)
+                   -> Line<'static>
+{
+    let mut positions = positions.peekable();
+    if positions.peek().is_none()
+    {
+        return Line::from(line);
+    }
+
+    let mut spans = Vec::new();
+    let mut run = String::new();
+    let mut run_matched = false;
+    for (i, c) in line.chars().enumerate()
+    {
+        let matched = positions.next_if_eq(&i).is_some();
+        if matched != run_matched && !run.is_empty()
+        {
+            let text = std::mem::take(&mut run);
+            spans.push(if run_matched { Span::styled(text, style) } else { Span::raw(text) });
+        }
+        run_matched = matched;
+        run.push(c);
+    }
+    spans.push(if run_matched { Span::styled(run, style) } else { Span::raw(run) });
+
+    Line::from(spans)
+}
+
+/// Entry name with a trailing slash for directories
+fn entry_name(file: &FileNode) -> String
+{
+    if file.is_dir()
+    {
+        format!("{}/", file.name)
+    }
+    else
+    {
+        file.name.clone()
+    }
+}
+
 /// Render the command log panel
 fn render_command_log(frame: &mut Frame,
                       app: &mut App,
                       area: Rect)
 {
+    let theme = app.theme.clone();
     let focused = app.focused_panel == Panel::CommandLog;
     let border_style = if focused
     {
-        Style::default().fg(Color::Cyan)
+        theme.border_focused
     }
     else
     {
-        Style::default().fg(Color::DarkGray)
+        theme.border
     };
 
     // Calculate visible height (area height minus borders)
@@ -319,9 +720,10 @@ fn render_command_log(frame: &mut Frame,
 
     if app.command_logs.is_empty()
     {
+        app.mouse_areas.panels.push((Panel::CommandLog, ListArea::new(area, Vec::new())));
         let paragraph = Paragraph::new("  No commands executed yet")
             .block(block)
-            .style(Style::default().fg(Color::DarkGray));
+            .style(theme.muted);
         frame.render_widget(paragraph, area);
         return;
     }
@@ -340,7 +742,7 @@ fn render_command_log(frame: &mut Frame,
         // Format header: "> [HH:MM:SS] [OK/FAIL] "
         let time_str = entry.timestamp.format("%H:%M:%S");
         let status = if entry.success { "OK" } else { "FAIL" };
-        let status_color = if entry.success { Color::Green } else { Color::Red };
+        let status_style = if entry.success { theme.log_success } else { theme.log_failure };
 
         let header = format!("{} [{}] [{:4}] ", prefix, time_str, status);
         let header_len = header.len();
@@ -350,19 +752,23 @@ fn render_command_log(frame: &mut Frame,
 
         let style = if is_selected && focused
         {
-            Style::default().add_modifier(Modifier::BOLD)
+            theme.selected
         }
         else if is_selected
         {
-            Style::default().fg(Color::White)
+            theme.selected_inactive
         }
         else
         {
-            Style::default().fg(Color::Gray)
+            theme.text
         };
 
-        // Wrap the command text
-        let command = &entry.command;
+        // Wrap the command text (long-running commands show how long they took)
+        let command = match entry.duration
+        {
+            Some(d) => format!("{} ({})", entry.command, format_duration(d)),
+            None => entry.command.clone(),
+        };
         let mut first_line = true;
 
         if !command.is_empty()
@@ -378,8 +784,8 @@ fn render_command_log(frame: &mut Frame,
                 {
                     lines.push(Line::from(vec![
                         Span::raw(format!("{} [{}] ", prefix, time_str)),
-                        Span::styled(format!("[{:4}] ", status), Style::default().fg(status_color)),
-                        Span::styled(chunk, style),
+                        Span::styled(format!("[{:4}] ", status), status_style),
+                        Span::styled(chunk.to_string(), style),
                     ]));
                     first_line = false;
                 }
@@ -398,7 +804,7 @@ fn render_command_log(frame: &mut Frame,
             // No command, just show header
             lines.push(Line::from(vec![
                 Span::raw(format!("{} [{}] ", prefix, time_str)),
-                Span::styled(format!("[{:4}] ", status), Style::default().fg(status_color)),
+                Span::styled(format!("[{:4}] ", status), status_style),
             ]));
         }
 
@@ -411,7 +817,7 @@ fn render_command_log(frame: &mut Frame,
                 for err_line in err.lines().take(3)
                 {
                     lines.push(Line::from(vec![
-                        Span::styled(format!("{}{}", indent, err_line), Style::default().fg(Color::Red)),
+                        Span::styled(format!("{}{}", indent, err_line), theme.log_failure),
                     ]));
                 }
             }
@@ -450,6 +856,12 @@ fn render_command_log(frame: &mut Frame,
         }
     }
 
+    // Entry shown on each visible line, for mouse clicks
+    let rows = (app.log_scroll..total_lines.min(app.log_scroll + visible_height))
+        .map(|line| entry_start_lines.partition_point(|&start| start <= line) - 1)
+        .collect();
+    app.mouse_areas.panels.push((Panel::CommandLog, ListArea::new(area, rows)));
+
     // Use Paragraph with scroll
     let paragraph = Paragraph::new(lines)
         .block(block)
@@ -457,29 +869,200 @@ fn render_command_log(frame: &mut Frame,
     frame.render_widget(paragraph, area);
 }
 
+/// Render the jobs panel
+fn render_jobs_panel(frame: &mut Frame,
+                     app: &mut App,
+                     area: Rect)
+{
+    let theme = app.theme.clone();
+    let focused = app.focused_panel == Panel::Jobs;
+    let border_style = if focused
+    {
+        theme.border_focused
+    }
+    else
+    {
+        theme.border
+    };
+
+    let visible_height = area.height.saturating_sub(2) as usize;
+    app.job_visible_height = visible_height;
+    app.adjust_scroll(Panel::Jobs, visible_height);
+    app.mouse_areas.panels.push((Panel::Jobs,
+                                 ListArea::scrolled(area, app.job_scroll, visible_height, app.jobs.len())));
+
+    let running = app.jobs.iter().filter(|j| j.status == JobStatus::Running).count();
+    let title = format!(" Jobs ({} running, max {}) ", running, app.max_jobs);
+    let block = Block::default().title(title)
+                                .borders(Borders::ALL)
+                                .border_style(border_style);
+
+    let items: Vec<ListItem> =
+        app.jobs
+           .iter()
+           .enumerate()
+           .skip(app.job_scroll)
+           .take(visible_height)
+           .map(|(i, job)| {
+               let is_selected = focused && i == app.job_cursor;
+               let prefix = if is_selected { ">" } else { " " };
+               let status_style = match job.status
+               {
+                   JobStatus::Queued => theme.muted,
+                   JobStatus::Running => theme.warning,
+                   JobStatus::Done => theme.success,
+                   JobStatus::Failed(_) => theme.error,
+               };
+               let style = if is_selected
+               {
+                   theme.selected
+               }
+               else
+               {
+                   theme.text
+               };
+
+               let mut spans = vec![
+                   Span::styled(format!("{} #{:<3} {:<7} ", prefix, job.id, job.kind.verb()), style),
+                   Span::styled(format!("[{}] ", job.status_text()), status_style),
+                   Span::styled(job.kind.description(), style),
+               ];
+               if let JobStatus::Failed(ref e) = job.status
+               {
+                   spans.push(Span::styled(format!("  {}", e.lines().next().unwrap_or("")),
+                                           theme.error));
+               }
+               ListItem::new(Line::from(spans))
+           })
+           .collect();
+
+    let list = List::new(items).block(block);
+    frame.render_widget(list, area);
+}
+
+/// Render the repository check results panel
+fn render_check_panel(frame: &mut Frame,
+                      app: &mut App,
+                      area: Rect)
+{
+    let theme = app.theme.clone();
+    let focused = app.focused_panel == Panel::Check;
+    let border_style = if focused
+    {
+        theme.border_focused
+    }
+    else
+    {
+        theme.border
+    };
+
+    let visible_height = area.height.saturating_sub(2) as usize;
+    app.check_visible_height = visible_height;
+    app.adjust_scroll(Panel::Check, visible_height);
+
+    let spinner = app.spinner_char();
+    let check = match &app.check
+    {
+        Some(c) => c,
+        None => return,
+    };
+    app.mouse_areas.panels.push((Panel::Check,
+                                 ListArea::scrolled(area, check.scroll, visible_height, check.output.len())));
+
+    let (summary_style, summary) = match &check.status
+    {
+        CheckStatus::Running => (theme.warning, format!("{} {}", spinner, check.summary())),
+        CheckStatus::Clean => (theme.success, check.summary()),
+        CheckStatus::ErrorsFound | CheckStatus::Failed(_) => (theme.error, check.summary()),
+    };
+    let subset = match &check.read_data_subset
+    {
+        Some(subset) => format!(" --read-data-subset={}", subset),
+        None => String::new(),
+    };
+
+    let block = Block::default().title(Line::from(vec![
+                                    Span::raw(format!(" Check{} [", subset)),
+                                    Span::styled(summary, summary_style),
+                                    Span::raw("] "),
+                                ]))
+                                .borders(Borders::ALL)
+                                .border_style(border_style);
+
+    if check.output.is_empty()
+    {
+        let paragraph = Paragraph::new("  Waiting for output...")
+            .block(block)
+            .style(theme.muted);
+        frame.render_widget(paragraph, area);
+        return;
+    }
+
+    let items: Vec<ListItem> =
+        check.output
+             .iter()
+             .enumerate()
+             .skip(check.scroll)
+             .take(visible_height)
+             .map(|(i, line)| {
+                 let is_selected = focused && i == check.cursor;
+                 let prefix = if is_selected { ">" } else { " " };
+                 let style = if is_error_line(line)
+                 {
+                     theme.error
+                 }
+                 else if is_selected
+                 {
+                     theme.selected_inactive
+                 }
+                 else
+                 {
+                     theme.text
+                 };
+                 ListItem::new(format!("{} {}", prefix, line)).style(style)
+             })
+             .collect();
+
+    let list = List::new(items).block(block);
+    frame.render_widget(list, area);
+}
+
 /// Render the search bar
 fn render_search_bar(frame: &mut Frame,
                      app: &App,
                      area: Rect,
                      is_active: bool)
 {
+    let theme = &app.theme;
     let style = if is_active
     {
-        Style::default().fg(Color::Yellow)
+        theme.input_focused
     }
     else
     {
-        Style::default().fg(Color::DarkGray)
+        theme.muted
     };
 
-    let search_text = format!("/{}",  app.search_query);
-    let paragraph = Paragraph::new(search_text).style(style);
-    frame.render_widget(paragraph, area);
+    // Non-default modes are shown before the query, e.g. "[glob] /*.sql.gz"
+    let mode = match app.search_mode
+    {
+        SearchMode::Fuzzy => String::new(),
+        mode => format!("[{}] ", mode.label()),
+    };
+    let mut spans = vec![
+        Span::styled(mode.clone(), theme.label),
+        Span::styled(format!("/{}", app.search_query), style),
+    ];
+    if let (Some(e), false) = (&app.search_error, app.search_query.is_empty())
+    {
+        spans.push(Span::styled(format!("  {}", e), theme.error));
+    }
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
 
     // Show cursor if actively searching
     if is_active
     {
-        frame.set_cursor_position((area.x + 1 + app.search_cursor as u16, area.y));
+        frame.set_cursor_position((area.x + (mode.len() + 1 + app.search_query[..app.search_cursor].chars().count()) as u16, area.y));
     }
 }
 
@@ -488,6 +1071,7 @@ fn render_status_bar(frame: &mut Frame,
                      app: &App,
                      area: Rect)
 {
+    let theme = &app.theme;
     let spinner = app.spinner_char();
 
     let status_text = if let Some(ref msg) = app.status_message
@@ -499,24 +1083,52 @@ fn render_status_bar(frame: &mut Frame,
         match &app.state
         {
             AppState::Loading => format!("{} Loading...", spinner),
-            AppState::Downloading(path) => format!("{} Downloading: {}", spinner, path),
             AppState::FileSearch => "[Enter]confirm  [Esc]clear  [↑↓]navigate".to_string(),
+            AppState::DeepSearch => "[Enter]go to  [Tab]mode  [↑↓]select  [Esc]close".to_string(),
+            AppState::Diff => "[↑↓/PgUp/PgDn]scroll  [n]/[N]next/previous hunk  [Esc]back".to_string(),
+            AppState::Compare if app.compare.as_ref().is_some_and(|c| c.local.is_some()) =>
+            {
+                "[Enter]open/diff  [Bksp]up  [c]changes only  [<]restore entry  [R]restore all differences  [r]eload  [Esc]close".to_string()
+            }
+            AppState::Compare => "[Enter]open/diff  [Bksp]up  [c]changes only  [<]/[>]restore left/right  [Esc]close".to_string(),
+            AppState::GoToPath => "[Tab]complete  [Enter]go  [Ctrl-W]delete component  [Ctrl-U]clear  [Esc]cancel".to_string(),
+            AppState::Bookmarks => "[Enter]open  [c]open in selected snapshot  [d]delete  [Esc]close".to_string(),
             AppState::DownloadDialog => "[Tab]switch  [↑↓]select  [Enter]open/confirm  [Esc]cancel".to_string(),
             AppState::Error(e) => format!("Error: {}", e),
             AppState::Help => "Press q or ? to close help".to_string(),
+            AppState::Stats => "[r]efresh repository  [Esc/s]close".to_string(),
+            AppState::CheckPrompt => "[Enter]start check  [Esc]cancel".to_string(),
+            AppState::ForgetDialog => "[Tab/↑↓]field  [Enter]simulate (dry run)  [Esc]cancel".to_string(),
             AppState::Ready =>
             {
-                "[↑↓/jk]move  [Tab]panel  [Enter]open  [Backspace]back  [d]download  [?]help  [q]uit"
-                    .to_string()
+                let running = app.jobs.iter().filter(|j| j.status == JobStatus::Running).count();
+                let keymap = &app.keymap;
+                let keys = format!("[{}{}]move  [{}]panel  [{}]open  [{}]back  [{}]download  [{}]help  [{}]quit",
+                                   keymap.first_key(Action::Up),
+                                   keymap.first_key(Action::Down),
+                                   keymap.first_key(Action::SwitchPanel),
+                                   keymap.first_key(Action::Select),
+                                   keymap.first_key(Action::Back),
+                                   keymap.first_key(Action::Download),
+                                   keymap.first_key(Action::Help),
+                                   keymap.first_key(Action::Quit));
+                if running > 0
+                {
+                    format!("{} {} job(s) running  {}", spinner, running, keys)
+                }
+                else
+                {
+                    keys
+                }
             }
         }
     };
 
     let style = match &app.state
     {
-        AppState::Error(_) => Style::default().fg(Color::Red),
-        AppState::Loading | AppState::Downloading(_) => Style::default().fg(Color::Yellow),
-        _ => Style::default().fg(Color::DarkGray),
+        AppState::Error(_) => theme.error,
+        AppState::Loading => theme.warning,
+        _ => theme.muted,
     };
 
     let paragraph = Paragraph::new(status_text).style(style);
@@ -527,6 +1139,7 @@ fn render_status_bar(frame: &mut Frame,
 fn render_loading_overlay(frame: &mut Frame,
                           app: &App)
 {
+    let theme = &app.theme;
     let area = centered_rect(40, 20, frame.area());
 
     frame.render_widget(Clear, area);
@@ -535,23 +1148,15 @@ fn render_loading_overlay(frame: &mut Frame,
     let message = match &app.state
     {
         AppState::Loading => format!("{}  Loading...", spinner),
-        AppState::Downloading(path) =>
-        {
-            let filename = std::path::Path::new(path)
-                .file_name()
-                .map(|s| s.to_string_lossy().to_string())
-                .unwrap_or_else(|| path.clone());
-            format!("{}  Downloading: {}", spinner, filename)
-        }
         _ => return,
     };
 
     let block = Block::default().borders(Borders::ALL)
-                                .border_style(Style::default().fg(Color::Yellow));
+                                .border_style(theme.warning);
 
     let text = vec![
         Line::from(""),
-        Line::from(Span::styled(message, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))),
+        Line::from(Span::styled(message, theme.warning.add_modifier(Modifier::BOLD))),
         Line::from(""),
     ];
 
@@ -561,112 +1166,819 @@ fn render_loading_overlay(frame: &mut Frame,
 }
 
 /// Render help overlay
-fn render_help_overlay(frame: &mut Frame)
+fn render_help_overlay(frame: &mut Frame,
+                       app: &App)
 {
+    let theme = &app.theme;
     let area = centered_rect(60, 70, frame.area());
 
     // Clear the area first
     frame.render_widget(Clear, area);
 
-    let help_text = vec![
+    // Key column is as wide as the longest binding list
+    let keys: Vec<Vec<(String, &str)>> = SECTIONS
+        .iter()
+        .map(|section| {
+            section.iter()
+                   .map(|action| (app.keymap.keys(*action).join(" / "), action.description()))
+                   .filter(|(k, _)| !k.is_empty())
+                   .collect()
+        })
+        .collect();
+    let width = keys.iter().flatten().map(|(k, _)| k.chars().count()).max().unwrap_or(0) + 1;
+
+    let mut help_text = vec![
         Line::from(vec![
-            Span::styled("Keyboard Controls", Style::default().add_modifier(Modifier::BOLD)),
+            Span::styled("Keyboard Controls", theme.emphasis),
         ]),
+    ];
+    for section in keys
+    {
+        help_text.push(Line::from(""));
+        for (key, description) in section
+        {
+            help_text.push(Line::from(vec![
+                Span::styled(format!("  {:<width$}", key, width = width), theme.label),
+                Span::raw(description),
+            ]));
+        }
+    }
+    help_text.extend([
         Line::from(""),
         Line::from(vec![
-            Span::styled("  ↑ / k    ", Style::default().fg(Color::Cyan)),
-            Span::raw("Move cursor up"),
-        ]),
-        Line::from(vec![
-            Span::styled("  ↓ / j    ", Style::default().fg(Color::Cyan)),
-            Span::raw("Move cursor down"),
-        ]),
-        Line::from(vec![
-            Span::styled("  Ctrl-F   ", Style::default().fg(Color::Cyan)),
-            Span::raw("Page down (full screen)"),
-        ]),
-        Line::from(vec![
-            Span::styled("  Ctrl-B   ", Style::default().fg(Color::Cyan)),
-            Span::raw("Page up (full screen)"),
-        ]),
-        Line::from(vec![
-            Span::styled("  Ctrl-D   ", Style::default().fg(Color::Cyan)),
-            Span::raw("Scroll down (half screen)"),
-        ]),
-        Line::from(vec![
-            Span::styled("  Ctrl-U   ", Style::default().fg(Color::Cyan)),
-            Span::raw("Scroll up (half screen)"),
-        ]),
-        Line::from(vec![
-            Span::styled("  g / Home ", Style::default().fg(Color::Cyan)),
-            Span::raw("Go to first item"),
-        ]),
-        Line::from(vec![
-            Span::styled("  G / End  ", Style::default().fg(Color::Cyan)),
-            Span::raw("Go to last item"),
+            Span::styled("Command Log:", theme.heading),
         ]),
+        Line::from("  Shows restic commands with OK/FAIL status"),
+        Line::from("  Auto-scrolls when at bottom; scroll to review history"),
         Line::from(""),
         Line::from(vec![
-            Span::styled("  Tab      ", Style::default().fg(Color::Cyan)),
-            Span::raw("Switch panel (Snapshots→Files→Log)"),
-        ]),
-        Line::from(vec![
-            Span::styled("  Enter    ", Style::default().fg(Color::Cyan)),
-            Span::raw("Open directory / Select snapshot"),
-        ]),
-        Line::from(vec![
-            Span::styled("  Bksp/h   ", Style::default().fg(Color::Cyan)),
-            Span::raw("Go to parent directory"),
+            Span::styled("Search Mode:", theme.heading),
         ]),
+        Line::from("  Type to filter (smart case), Tab=fuzzy/substring/glob/regex"),
+        Line::from("  Enter=confirm, Esc=clear"),
         Line::from(""),
         Line::from(vec![
-            Span::styled("  /        ", Style::default().fg(Color::Cyan)),
-            Span::raw("Search/filter files (in Files panel)"),
-        ]),
-        Line::from(vec![
-            Span::styled("  d        ", Style::default().fg(Color::Cyan)),
-            Span::raw("Download selected file/folder"),
-        ]),
-        Line::from(vec![
-            Span::styled("  ?        ", Style::default().fg(Color::Cyan)),
-            Span::raw("Toggle this help"),
+            Span::styled("Deep Search:", theme.heading),
         ]),
+        Line::from("  Type to match names below the current directory, Tab=mode"),
+        Line::from("  ↑↓=select, Enter=go to the match, Esc=close (keeps searching)"),
+        Line::from(""),
         Line::from(vec![
-            Span::styled("  q / Esc  ", Style::default().fg(Color::Cyan)),
-            Span::raw("Quit"),
+            Span::styled("Go to Path:", theme.heading),
         ]),
+        Line::from("  Tab=complete, Ctrl-W=delete component, Enter=go, Esc=cancel"),
         Line::from(""),
         Line::from(vec![
-            Span::styled("Command Log:", Style::default().fg(Color::Yellow)),
+            Span::styled("Compare:", theme.heading),
         ]),
-        Line::from("  Shows restic commands with OK/FAIL status"),
-        Line::from("  Auto-scrolls when at bottom; scroll to review history"),
+        Line::from("  Enter=open dir or diff file, Backspace=up, c=changes only"),
+        Line::from("  </>=restore the left/right version, Esc=close"),
+        Line::from("  Diff: n/N=next/previous hunk, Esc=back"),
+        Line::from("  Local (L): <=restore entry in place, R=restore all differences, r=reload"),
         Line::from(""),
         Line::from(vec![
-            Span::styled("Search Mode:", Style::default().fg(Color::Yellow)),
+            Span::styled("Bookmarks:", theme.heading),
         ]),
-        Line::from("  Type to filter, Enter=confirm, Esc=clear"),
+        Line::from("  Enter=open in the pinned/latest snapshot, c=open in the selected one"),
+        Line::from("  d=delete, Esc=close"),
         Line::from(""),
         Line::from(vec![
-            Span::styled("Download Dialog:", Style::default().fg(Color::Yellow)),
+            Span::styled("Download Dialog:", theme.heading),
         ]),
         Line::from("  Tab/Shift+Tab=switch focus  Esc=cancel"),
         Line::from("  Path picker: type, ↑↓=select, Enter=open"),
         Line::from("  On button: Enter=activate"),
-    ];
+    ]);
 
     let block = Block::default().title(" Help ")
                                 .borders(Borders::ALL)
-                                .border_style(Style::default().fg(Color::Cyan));
+                                .border_style(theme.dialog_border);
 
     let paragraph = Paragraph::new(help_text).block(block);
     frame.render_widget(paragraph, area);
 }
 
+/// Render the retention policy dialog
+fn render_forget_dialog(frame: &mut Frame,
+                        app: &mut App)
+{
+    let theme = app.theme.clone();
+    let dialog = match &app.forget_dialog
+    {
+        Some(d) => d,
+        None => return,
+    };
+
+    let area = centered_rect(50, 50, frame.area());
+    frame.render_widget(Clear, area);
+
+    let block = Block::default().title(" Simulate Retention Policy (dry run) ")
+                                .borders(Borders::ALL)
+                                .border_style(theme.dialog_border);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    // One row per field, below the blank first line
+    app.mouse_areas.forget_fields = Some(Rect {
+        x: inner.x,
+        y: inner.y + 1,
+        width: inner.width,
+        height: POLICY_FIELDS.len() as u16,
+    }.intersection(inner));
+
+    let mut lines: Vec<Line> = vec![Line::from("")];
+    for (i, (label, value)) in POLICY_FIELDS.iter().zip(dialog.values.iter()).enumerate()
+    {
+        let focused = i == dialog.focus;
+        let prefix = if focused { ">" } else { " " };
+        let value_style = if focused
+        {
+            theme.selected
+        }
+        else
+        {
+            theme.input
+        };
+        lines.push(Line::from(vec![
+            Span::raw(format!("{} ", prefix)),
+            Span::styled(format!("{:<14}", format!("{}:", label)), theme.label),
+            Span::styled(value.clone(), value_style),
+        ]));
+    }
+
+    lines.push(Line::from(""));
+    match &dialog.error
+    {
+        Some(e) => lines.push(Line::from(Span::styled(format!("  {}", e), theme.error))),
+        None => lines.push(Line::from(Span::styled("  Counts are numbers, within is e.g. 1y6m2d",
+                                                   theme.muted))),
+    }
+
+    let paragraph = Paragraph::new(lines);
+    frame.render_widget(paragraph, inner);
+
+    // Cursor at the end of the focused value (prefix 2 + label 14)
+    let value_len = dialog.values[dialog.focus].len() as u16;
+    frame.set_cursor_position((inner.x + 16 + value_len, inner.y + 1 + dialog.focus as u16));
+}
+
+/// Render the prompt asking for the --read-data-subset value
+fn render_check_prompt(frame: &mut Frame,
+                       app: &App)
+{
+    let theme = &app.theme;
+    let area = centered_rect(50, 20, frame.area());
+    frame.render_widget(Clear, area);
+
+    let block = Block::default().title(" Check Repository ")
+                                .borders(Borders::ALL)
+                                .border_style(theme.dialog_border);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let label = "  Read data subset: ";
+    let lines = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::styled(label, theme.label),
+            Span::styled(app.check_subset_input.clone(),
+                         theme.selected),
+        ]),
+        Line::from(""),
+        Line::from(Span::styled("  e.g. 5% or 1/10 or 500M; empty checks metadata only",
+                                theme.muted)),
+    ];
+    frame.render_widget(Paragraph::new(lines), inner);
+
+    frame.set_cursor_position((inner.x + (label.len() + app.check_subset_input.len()) as u16, inner.y + 1));
+}
+
+/// Render the go-to-path prompt with completion candidates or errors below
+fn render_goto_prompt(frame: &mut Frame,
+                      app: &App)
+{
+    let prompt = match app.goto_prompt
+    {
+        Some(ref p) => p,
+        None => return,
+    };
+    let theme = &app.theme;
+    let area = centered_rect(70, 40, frame.area());
+    frame.render_widget(Clear, area);
+
+    let block = Block::default().title(" Go to Path ")
+                                .borders(Borders::ALL)
+                                .border_style(theme.dialog_border);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let label = "  Path: ";
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::styled(label, theme.label),
+            Span::styled(prompt.input.clone(), theme.selected),
+        ]),
+        Line::from(""),
+    ];
+    if let Some(ref e) = prompt.error
+    {
+        lines.push(Line::from(Span::styled(format!("  {}", e), theme.error)));
+    }
+    else if prompt.waiting.is_some()
+    {
+        lines.push(Line::from(Span::styled(format!("  {} Listing...", app.spinner_char()), theme.warning)));
+    }
+    else if !prompt.candidates.is_empty()
+    {
+        lines.push(Line::from(Span::styled(format!("  {}", prompt.candidates.join("  ")), theme.text)));
+    }
+    else
+    {
+        lines.push(Line::from(Span::styled("  Tab completes names from the snapshot", theme.muted)));
+    }
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+
+    let column = label.len() + prompt.input[..prompt.cursor].chars().count();
+    frame.set_cursor_position((inner.x + column as u16, inner.y + 1));
+}
+
+/// Render the stats view for the selected snapshot and the repository
+fn render_stats_overlay(frame: &mut Frame,
+                        app: &App)
+{
+    let theme = &app.theme;
+    let area = centered_rect(60, 60, frame.area());
+    frame.render_widget(Clear, area);
+
+    let spinner = app.spinner_char();
+    let mut lines: Vec<Line> = Vec::new();
+
+    if let Some(ref id) = app.stats_snapshot_id
+    {
+        let heading = match app.snapshots.iter().find(|s| &s.full_id == id)
+        {
+            Some(s) => format!("Snapshot {}  ({}, {})", s.display_id(), s.formatted_time(&app.time_format), s.hostname),
+            None => format!("Snapshot {}", id),
+        };
+        lines.push(Line::from(Span::styled(heading, theme.emphasis)));
+        stats_lines(&mut lines, app.stats_cache.get(&StatsTarget::Snapshot(id.clone())), spinner, theme);
+        lines.push(Line::from(""));
+    }
+
+    lines.push(Line::from(Span::styled("Repository (all snapshots)",
+                                       theme.emphasis)));
+    stats_lines(&mut lines, app.stats_cache.get(&StatsTarget::Repository), spinner, theme);
+
+    let block = Block::default().title(" Stats ")
+                                .borders(Borders::ALL)
+                                .border_style(theme.dialog_border);
+
+    let paragraph = Paragraph::new(lines).block(block);
+    frame.render_widget(paragraph, area);
+}
+
+/// Render the deep search: query, progress and matches with their relative paths
+fn render_deep_search(frame: &mut Frame,
+                      app: &mut App)
+{
+    let theme = app.theme.clone();
+    let spinner = app.spinner_char();
+    let area = centered_rect(80, 70, frame.area());
+    frame.render_widget(Clear, area);
+
+    let search = match app.deep_search.as_mut()
+    {
+        Some(s) => s,
+        None => return,
+    };
+
+    let title = if search.root.is_empty()
+    {
+        " Deep search: all paths ".to_string()
+    }
+    else
+    {
+        format!(" Deep search: {} ", search.root)
+    };
+    let block = Block::default().title(title)
+                                .borders(Borders::ALL)
+                                .border_style(theme.dialog_border);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::vertical([
+        Constraint::Length(1), // Query
+        Constraint::Length(1), // Progress
+        Constraint::Min(3),    // Matches
+    ])
+    .split(inner);
+
+    // Query, with the mode as in the file filter's search bar
+    let mode = match search.mode
+    {
+        SearchMode::Fuzzy => String::new(),
+        mode => format!("[{}] ", mode.label()),
+    };
+    let mut spans = vec![
+        Span::styled(mode.clone(), theme.label),
+        Span::styled(format!("/{}", search.query), theme.input_focused),
+    ];
+    if let (Some(e), false) = (&search.error, search.query.is_empty())
+    {
+        spans.push(Span::styled(format!("  {}", e), theme.error));
+    }
+    frame.render_widget(Paragraph::new(Line::from(spans)), chunks[0]);
+    let cursor_x = mode.len() + 1 + search.query[..search.query_cursor].chars().count();
+    frame.set_cursor_position((chunks[0].x + cursor_x as u16, chunks[0].y));
+
+    let counts = format!("{} matches in {} entries", search.results.len(), search.entries.len());
+    let progress = match search.status
+    {
+        DeepSearchStatus::Running => Span::styled(format!("{} Searching... {}", spinner, counts), theme.warning),
+        DeepSearchStatus::Done => Span::styled(format!("{} ({})", counts, format_duration(search.elapsed.unwrap_or_default())),
+                                               theme.muted),
+        DeepSearchStatus::Failed(ref e) => Span::styled(e.lines().next().unwrap_or("").to_string(), theme.error),
+    };
+    frame.render_widget(Paragraph::new(Line::from(progress)), chunks[1]);
+
+    let list_area = chunks[2];
+    let block = Block::default().borders(Borders::ALL)
+                                .border_style(theme.border_focused);
+    let visible_height = list_area.height.saturating_sub(2) as usize;
+    search.visible_height = visible_height;
+    search.adjust_scroll(visible_height);
+    app.mouse_areas.deep_search = Some(ListArea::scrolled(list_area, search.scroll, visible_height, search.results.len()));
+
+    if search.results.is_empty()
+    {
+        let message = if search.query.is_empty()
+        {
+            "  Type to search every file and directory below".to_string()
+        }
+        else if search.is_running()
+        {
+            "  No matches yet".to_string()
+        }
+        else
+        {
+            "  No matches found".to_string()
+        };
+        frame.render_widget(Paragraph::new(message).block(block).style(theme.muted), list_area);
+        return;
+    }
+
+    let items: Vec<ListItem> =
+        search.results
+              .iter()
+              .enumerate()
+              .skip(search.scroll)
+              .take(visible_height)
+              .map(|(i, &(_, index))| {
+                  let node = &search.entries[index];
+                  let is_selected = i == search.cursor;
+                  let prefix = if is_selected { ">" } else { " " };
+                  let relative = search.relative_path(node);
+                  let suffix = if node.is_dir() { "/" } else { "" };
+                  let line = format!("{} {}{}", prefix, relative, suffix);
+
+                  // The query matched the name, which ends the relative path
+                  let name_start = 2 + relative.chars().count().saturating_sub(node.name.chars().count());
+                  let matched = search.find(node).map(|m| m.positions).unwrap_or_default();
+                  let line = highlight_chars(line, matched.iter().map(|p| name_start + p), theme.search_match);
+
+                  let style = if is_selected
+                  {
+                      theme.selected
+                  }
+                  else if node.is_dir()
+                  {
+                      theme.directory
+                  }
+                  else
+                  {
+                      theme.text
+                  };
+                  ListItem::new(line).style(style)
+              })
+              .collect();
+
+    frame.render_widget(List::new(items).block(block), list_area);
+}
+
+/// Render the bookmark picker with the snapshot each bookmark would open
+fn render_bookmarks(frame: &mut Frame,
+                    app: &mut App)
+{
+    let theme = app.theme.clone();
+    let area = centered_rect(70, 50, frame.area());
+    frame.render_widget(Clear, area);
+
+    let block = Block::default().title(format!(" Bookmarks ({}) ", app.bookmarks.len()))
+                                .borders(Borders::ALL)
+                                .border_style(theme.dialog_border);
+
+    let visible_height = area.height.saturating_sub(2) as usize;
+    if app.bookmark_cursor < app.bookmark_scroll
+    {
+        app.bookmark_scroll = app.bookmark_cursor;
+    }
+    else if app.bookmark_cursor >= app.bookmark_scroll + visible_height
+    {
+        app.bookmark_scroll = app.bookmark_cursor + 1 - visible_height;
+    }
+    app.mouse_areas.bookmarks = Some(ListArea::scrolled(area, app.bookmark_scroll, visible_height, app.bookmarks.len()));
+
+    if app.bookmarks.is_empty()
+    {
+        let message = format!("  No bookmarks yet. Press {} in the Files panel to bookmark a directory",
+                              app.keymap.first_key(Action::Bookmark));
+        frame.render_widget(Paragraph::new(message).block(block).style(theme.muted), area);
+        return;
+    }
+
+    let host_width = app.bookmarks.iter().map(|b| b.host.chars().count()).max().unwrap_or(0);
+    let path_width = app.bookmarks.iter().map(|b| b.path.chars().count()).max().unwrap_or(0);
+
+    let items: Vec<ListItem> =
+        app.bookmarks
+           .iter()
+           .enumerate()
+           .skip(app.bookmark_scroll)
+           .take(visible_height)
+           .map(|(i, bookmark)| {
+               let is_selected = i == app.bookmark_cursor;
+               let prefix = if is_selected { ">" } else { " " };
+
+               // Which snapshot Enter would open
+               let snapshot = bookmark.find_snapshot(&app.snapshots).map(|i| &app.snapshots[i]);
+               let target = match (snapshot, &bookmark.snapshot)
+               {
+                   (Some(s), Some(_)) => format!("pinned {}  {}", s.display_id(), s.formatted_time(&app.time_format)),
+                   (Some(s), None) => format!("latest {}  {}", s.display_id(), s.formatted_time(&app.time_format)),
+                   (None, Some(id)) => format!("pinned snapshot {} no longer exists", id.chars().take(8).collect::<String>()),
+                   (None, None) => "no snapshot contains this path".to_string(),
+               };
+
+               let line = format!("{} {:<host_width$}  {:<path_width$}  {}", prefix, bookmark.host, bookmark.path, target);
+               let style = if is_selected
+               {
+                   theme.selected
+               }
+               else if snapshot.is_none()
+               {
+                   theme.muted
+               }
+               else
+               {
+                   theme.text
+               };
+               ListItem::new(line).style(style)
+           })
+           .collect();
+
+    frame.render_widget(List::new(items).block(block), area);
+}
+
+/// Width of the column naming the change after each comparison row
+const CHANGE_LABEL_WIDTH: u16 = 16;
+
+/// Render the side-by-side comparison of a directory in two snapshots
+fn render_compare(frame: &mut Frame,
+                  app: &mut App)
+{
+    let theme = app.theme.clone();
+    let spinner = app.spinner_char();
+    let time_format = app.time_format.clone();
+    let area = centered_rect(90, 80, frame.area());
+    frame.render_widget(Clear, area);
+
+    let compare = match app.compare
+    {
+        Some(ref mut c) => c,
+        None => return,
+    };
+
+    let location = if compare.path.is_empty() { "paths root".to_string() } else { compare.path.clone() };
+    let title = if compare.is_loading()
+    {
+        format!(" Compare {} ", location)
+    }
+    else
+    {
+        format!(" Compare {} [{} changed{}] ", location, compare.change_count(), if compare.changes_only { ", changes only" } else { "" })
+    };
+    let block = Block::default().title(title)
+                                .borders(Borders::ALL)
+                                .border_style(theme.dialog_border);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    // Each side gets half the width, minus the separator and the change label column
+    let half = (inner.width.saturating_sub(1 + CHANGE_LABEL_WIDTH) / 2) as usize;
+    let separator = Span::styled("│", theme.border);
+
+    let snapshot_name = |snapshot_id: &str| -> String {
+        let snapshot = app.snapshots.iter().find(|s| s.full_id == snapshot_id);
+        snapshot.map(|s| format!("{}  {}", s.display_id(), s.formatted_time(&time_format)))
+                .unwrap_or_else(|| snapshot_id.chars().take(8).collect())
+    };
+    let right_name = match compare.local
+    {
+        Some(ref local) => format!("local {}", local.dir_for(&compare.path).display()),
+        None => snapshot_name(&compare.right_id),
+    };
+    let header = |name: String, listing: &Listing| -> Vec<Span<'static>> {
+        let (status, style) = match listing
+        {
+            Listing::Loading => (format!("{} Loading...", spinner), theme.warning),
+            Listing::Entries(_) => (String::new(), theme.muted),
+            Listing::Absent(reason) => (reason.lines().next().unwrap_or_default().to_string(), theme.error),
+        };
+        let name_width = (name.chars().count() + 1).min(half);
+        vec![Span::styled(fit(&format!(" {}", name), name_width), theme.heading),
+             Span::styled(fit(&format!(" {}", status), half - name_width), style)]
+    };
+    let mut header_spans = header(snapshot_name(&compare.left_id), &compare.left);
+    header_spans.push(separator.clone());
+    header_spans.extend(header(right_name, &compare.right));
+    frame.render_widget(Paragraph::new(Line::from(header_spans)),
+                        Rect { height: 1, ..inner });
+
+    let list_area = Rect { y: inner.y + 1, height: inner.height.saturating_sub(1), ..inner };
+    let visible_height = list_area.height as usize;
+    compare.visible_height = visible_height;
+    compare.adjust_scroll(visible_height);
+    // The header row takes the place of a list border for hit-testing
+    app.mouse_areas.compare = Some(ListArea::scrolled(Rect { height: list_area.height + 1, ..inner },
+                                                      compare.scroll,
+                                                      visible_height,
+                                                      compare.rows.len()));
+
+    if compare.rows.is_empty() && !compare.is_loading()
+    {
+        let message = if compare.changes_only { "  No differences" } else { "  Empty directory" };
+        frame.render_widget(Paragraph::new(message).style(theme.muted), list_area);
+        return;
+    }
+
+    let lines: Vec<Line> = compare.rows
+                                  .iter()
+                                  .enumerate()
+                                  .skip(compare.scroll)
+                                  .take(visible_height)
+                                  .map(|(i, row)| {
+                                      let (left_style, right_style) = if i == compare.cursor
+                                      {
+                                          (theme.selected, theme.selected)
+                                      }
+                                      else
+                                      {
+                                          change_styles(row, &theme)
+                                      };
+                                      Line::from(vec![
+                                          Span::styled(compare_cell(row.left.as_ref(), half, &time_format), left_style),
+                                          separator.clone(),
+                                          Span::styled(compare_cell(row.right.as_ref(), half, &time_format), right_style),
+                                          Span::styled(format!(" {}", compare.change_label(row.change)), theme.muted),
+                                      ])
+                                  })
+                                  .collect();
+    frame.render_widget(Paragraph::new(lines), list_area);
+}
+
+/// Render the unified diff of a compared file
+fn render_diff(frame: &mut Frame,
+               app: &mut App)
+{
+    let theme = app.theme.clone();
+    let spinner = app.spinner_char();
+    let area = centered_rect(90, 80, frame.area());
+    frame.render_widget(Clear, area);
+
+    let left_label = snapshot_label(app, app.diff.as_ref().map_or("", |d| d.left_id.as_str()));
+    // Against a local directory the right side has no snapshot
+    let right_label = match app.diff
+    {
+        Some(ref d) if d.right_id.is_empty() => "local".to_string(),
+        Some(ref d) => snapshot_label(app, &d.right_id),
+        None => String::new(),
+    };
+    let diff = match app.diff
+    {
+        Some(ref mut d) => d,
+        None => return,
+    };
+
+    let mut title = format!(" Diff {}  {} → {} ", diff.path, left_label, right_label);
+    if let DiffBody::Text(_) = diff.body
+    {
+        let (removed, added) = diff.counts();
+        title.push_str(&format!("[-{} +{}, hunk {}/{}] ", removed, added, diff.current_hunk(), diff.hunks.len()));
+    }
+    let block = Block::default().title(title)
+                                .borders(Borders::ALL)
+                                .border_style(theme.dialog_border);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    diff.visible_height = inner.height as usize;
+
+    let lines = match diff.body
+    {
+        DiffBody::Loading => vec![Line::styled(format!("  {} Loading both versions...", spinner), theme.warning)],
+        DiffBody::Computing => vec![Line::styled(format!("  {} Comparing...", spinner), theme.warning)],
+        DiffBody::Failed(ref e) => vec![Line::styled(format!("  {}", e.lines().next().unwrap_or_default()), theme.error)],
+        DiffBody::Identical => vec![Line::styled("  The files are identical", theme.muted)],
+        DiffBody::Summary { ref reason, ref left, ref right } =>
+        {
+            let verdict = match (&left.sha256, &right.sha256)
+            {
+                (Some(l), Some(r)) if l == r => "Same content",
+                _ if left.size != right.size => "Sizes differ",
+                (Some(_), Some(_)) => "Same size, different content",
+                _ => "Same size, content not compared",
+            };
+            vec![
+                Line::styled(format!("  {}", reason), theme.warning),
+                Line::from(""),
+                summary_line(&left_label, left, &theme),
+                summary_line(&right_label, right, &theme),
+                Line::from(""),
+                Line::styled(format!("  {}", verdict), theme.emphasis),
+            ]
+        }
+        DiffBody::Text(ref lines) =>
+        {
+            // Line number columns as wide as the largest number
+            let width = lines.iter()
+                             .filter_map(|l| l.old_no.max(l.new_no))
+                             .max()
+                             .unwrap_or(0)
+                             .to_string()
+                             .len();
+            let number = |n: Option<usize>| n.map_or_else(|| " ".repeat(width), |n| format!("{:>width$}", n));
+            lines.iter()
+                 .skip(diff.scroll)
+                 .take(diff.visible_height)
+                 .map(|line| match line.kind
+                 {
+                     LineKind::Hunk => Line::styled(line.text.clone(), theme.label),
+                     kind =>
+                     {
+                         let (marker, style) = match kind
+                         {
+                             LineKind::Removed => ('-', theme.error),
+                             LineKind::Added => ('+', theme.success),
+                             _ => (' ', theme.text),
+                         };
+                         Line::from(vec![
+                             Span::styled(format!("{} {} ", number(line.old_no), number(line.new_no)), theme.muted),
+                             Span::styled(format!("{}{}", marker, line.text), style),
+                         ])
+                     }
+                 })
+                 .collect()
+        }
+    };
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+/// Size and hash of one version of a file that isn't diffed as text
+fn summary_line(label: &str,
+                summary: &Summary,
+                theme: &Theme)
+                -> Line<'static>
+{
+    Line::from(vec![
+        Span::styled(format!("  {:<10}", label), theme.heading),
+        Span::styled(format!("{:>10}  ", summary.formatted_size()), theme.text),
+        Span::styled(format!("sha256 {}", summary.sha256.as_deref().unwrap_or("(not computed)")), theme.muted),
+    ])
+}
+
+/// Short id of a snapshot by its full id
+fn snapshot_label(app: &App,
+                  snapshot_id: &str)
+                  -> String
+{
+    app.snapshots
+       .iter()
+       .find(|s| s.full_id == snapshot_id)
+       .map(|s| s.display_id().to_string())
+       .unwrap_or_else(|| snapshot_id.chars().take(8).collect())
+}
+
+/// Styles of the left and right cell for how an entry changed
+fn change_styles(row: &CompareRow,
+                 theme: &Theme)
+                 -> (Style, Style)
+{
+    match row.change
+    {
+        Change::OnlyLeft => (theme.error, theme.muted),
+        Change::OnlyRight => (theme.muted, theme.success),
+        Change::Size => (theme.warning, theme.warning),
+        Change::Mtime => (theme.label, theme.label),
+        Change::Same if row.is_dir() => (theme.directory, theme.directory),
+        Change::Same => (theme.text, theme.text),
+    }
+}
+
+/// One side of a comparison row: name, size and mtime in `width` columns
+fn compare_cell(node: Option<&FileNode>,
+                width: usize,
+                time_format: &str)
+                -> String
+{
+    let node = match node
+    {
+        Some(n) => n,
+        None => return " ".repeat(width),
+    };
+    let details = format!(" {:>9} {}", node.formatted_size(), node.formatted_mtime(time_format));
+    let name_width = width.saturating_sub(details.chars().count() + 1);
+    fit(&format!(" {}{}", fit(&entry_name(node), name_width), details), width)
+}
+
+/// Cut or pad text to exactly `width` columns
+fn fit(text: &str,
+       width: usize)
+       -> String
+{
+    let mut out: String = text.chars().take(width).collect();
+    let len = out.chars().count();
+    out.extend(std::iter::repeat(' ').take(width - len));
+    out
+}
+
+/// Append the restore-size and raw-data rows for one stats target
+fn stats_lines(lines: &mut Vec<Line>,
+               entry: Option<&StatsEntry>,
+               spinner: char,
+               theme: &Theme)
+{
+    let label_style = theme.label;
+    let row = |label: &str, value: String| {
+        Line::from(vec![
+            Span::styled(format!("  {:<20}", label), label_style),
+            Span::raw(value),
+        ])
+    };
+    let pending = |status: &Option<StatsStatus>| -> Option<Line<'static>> {
+        match status
+        {
+            None => Some(Line::from(Span::styled("  -", theme.muted))),
+            Some(StatsStatus::Loading) => Some(Line::from(Span::styled(
+                format!("  {} Loading...", spinner),
+                theme.warning,
+            ))),
+            Some(StatsStatus::Failed(e)) => Some(Line::from(Span::styled(
+                format!("  {}", e.lines().next().unwrap_or("failed")),
+                theme.error,
+            ))),
+            Some(StatsStatus::Loaded(_)) => None,
+        }
+    };
+
+    let entry = entry.cloned().unwrap_or_default();
+
+    // Restore size: what you'd get back by restoring everything
+    match &entry.restore_size
+    {
+        Some(StatsStatus::Loaded(stats)) =>
+        {
+            lines.push(row("Total size:", stats.formatted_size()));
+            lines.push(row("Files:", stats.total_file_count.to_string()));
+            if stats.snapshots_count > 1
+            {
+                lines.push(row("Snapshots:", stats.snapshots_count.to_string()));
+            }
+        }
+        status => lines.extend(pending(status)),
+    }
+
+    // Raw data: deduplicated (and possibly compressed) blobs in the repository
+    match &entry.raw_data
+    {
+        Some(StatsStatus::Loaded(stats)) =>
+        {
+            lines.push(row("Blobs:", stats.total_blob_count.to_string()));
+            lines.push(row("Stored size:", stats.formatted_size()));
+            if stats.total_uncompressed_size > 0
+            {
+                lines.push(row("Uncompressed size:", stats.formatted_uncompressed_size()));
+            }
+            lines.push(row("Compression ratio:", stats.formatted_compression_ratio()));
+        }
+        status => lines.extend(pending(status)),
+    }
+}
+
 /// Render download directory picker dialog
 fn render_download_dialog(frame: &mut Frame,
                           app: &mut App)
 {
+    let theme = app.theme.clone();
     let area = centered_rect(70, 60, frame.area());
     frame.render_widget(Clear, area);
 
@@ -677,15 +1989,17 @@ fn render_download_dialog(frame: &mut Frame,
     };
 
     // Get source filename for title
-    let source_name = std::path::Path::new(&dialog.source_path)
-        .file_name()
-        .map(|s| s.to_string_lossy().to_string())
-        .unwrap_or_else(|| dialog.source_path.clone());
+    let title = match dialog.mode
+    {
+        DownloadMode::Restore => format!(" Download: {} ", dialog.source_name()),
+        DownloadMode::Dump => format!(" Dump to: {} ", dialog.dump_target_file()),
+        DownloadMode::CompareLocal => format!(" Compare {} with local directory ", dialog.source_path),
+    };
 
     let block = Block::default()
-        .title(format!(" Download: {} ", source_name))
+        .title(title)
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Cyan));
+        .border_style(theme.dialog_border);
 
     frame.render_widget(block, area);
 
@@ -706,69 +2020,98 @@ fn render_download_dialog(frame: &mut Frame,
     .split(inner);
 
     // Render path input
-    render_path_input(frame, dialog, chunks[0]);
+    render_path_input(frame, dialog, chunks[0], &theme);
+    app.mouse_areas.dialog_input = Some(chunks[0]);
 
     // Render directory listing
-    render_dir_listing(frame, dialog, chunks[1]);
+    let listing = render_dir_listing(frame, dialog, chunks[1], &theme);
+    app.mouse_areas.dialog_listing = Some(listing);
 
     // Render buttons
-    render_dialog_buttons(frame, dialog, chunks[2]);
+    app.mouse_areas.dialog_buttons = render_dialog_buttons(frame, dialog, chunks[2], &theme);
 }
 
-/// Render dialog buttons
+/// Render dialog buttons, returning where each button was drawn
 fn render_dialog_buttons(frame: &mut Frame,
                          dialog: &DownloadDialog,
-                         area: Rect)
+                         area: Rect,
+                         theme: &Theme)
+                         -> Vec<(DialogFocus, Rect)>
 {
     let download_focused = dialog.focus == DialogFocus::DownloadButton;
     let cancel_focused = dialog.focus == DialogFocus::CancelButton;
 
     let download_style = if download_focused
     {
-        Style::default().fg(Color::Black).bg(Color::Yellow).add_modifier(Modifier::BOLD)
+        theme.button_focused
     }
     else
     {
-        Style::default().fg(Color::White)
+        theme.button
     };
 
     let cancel_style = if cancel_focused
     {
-        Style::default().fg(Color::Black).bg(Color::Yellow).add_modifier(Modifier::BOLD)
+        theme.button_focused
     }
     else
     {
-        Style::default().fg(Color::White)
+        theme.button
     };
 
-    let buttons = vec![
-        Line::from(""),
-        Line::from(vec![
-            Span::raw("        "),
-            Span::styled(" [ Download ] ", download_style),
-            Span::raw("        "),
-            Span::styled(" [ Cancel ] ", cancel_style),
-            Span::raw("        "),
-        ]),
-        Line::from(""),
+    // First line shows the size warning while a confirmation is pending
+    let warning = match dialog.warning
+    {
+        Some(ref w) => Line::from(Span::styled(w.clone(), theme.warning.add_modifier(Modifier::BOLD))),
+        None => Line::from(""),
+    };
+
+    let gap = "        ";
+    let download = if dialog.mode == DownloadMode::CompareLocal { " [ Compare ] " } else { " [ Download ] " };
+    let cancel = " [ Cancel ] ";
+    let button_line = Line::from(vec![
+        Span::raw(gap),
+        Span::styled(download, download_style),
+        Span::raw(gap),
+        Span::styled(cancel, cancel_style),
+        Span::raw(gap),
+    ]);
+
+    // Button positions on the centered line
+    let left = area.x + area.width.saturating_sub(button_line.width() as u16) / 2;
+    let button_area = |offset: usize, label: &str| Rect {
+        x: left + offset as u16,
+        y: area.y + 1,
+        width: label.len() as u16,
+        height: 1,
+    }.intersection(area);
+    let areas = vec![
+        (DialogFocus::DownloadButton, button_area(gap.len(), download)),
+        (DialogFocus::CancelButton, button_area(gap.len() * 2 + download.len(), cancel)),
     ];
 
+    let buttons = vec![warning, button_line, Line::from("")];
+
     let paragraph = Paragraph::new(buttons).alignment(ratatui::layout::Alignment::Center);
     frame.render_widget(paragraph, area);
+
+    areas
 }
 
 /// Render the path input box
 fn render_path_input(frame: &mut Frame,
                      dialog: &DownloadDialog,
-                     area: Rect)
+                     area: Rect,
+                     theme: &Theme)
 {
     let is_focused = dialog.focus == DialogFocus::PathPicker;
-    let border_color = if is_focused { Color::Yellow } else { Color::DarkGray };
+    let border_style = if is_focused { theme.input_focused } else { theme.border };
 
+    let title = if dialog.mode == DownloadMode::CompareLocal { " Local Directory " } else { " Target Directory " };
     let block = Block::default()
-        .title(" Target Directory ")
+        .title(title)
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(border_color));
+        .border_style(border_style);
 
     let input_area = Rect {
         x: area.x + 1,
@@ -808,7 +2151,7 @@ fn render_path_input(frame: &mut Frame,
         (&text[start..end], cursor_pos - start)
     };
 
-    let paragraph = Paragraph::new(visible_text).style(Style::default().fg(Color::White));
+    let paragraph = Paragraph::new(visible_text).style(theme.input);
     frame.render_widget(paragraph, input_area);
 
     // Show cursor only when path picker is focused
@@ -818,28 +2161,31 @@ fn render_path_input(frame: &mut Frame,
     }
 }
 
-/// Render the directory listing
+/// Render the directory listing, returning its rows for mouse clicks
 fn render_dir_listing(frame: &mut Frame,
                       dialog: &mut DownloadDialog,
-                      area: Rect)
+                      area: Rect,
+                      theme: &Theme)
+                      -> ListArea
 {
     let is_focused = dialog.focus == DialogFocus::PathPicker;
-    let border_color = if is_focused { Color::Yellow } else { Color::DarkGray };
+    let border_style = if is_focused { theme.input_focused } else { theme.border };
 
     let block = Block::default()
         .title(" Directories ")
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(border_color));
+        .border_style(border_style);
 
     let inner_height = area.height.saturating_sub(2) as usize;
     dialog.adjust_scroll(inner_height);
+    let listing = ListArea::scrolled(area, dialog.scroll, inner_height, dialog.entries.len());
 
     if dialog.entries.is_empty()
     {
         let paragraph = Paragraph::new("  (no subdirectories)").block(block)
-            .style(Style::default().fg(Color::DarkGray));
+            .style(theme.muted);
         frame.render_widget(paragraph, area);
-        return;
+        return listing;
     }
 
     let items: Vec<ListItem> = dialog
@@ -855,15 +2201,15 @@ fn render_dir_listing(frame: &mut Frame,
 
             let style = if is_selected && is_focused
             {
-                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+                theme.selected
             }
             else if is_selected
             {
-                Style::default().fg(Color::White)
+                theme.selected_inactive
             }
             else
             {
-                Style::default().fg(Color::Blue)
+                theme.directory
             };
 
             ListItem::new(name).style(style)
@@ -872,6 +2218,8 @@ fn render_dir_listing(frame: &mut Frame,
 
     let list = List::new(items).block(block);
     frame.render_widget(list, area);
+
+    listing
 }
 
 /// Create a centered rect with percentage of parent